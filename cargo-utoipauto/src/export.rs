@@ -0,0 +1,166 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use utoipauto_core::manifest::Manifest;
+
+use crate::yaml::to_yaml;
+
+/// Name of the temporary example printing the documentation
+const SHIM_NAME: &str = "utoipauto_export";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+}
+
+impl Format {
+    pub fn from_arg(arg: &str) -> Result<Format, String> {
+        match arg {
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            _ => Err(format!("Unknown format `{}`, expected json or yaml", arg)),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+        }
+    }
+}
+
+pub struct Export {
+    pub doc: String,
+    pub format: Format,
+    pub output: Option<PathBuf>,
+    pub manifest_path: PathBuf,
+}
+
+impl Export {
+    /// Build and run a temporary example of the package printing the documentation, then write it to the output.
+    ///
+    /// An example is used rather than a separate crate so the package is compiled from its own workspace,
+    /// where the relative paths given to `#[utoipauto]` are valid.
+    pub fn run(&self) -> Result<(), String> {
+        let manifest = Manifest::from_path(&self.manifest_path)
+            .map_err(|e| format!("Failed to read {}: {}", self.manifest_path.display(), e))?;
+        let lib_name = manifest
+            .lib_name()
+            .ok_or_else(|| format!("No [package] name found in {}", self.manifest_path.display()))?;
+        let package_dir = match self.manifest_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        let shim = Shim::create(package_dir, &shim_source(&lib_name, &self.doc))?;
+        let cargo = env::var("CARGO").unwrap_or("cargo".to_string());
+        let output = Command::new(cargo)
+            .arg("run")
+            .arg("--quiet")
+            .arg("--manifest-path")
+            .arg(&self.manifest_path)
+            .arg("--example")
+            .arg(SHIM_NAME)
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(|e| format!("Failed to run cargo: {}", e))?;
+        drop(shim);
+        if !output.status.success() {
+            return Err(format!("Failed to build the documentation of `{}`", self.doc));
+        }
+
+        let json = String::from_utf8(output.stdout).map_err(|_| "The documentation is not valid UTF-8".to_string())?;
+        let spec = match self.format {
+            Format::Json => json,
+            Format::Yaml => {
+                let value = serde_json::from_str(&json).map_err(|e| format!("Invalid documentation: {}", e))?;
+                to_yaml(&value)
+            }
+        };
+
+        let output_path = self
+            .output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(format!("openapi.{}", self.format.extension())));
+        fs::write(&output_path, spec).map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))?;
+        eprintln!("OpenApi documentation written to {}", output_path.display());
+        Ok(())
+    }
+}
+
+/// Source of the example printing the documentation as json
+fn shim_source(lib_name: &str, doc: &str) -> String {
+    let doc = match doc.strip_prefix("crate::") {
+        Some(doc) => format!("{}::{}", lib_name, doc),
+        None if doc.starts_with(&format!("{}::", lib_name)) => doc.to_string(),
+        None => format!("{}::{}", lib_name, doc),
+    };
+    format!(
+        "// Generated by cargo-utoipauto, removed once the documentation is exported
+fn main() {{
+    let openapi = <{} as utoipa::OpenApi>::openapi();
+    println!(\"{{}}\", openapi.to_pretty_json().expect(\"Failed to serialize the documentation\"));
+}}
+",
+        doc
+    )
+}
+
+/// The temporary example file, removed when dropped
+struct Shim {
+    path: PathBuf,
+}
+
+impl Shim {
+    fn create(package_dir: &Path, source: &str) -> Result<Shim, String> {
+        let examples_dir = package_dir.join("examples");
+        let path = examples_dir.join(format!("{}.rs", SHIM_NAME));
+        if path.exists() {
+            return Err(format!("{} already exists, remove it first", path.display()));
+        }
+        fs::create_dir_all(&examples_dir).map_err(|e| format!("Failed to create {}: {}", examples_dir.display(), e))?;
+        fs::write(&path, source).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(Shim { path })
+    }
+}
+
+impl Drop for Shim {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        // Only removes the examples directory if we left it empty
+        if let Some(dir) = self.path.parent() {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    #[test]
+    fn test_shim_source() {
+        assert!(shim_source("my_api", "crate::docs::ApiDoc").contains("<my_api::docs::ApiDoc as utoipa::OpenApi>"));
+        assert!(shim_source("my_api", "my_api::ApiDoc").contains("<my_api::ApiDoc as utoipa::OpenApi>"));
+        assert!(shim_source("my_api", "ApiDoc").contains("<my_api::ApiDoc as utoipa::OpenApi>"));
+    }
+
+    #[test]
+    fn test_shim_is_removed() {
+        let package_dir = TempDir::new("test-shim");
+
+        let shim = Shim::create(&package_dir, "fn main() {}").unwrap();
+        let path = shim.path.clone();
+        assert!(path.exists());
+        assert!(Shim::create(&package_dir, "fn main() {}").is_err());
+
+        drop(shim);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,42 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use utoipauto_core::{codegen::write_discovered, token_utils::Parameters};
+
+pub struct Generate {
+    /// The attributes of `#[utoipauto(...)]`, e.g. `paths = "./src", prefer_reexports = true`
+    pub attributes: String,
+    pub struct_name: String,
+    pub output: PathBuf,
+    pub manifest_path: PathBuf,
+}
+
+impl Generate {
+    /// Write the discovered items to the output, from the package directory where the relative paths given in the
+    /// attributes are valid
+    pub fn run(&self) -> Result<(), String> {
+        let current_dir = env::current_dir().map_err(|e| format!("Failed to read the current directory: {}", e))?;
+        let output = current_dir.join(&self.output);
+        let package_dir = match self.manifest_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        env::set_current_dir(package_dir).map_err(|e| format!("Failed to open {}: {}", package_dir.display(), e))?;
+
+        let params = Parameters::from_attributes(&self.attributes).map_err(|e| e.to_string())?;
+        write_discovered(&self.struct_name, params, &output)
+            .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+        // The generated source is a single line per item, format it if rustfmt is available
+        let _ = Command::new("rustfmt")
+            .arg("--edition")
+            .arg("2021")
+            .arg(&output)
+            .stderr(Stdio::null())
+            .status();
+        eprintln!("Discovered items written to {}", output.display());
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use std::{env, path::PathBuf, process};
+
+use export::{Export, Format};
+use generate::Generate;
+
+mod export;
+mod generate;
+mod yaml;
+
+#[cfg(test)]
+mod test_utils;
+
+const USAGE: &str =
+    "Usage: cargo utoipauto export --doc <PATH> [--format json|yaml] [--output <FILE>] [--manifest-path <PATH>]
+       cargo utoipauto generate [--attributes <ATTRIBUTES>] [--struct <NAME>] [--output <FILE>] [--manifest-path <PATH>]
+
+Commands:
+    export      Write the OpenApi documentation of the doc struct to a file
+    generate    Write a source file with a struct deriving OpenApi with the discovered items
+
+Options:
+    --doc <PATH>                Path of the doc struct deriving OpenApi in the library, e.g. crate::ApiDoc
+    --format <FORMAT>           Output format, json or yaml [default: json]
+    --output <FILE>             Output file [default: openapi.json or openapi.yaml, src/discovered.rs]
+    --attributes <ATTRIBUTES>   Attributes of #[utoipauto(...)], e.g. 'paths = \"./src\"' [default: none]
+    --struct <NAME>             Name of the generated struct [default: DiscoveredApi]
+    --manifest-path <PATH>      Path to the Cargo.toml of the package [default: Cargo.toml]";
+
+enum Subcommand {
+    Export(Export),
+    Generate(Generate),
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // When invoked as `cargo utoipauto`, cargo passes the subcommand name as first argument
+    if args.first().is_some_and(|arg| arg == "utoipauto") {
+        args.remove(0);
+    }
+
+    let subcommand = match parse_args(args) {
+        Ok(subcommand) => subcommand,
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+    let result = match subcommand {
+        Subcommand::Export(export) => export.run(),
+        Subcommand::Generate(generate) => generate.run(),
+    };
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Subcommand, String> {
+    let mut args = args.into_iter();
+    match args.next().as_deref() {
+        Some("export") => parse_export_args(args).map(Subcommand::Export),
+        Some("generate") => parse_generate_args(args).map(Subcommand::Generate),
+        Some(command) => Err(format!("Unknown command `{}`", command)),
+        None => Err("No command specified".to_string()),
+    }
+}
+
+fn parse_export_args(mut args: impl Iterator<Item = String>) -> Result<Export, String> {
+    let mut doc = None;
+    let mut format = Format::Json;
+    let mut output = None;
+    let mut manifest_path = PathBuf::from("Cargo.toml");
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("Missing value for `{}`", arg));
+        match arg.as_str() {
+            "--doc" => doc = Some(value()?),
+            "--format" => format = Format::from_arg(&value()?)?,
+            "--output" => output = Some(PathBuf::from(value()?)),
+            "--manifest-path" => manifest_path = PathBuf::from(value()?),
+            _ => return Err(format!("Unknown argument `{}`", arg)),
+        }
+    }
+
+    Ok(Export {
+        doc: doc.ok_or("Missing `--doc`")?,
+        format,
+        output,
+        manifest_path,
+    })
+}
+
+fn parse_generate_args(mut args: impl Iterator<Item = String>) -> Result<Generate, String> {
+    let mut generate = Generate {
+        attributes: String::new(),
+        struct_name: "DiscoveredApi".to_string(),
+        output: PathBuf::from("src/discovered.rs"),
+        manifest_path: PathBuf::from("Cargo.toml"),
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("Missing value for `{}`", arg));
+        match arg.as_str() {
+            "--attributes" => generate.attributes = value()?,
+            "--struct" => generate.struct_name = value()?,
+            "--output" => generate.output = PathBuf::from(value()?),
+            "--manifest-path" => generate.manifest_path = PathBuf::from(value()?),
+            _ => return Err(format!("Unknown argument `{}`", arg)),
+        }
+    }
+    Ok(generate)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let Ok(Subcommand::Export(export)) =
+            parse_args(args("export --doc crate::ApiDoc --format yaml --output spec.yaml"))
+        else {
+            panic!("Expected the export command");
+        };
+        assert_eq!(export.doc, "crate::ApiDoc");
+        assert_eq!(export.format, Format::Yaml);
+        assert_eq!(export.output, Some(PathBuf::from("spec.yaml")));
+        assert_eq!(export.manifest_path, PathBuf::from("Cargo.toml"));
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(parse_args(args("")).is_err());
+        assert!(parse_args(args("import --doc crate::ApiDoc")).is_err());
+        assert!(parse_args(args("export")).is_err());
+        assert!(parse_args(args("export --doc")).is_err());
+        assert!(parse_args(args("export --doc crate::ApiDoc --format xml")).is_err());
+        assert!(parse_args(args("generate --struct")).is_err());
+        assert!(parse_args(args("generate --doc crate::ApiDoc")).is_err());
+    }
+
+    #[test]
+    fn test_parse_generate_args() {
+        let Ok(Subcommand::Generate(generate)) = parse_args(args("generate --struct Api --output src/api.rs")) else {
+            panic!("Expected the generate command");
+        };
+        assert_eq!(generate.attributes, "");
+        assert_eq!(generate.struct_name, "Api");
+        assert_eq!(generate.output, PathBuf::from("src/api.rs"));
+        assert_eq!(generate.manifest_path, PathBuf::from("Cargo.toml"));
+    }
+}
@@ -0,0 +1,30 @@
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A directory of the system temp dir, unique to the test process, removed with everything in it when dropped,
+/// even if the test fails
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// `cargo-utoipauto-{name}-{pid}`, created empty
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("cargo-utoipauto-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,146 @@
+use serde_json::Value;
+
+/// Convert a json value into a block style yaml document
+///
+/// Strings are always written as double-quoted yaml strings, whose escaping is the same as json.
+pub fn to_yaml(value: &Value) -> String {
+    let mut out = String::new();
+    write_block(&mut out, value, 0);
+    out
+}
+
+fn write_block(out: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                out.push_str(&" ".repeat(indent));
+                write_entry(out, key, value, indent);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for item in items {
+                out.push_str(&" ".repeat(indent));
+                out.push('-');
+                write_item(out, item, indent + 2);
+            }
+        }
+        _ => {
+            out.push_str(&" ".repeat(indent));
+            out.push_str(&scalar(value));
+            out.push('\n');
+        }
+    }
+}
+
+fn write_entry(out: &mut String, key: &str, value: &Value, indent: usize) {
+    out.push_str(&yaml_key(key));
+    out.push(':');
+    if is_block(value) {
+        out.push('\n');
+        write_block(out, value, indent + 2);
+    } else {
+        out.push(' ');
+        out.push_str(&scalar(value));
+        out.push('\n');
+    }
+}
+
+/// Write an array item, the `-` has already been written
+fn write_item(out: &mut String, item: &Value, indent: usize) {
+    if let Value::Array(items) = item {
+        if !items.is_empty() {
+            out.push('\n');
+            write_block(out, item, indent);
+            return;
+        }
+    }
+    out.push(' ');
+    match item {
+        Value::Object(map) if !map.is_empty() => {
+            for (i, (key, value)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(&" ".repeat(indent));
+                }
+                write_entry(out, key, value, indent);
+            }
+        }
+        _ => {
+            out.push_str(&scalar(item));
+            out.push('\n');
+        }
+    }
+}
+
+fn is_block(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => !map.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        _ => false,
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Object(_) => "{}".to_string(),
+        Value::Array(_) => "[]".to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// Keys are left plain when they can't be mistaken for another yaml value
+fn yaml_key(key: &str) -> String {
+    let is_plain = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '/')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '/' | '.' | '{' | '}'))
+        && !matches!(key, "true" | "false" | "null" | "yes" | "no" | "on" | "off");
+    if is_plain {
+        key.to_string()
+    } else {
+        Value::String(key.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_yaml() {
+        let value = serde_json::json!({
+            "openapi": "3.1.0",
+            "paths": {
+                "/users/{id}": {
+                    "get": {
+                        "parameters": [{ "name": "id", "required": true }],
+                        "responses": { "200": { "description": "Ok" } },
+                        "tags": []
+                    }
+                }
+            },
+            "components": {}
+        });
+        assert_eq!(
+            to_yaml(&value),
+            r#"openapi: "3.1.0"
+paths:
+  /users/{id}:
+    get:
+      parameters:
+        - name: "id"
+          required: true
+      responses:
+        "200":
+          description: "Ok"
+      tags: []
+components: {}
+"#
+        );
+    }
+
+    #[test]
+    fn test_to_yaml_nested_arrays() {
+        let value = serde_json::json!({ "security": [["a", "b"], []] });
+        assert_eq!(to_yaml(&value), "security:\n  -\n    - \"a\"\n    - \"b\"\n  - []\n");
+    }
+}
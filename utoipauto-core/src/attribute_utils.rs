@@ -1,12 +1,426 @@
-use proc_macro2::TokenStream;
-use syn::{punctuated::Punctuated, Attribute, Meta, Token};
+use proc_macro2::{Group, TokenStream, TokenTree};
+use quote::quote;
+use syn::{punctuated::Punctuated, Attribute, Ident, Meta, Token};
+
+use crate::{
+    diagnostics::{self, emit_warnings},
+    discover::Discovered,
+    path_utils::{canonicalize, path_to_string, starts_with},
+    spec::Spec,
+    string_utils::{discover_all, discover_schemas},
+    token_utils::{cfg_variants, with_cfg, ComposedDoc, Hierarchy, Parameters},
+};
+
+/// Expand the `include_schemas!("...")` invocations inside the #[openapi(...)] attribute
+/// into the schemas discovered in the given paths, before the openapi macro sees them
+pub fn expand_include_schemas(macro_attibutes: &mut [Attribute], params: &Parameters) -> syn::Result<()> {
+    for attr in macro_attibutes {
+        if !attr.path().is_ident("openapi") {
+            continue;
+        }
+        if let Meta::List(meta_list) = &mut attr.meta {
+            meta_list.tokens = expand_include_schemas_tokens(meta_list.tokens.clone(), params)?;
+        }
+    }
+    Ok(())
+}
+
+fn expand_include_schemas_tokens(tokens: TokenStream, params: &Parameters) -> syn::Result<TokenStream> {
+    let mut output: Vec<TokenTree> = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) if ident == "include_schemas" => {
+                let is_bang = matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == '!');
+                if !is_bang {
+                    output.push(TokenTree::Ident(ident));
+                    continue;
+                }
+                tokens.next();
+                let Some(TokenTree::Group(group)) = tokens.next() else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "utoipauto: Expected include_schemas!(\"PATHS\")",
+                    ));
+                };
+                let paths: syn::LitStr = syn::parse2(group.stream())
+                    .map_err(|_| syn::Error::new(group.span(), "utoipauto: Expected include_schemas!(\"PATHS\")"))?;
+
+                // Remove the `utoipauto::` prefix of the invocation
+                while matches!(output.last(), Some(TokenTree::Punct(punct)) if punct.as_char() == ':') {
+                    output.pop();
+                    if matches!(output.last(), Some(TokenTree::Ident(_))) {
+                        output.pop();
+                    }
+                }
+
+                let schemas = discover_schemas(&paths.value(), params)?;
+                if schemas.is_empty() {
+                    // Don't leave a dangling coma behind
+                    if matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == ',') {
+                        tokens.next();
+                    }
+                }
+                output.extend(schemas);
+            }
+            TokenTree::Group(group) => {
+                let mut expanded = Group::new(
+                    group.delimiter(),
+                    expand_include_schemas_tokens(group.stream(), params)?,
+                );
+                expanded.set_span(group.span());
+                output.push(TokenTree::Group(expanded));
+            }
+            token => output.push(token),
+        }
+    }
+    Ok(output.into_iter().collect())
+}
+
+/// Rewrite the `self::` and `super::` paths inside the #[openapi(...)] attribute relative to the crate,
+/// so that they compare equal to the discovered paths of the same items
+pub fn canonicalize_listed_paths(macro_attibutes: &mut [Attribute], doc_module: &syn::Path) {
+    for attr in macro_attibutes {
+        if !attr.path().is_ident("openapi") {
+            continue;
+        }
+        if let Meta::List(meta_list) = &mut attr.meta {
+            meta_list.tokens = canonicalize_tokens(meta_list.tokens.clone(), doc_module);
+        }
+    }
+}
+
+fn canonicalize_tokens(tokens: TokenStream, doc_module: &syn::Path) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut output: Vec<TokenTree> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        // The leading `self::` and `super::` of a path, but not the segments in the middle of one
+        let mut prefix: Vec<Ident> = Vec::new();
+        if !matches!(output.last(), Some(TokenTree::Punct(punct)) if punct.as_char() == ':') {
+            while let [TokenTree::Ident(ident), TokenTree::Punct(first), TokenTree::Punct(second), ..] =
+                &tokens[i + 3 * prefix.len()..]
+            {
+                if (ident != "self" && ident != "super") || first.as_char() != ':' || second.as_char() != ':' {
+                    break;
+                }
+                prefix.push(ident.clone());
+            }
+        }
+        if !prefix.is_empty() {
+            let relative: syn::Path = syn::parse_quote!(#(#prefix)::* :: item);
+            let canonical = canonicalize(&relative, doc_module);
+            // Unchanged if it goes past the crate root
+            if canonical.segments[0].ident != "self" && canonical.segments[0].ident != "super" {
+                let segments = canonical.segments.iter().take(canonical.segments.len() - 1);
+                output.extend(quote::quote!(#(#segments ::)*));
+                i += 3 * prefix.len();
+                continue;
+            }
+        }
+        match &tokens[i] {
+            TokenTree::Group(group) => {
+                let mut canonical = Group::new(group.delimiter(), canonicalize_tokens(group.stream(), doc_module));
+                canonical.set_span(group.span());
+                output.push(TokenTree::Group(canonical));
+            }
+            token => output.push(token.clone()),
+        }
+        i += 1;
+    }
+    output.into_iter().collect()
+}
+
+/// The paths, schemas and responses listed by hand in the #[openapi(...)] attribute
+pub fn listed_paths(macro_attibutes: &[Attribute]) -> Vec<String> {
+    let mut listed = Vec::new();
+    for attr in macro_attibutes {
+        if !attr.path().is_ident("openapi") {
+            continue;
+        }
+        let Ok(nested) = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) else {
+            continue;
+        };
+        // An invalid `components(...)` is reported when the attribute is updated
+        for tokens in [
+            extract_paths(&nested),
+            extract_components(&nested, "schemas").unwrap_or_default(),
+            extract_components(&nested, "responses").unwrap_or_default(),
+        ] {
+            // Skip the entries that aren't plain paths, e.g. macro calls, they can't be discovered items anyway
+            let entries = split_entries(tokens);
+            let paths = entries
+                .into_iter()
+                .filter_map(|entry| syn::parse2::<syn::Path>(entry).ok());
+            listed.extend(paths.map(|path| path_to_string(&path)));
+        }
+    }
+    listed
+}
+
+/// The entries of a comma separated list, the commas between the generic arguments of an entry included
+fn split_entries(tokens: TokenStream) -> Vec<TokenStream> {
+    let mut entries = vec![];
+    let mut entry: Vec<TokenTree> = vec![];
+    let mut depth = 0usize;
+    for token in tokens {
+        if let TokenTree::Punct(punct) = &token {
+            match punct.as_char() {
+                '<' => depth += 1,
+                '>' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    entries.push(entry.drain(..).collect());
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        entry.push(token);
+    }
+    if !entry.is_empty() {
+        entries.push(entry.into_iter().collect());
+    }
+    entries
+}
+
+/// The names of the modifiers listed by hand in the #[openapi(...)] attribute, e.g. `SecurityAddon` for
+/// `modifiers(&SecurityAddon)` or `modifiers(&addons::SecurityAddon)`
+pub fn listed_modifiers(macro_attibutes: &[Attribute]) -> Vec<String> {
+    let mut listed = Vec::new();
+    for attr in macro_attibutes {
+        if !attr.path().is_ident("openapi") {
+            continue;
+        }
+        let Ok(nested) = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) else {
+            continue;
+        };
+        let tokens: Vec<TokenTree> = extract_modifiers(&nested).into_iter().collect();
+        // The last identifier of each modifier, the groups of e.g. `&Addon { .. }` being skipped
+        let modifiers = tokens.split(|token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ','));
+        listed.extend(modifiers.filter_map(|modifier| {
+            modifier.iter().rev().find_map(|token| match token {
+                TokenTree::Ident(ident) => Some(ident.to_string()),
+                _ => None,
+            })
+        }));
+    }
+    listed
+}
+
+/// utoipa only accepts identifiers in `modifiers(&SecurityAddon)`, so every discovered modifier
+/// is imported next to the OpenApi struct under an alias.
+///
+/// Return the `use` items and the `&Alias` list to put into `modifiers(...)`
+pub fn build_modifier_aliases(struct_ident: &Ident, uto_modifiers: &[syn::Path]) -> (TokenStream, TokenStream) {
+    let aliases: Vec<Ident> = (0..uto_modifiers.len())
+        .map(|i| quote::format_ident!("__{}Modifier{}", struct_ident, i))
+        .collect();
+
+    (
+        quote::quote!(#(use #uto_modifiers as #aliases;)*),
+        quote::quote!(#(&#aliases),*),
+    )
+}
+
+/// Build a modifier merging or nesting the composed docs into the OpenApi struct.
+///
+/// Return the modifier items and the path of the modifier
+pub fn build_compose_modifier(struct_ident: &Ident, compose: &[ComposedDoc]) -> (TokenStream, syn::Path) {
+    let modifier = quote::format_ident!("__{}Compose", struct_ident);
+    let statements = compose.iter().map(|composed| {
+        let doc = &composed.doc;
+        match &composed.nest {
+            Some(nest) => quote::quote! {
+                *openapi = std::mem::take(openapi).nest(#nest, <#doc as utoipa::OpenApi>::openapi());
+            },
+            None => quote::quote! {
+                openapi.merge(<#doc as utoipa::OpenApi>::openapi());
+            },
+        }
+    });
+    (
+        quote::quote! {
+            #[allow(non_camel_case_types)]
+            struct #modifier;
+            impl utoipa::Modify for #modifier {
+                fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+                    #(#statements)*
+                }
+            }
+        },
+        syn::parse_quote!(self::#modifier),
+    )
+}
+
+/// The doc comment listing the discovered items, one section per non-empty list
+fn build_generated_doc(lists: &[(&str, &Vec<syn::Path>)]) -> Vec<Attribute> {
+    let mut lines = vec![String::new(), "Discovered by utoipauto :".to_string()];
+    for (title, items) in lists.iter().filter(|(_, items)| !items.is_empty()) {
+        lines.push(String::new());
+        lines.push(format!("{} :", title));
+        lines.extend(items.iter().map(|item| format!("- `{}`", path_to_string(item))));
+    }
+    lines
+        .iter()
+        .map(|line| {
+            let line = format!(" {}", line);
+            syn::parse_quote!(#[doc = #line])
+        })
+        .collect()
+}
+
+/// Move the discovered handlers, schemas and responses of each module of the doc module to their own doc,
+/// named after the doc struct and the module, e.g. `ApiDocUsers` for `crate::users` when `ApiDoc` is in `crate`.
+///
+/// The items of other crates get a doc per crate, and the other ones stay in the doc struct.
+/// Return the docs, and how to compose them into the doc struct.
+pub fn split_hierarchy(
+    openapi_macro: &syn::ItemStruct,
+    hierarchy: Hierarchy,
+    doc_module: &syn::Path,
+    items: [&mut Vec<syn::Path>; 3],
+) -> syn::Result<(TokenStream, Vec<ComposedDoc>)> {
+    let mut modules: Vec<(String, [Vec<syn::Path>; 3])> = vec![];
+    for (kind, items) in items.into_iter().enumerate() {
+        items.retain(|item| {
+            let Some(module) = hierarchy_module(item, doc_module) else {
+                return true;
+            };
+            match modules.iter_mut().find(|(name, _)| *name == module) {
+                Some((_, module_items)) => module_items[kind].push(item.clone()),
+                None => {
+                    let mut module_items: [Vec<syn::Path>; 3] = Default::default();
+                    module_items[kind].push(item.clone());
+                    modules.push((module, module_items));
+                }
+            }
+            false
+        });
+    }
+
+    let mut docs = TokenStream::new();
+    let mut composed = vec![];
+    for (module, items) in modules {
+        let ident = quote::format_ident!("{}{}", openapi_macro.ident, upper_camel_case(&module));
+        let doc = format!("The documentation of the items of `{}`, generated by utoipauto", module);
+        let vis = &openapi_macro.vis;
+        docs.extend(build_items_doc(&ident, &items, quote!(#[doc = #doc] #vis))?);
+        composed.push(ComposedDoc {
+            nest: (hierarchy == Hierarchy::Nest).then(|| format!("/{}", module)),
+            doc: syn::parse_quote!(self::#ident),
+        });
+    }
+    Ok((docs, composed))
+}
+
+/// How to compose the discovered docs, nested under the name of their module with `Hierarchy::Nest`, e.g. `/users` for
+/// `crate::api::users::UsersDoc`
+fn compose_sub_docs(hierarchy: Hierarchy, sub_docs: Vec<syn::Path>) -> Vec<ComposedDoc> {
+    sub_docs
+        .into_iter()
+        .map(|doc| ComposedDoc {
+            nest: match hierarchy {
+                Hierarchy::Merge => None,
+                Hierarchy::Nest => doc
+                    .segments
+                    .iter()
+                    .rev()
+                    .nth(1)
+                    .map(|module| format!("/{}", module.ident)),
+            },
+            doc,
+        })
+        .collect()
+}
+
+/// Move the discovered handlers, schemas and responses to hidden docs of at most `chunk_size` items of each kind,
+/// merged into the doc struct, so that no derive has to process thousands of items at once.
+///
+/// Return the docs, and how to compose them into the doc struct.
+pub fn split_chunks(
+    openapi_macro: &syn::ItemStruct,
+    chunk_size: usize,
+    items: [&mut Vec<syn::Path>; 3],
+) -> syn::Result<(TokenStream, Vec<ComposedDoc>)> {
+    if items.iter().all(|items| items.len() <= chunk_size) {
+        return Ok((TokenStream::new(), vec![]));
+    }
+    let mut chunks: Vec<[Vec<syn::Path>; 3]> = vec![];
+    for (kind, items) in items.into_iter().enumerate() {
+        for (idx, chunk) in std::mem::take(items).chunks(chunk_size).enumerate() {
+            if chunks.len() <= idx {
+                chunks.push(Default::default());
+            }
+            chunks[idx][kind] = chunk.to_vec();
+        }
+    }
+
+    let mut docs = TokenStream::new();
+    let mut composed = vec![];
+    for (idx, items) in chunks.iter().enumerate() {
+        let ident = quote::format_ident!("__{}Chunk{}", openapi_macro.ident, idx);
+        docs.extend(build_items_doc(&ident, items, quote!(#[allow(non_camel_case_types)]))?);
+        composed.push(ComposedDoc {
+            nest: None,
+            doc: syn::parse_quote!(self::#ident),
+        });
+    }
+    Ok((docs, composed))
+}
+
+/// A struct deriving OpenApi with the given handlers, schemas and responses, `prefix` holds its other attributes and
+/// its visibility
+fn build_items_doc(
+    ident: &Ident,
+    [fns, models, responses]: &[Vec<syn::Path>; 3],
+    prefix: TokenStream,
+) -> syn::Result<TokenStream> {
+    let empty = TokenStream::new();
+    let openapi_attribute = build_new_openapi_attributes(
+        Punctuated::new(),
+        &quote!(#(#fns),*),
+        &quote!(#(#models),*),
+        &quote!(#(#responses),*),
+        &empty,
+    )?;
+    Ok(quote! {
+        #[derive(utoipa::OpenApi)]
+        #openapi_attribute
+        #prefix struct #ident;
+    })
+}
+
+/// The module of the doc module the item is in, or the crate for the items of other crates
+fn hierarchy_module(item: &syn::Path, doc_module: &syn::Path) -> Option<String> {
+    let segments: Vec<String> = item.segments.iter().map(|segment| segment.ident.to_string()).collect();
+    if segments[0] != "crate" {
+        return (segments.len() > 1).then(|| segments[0].clone());
+    }
+    let depth = doc_module.segments.len();
+    // The items of the doc module itself stay in the doc struct
+    (starts_with(item, doc_module) && segments.len() > depth + 1).then(|| segments[depth].clone())
+}
+
+fn upper_camel_case(name: &str) -> String {
+    name.trim_start_matches("r#")
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
 
 pub fn update_openapi_macro_attributes(
     macro_attibutes: &mut Vec<Attribute>,
     uto_paths: &TokenStream,
     uto_models: &TokenStream,
     uto_responses: &TokenStream,
-) {
+    uto_modifiers: &TokenStream,
+) -> syn::Result<()> {
     let mut is_ok = false;
     for attr in macro_attibutes {
         if !attr.path().is_ident("openapi") {
@@ -16,21 +430,36 @@ pub fn update_openapi_macro_attributes(
         match &attr.meta {
             // #[openapi]
             Meta::Path(_path) => {
-                *attr = build_new_openapi_attributes(Punctuated::new(), uto_paths, uto_models, uto_responses);
+                *attr = build_new_openapi_attributes(
+                    Punctuated::new(),
+                    uto_paths,
+                    uto_models,
+                    uto_responses,
+                    uto_modifiers,
+                )?;
             }
             // #[openapi()] or #[openapi(attribute(...))]
             Meta::List(meta_list) => {
                 let nested = meta_list
                     .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
-                    .expect("Expected a list of attributes inside #[openapi(...)]!");
-                *attr = build_new_openapi_attributes(nested, uto_paths, uto_models, uto_responses);
+                    .map_err(|e| {
+                        diagnostics::error(format!("Expected a list of attributes inside #[openapi(...)]: {}", e))
+                    })?;
+                *attr = build_new_openapi_attributes(nested, uto_paths, uto_models, uto_responses, uto_modifiers)?;
             }
             // This would be #[openapi = "foo"], which is not valid
-            Meta::NameValue(_) => panic!("Expected #[openapi(...)], but found #[openapi = value]!"),
+            Meta::NameValue(_) => {
+                return Err(diagnostics::error(
+                    "Expected #[openapi(...)], but found #[openapi = value]",
+                ))
+            }
         }
     }
-    if !is_ok {
-        panic!("No utoipa::openapi Macro found !");
+    match is_ok {
+        true => Ok(()),
+        false => Err(diagnostics::error(
+            "No #[openapi(...)] attribute found under #[utoipauto]",
+        )),
     }
 }
 
@@ -40,10 +469,12 @@ pub fn build_new_openapi_attributes(
     uto_paths: &TokenStream,
     uto_models: &TokenStream,
     uto_responses: &TokenStream,
-) -> Attribute {
+    uto_modifiers: &TokenStream,
+) -> syn::Result<Attribute> {
     let paths = extract_paths(&nested_attributes);
-    let schemas = extract_components(&nested_attributes, "schemas");
-    let responses = extract_components(&nested_attributes, "responses");
+    let schemas = extract_components(&nested_attributes, "schemas")?;
+    let responses = extract_components(&nested_attributes, "responses")?;
+    let modifiers = extract_modifiers(&nested_attributes);
     let remaining_nested_attributes = remove_paths_and_components(nested_attributes);
 
     let uto_paths = match uto_paths.is_empty() {
@@ -58,12 +489,46 @@ pub fn build_new_openapi_attributes(
         true => TokenStream::new(),
         false => quote::quote!(#uto_responses,),
     };
+    // Only emit modifiers(...) if there is something to put in it
+    let uto_modifiers = match (uto_modifiers.is_empty(), modifiers.is_empty()) {
+        (true, true) => TokenStream::new(),
+        (true, false) => quote::quote!(modifiers(#modifiers),),
+        (false, _) => quote::quote!(modifiers(#uto_modifiers, #modifiers),),
+    };
     let uto_macro = quote::quote!(
         paths(#uto_paths #paths),components(schemas(#uto_models #schemas),responses(#uto_responses #responses)),
+        #uto_modifiers
         #remaining_nested_attributes
     );
 
-    syn::parse_quote! { #[openapi( #uto_macro )] }
+    Ok(syn::parse_quote! { #[openapi( #uto_macro )] })
+}
+
+/// Build the OpenApi implementation holding the discovered components for the `components_into` struct.
+///
+/// A macro can't modify another item, so the components are gathered into a hidden derived struct, and
+/// `target` forwards to its documentation.
+pub fn build_components_doc(
+    target: &syn::Path,
+    uto_models: &TokenStream,
+    uto_responses: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let empty = TokenStream::new();
+    let openapi_attribute = build_new_openapi_attributes(Punctuated::new(), &empty, uto_models, uto_responses, &empty)?;
+
+    Ok(quote::quote!(
+        const _: () = {
+            #[derive(utoipa::OpenApi)]
+            #openapi_attribute
+            struct UtoipautoComponents;
+
+            impl utoipa::OpenApi for #target {
+                fn openapi() -> utoipa::openapi::OpenApi {
+                    <UtoipautoComponents as utoipa::OpenApi>::openapi()
+                }
+            }
+        };
+    ))
 }
 
 fn remove_paths_and_components(nested_attributes: Punctuated<Meta, Token![,]>) -> TokenStream {
@@ -72,6 +537,7 @@ fn remove_paths_and_components(nested_attributes: Punctuated<Meta, Token![,]>) -
         match meta {
             Meta::List(list) if list.path.is_ident("paths") => (),
             Meta::List(list) if list.path.is_ident("components") => (),
+            Meta::List(list) if list.path.is_ident("modifiers") => (),
             // These should be handled by removing `components`, this is just in case they occur outside of `components` for some reason.
             Meta::List(list) if list.path.is_ident("schemas") => (),
             Meta::List(list) if list.path.is_ident("responses") => (),
@@ -91,27 +557,222 @@ fn extract_paths(nested_attributes: &Punctuated<Meta, Token![,]>) -> TokenStream
         .unwrap_or_else(TokenStream::new)
 }
 
-fn extract_components(nested_attributes: &Punctuated<Meta, Token![,]>, component_kind: &str) -> TokenStream {
+fn extract_modifiers(nested_attributes: &Punctuated<Meta, Token![,]>) -> TokenStream {
     nested_attributes
         .iter()
         .find_map(|meta| {
             let Meta::List(list) = meta else { return None };
-            if !list.path.is_ident("components") {
-                return None;
-            }
-
-            let nested = list
-                .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
-                .expect("Expected a list of attributes inside components(...)!");
-
-            nested.iter().find_map(|meta| {
-                let Meta::List(list) = meta else { return None };
-                list.path.is_ident(component_kind).then(|| list.tokens.clone())
-            })
+            list.path.is_ident("modifiers").then(|| list.tokens.clone())
         })
         .unwrap_or_else(TokenStream::new)
 }
 
+fn extract_components(
+    nested_attributes: &Punctuated<Meta, Token![,]>,
+    component_kind: &str,
+) -> syn::Result<TokenStream> {
+    for meta in nested_attributes {
+        let Meta::List(list) = meta else { continue };
+        if !list.path.is_ident("components") {
+            continue;
+        }
+
+        let nested = list
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            .map_err(|e| diagnostics::error(format!("Expected a list of attributes inside components(...): {}", e)))?;
+
+        let components = nested.iter().find_map(|meta| {
+            let Meta::List(list) = meta else { return None };
+            list.path.is_ident(component_kind).then(|| list.tokens.clone())
+        });
+        if let Some(components) = components {
+            return Ok(components);
+        }
+    }
+    Ok(TokenStream::new())
+}
+
+/// Report the endpoints of the previous documentation that were removed, or fail if `deny` is set
+fn check_removed_endpoints(
+    previous_spec: &str,
+    deny: bool,
+    discovered: &mut Discovered,
+    listed: &[String],
+) -> syn::Result<()> {
+    let previous = match Spec::from_path(previous_spec) {
+        Ok(Some(previous)) => previous,
+        // The documentation wasn't exported yet
+        Ok(None) => return Ok(()),
+        Err(error) => return Err(diagnostics::error(error)),
+    };
+    let first_new = discovered.warnings.len();
+    discovered.check_removed_endpoints(&previous, listed);
+    if deny && discovered.warnings.len() > first_new {
+        let removed: Vec<&str> = discovered.warnings[first_new..]
+            .iter()
+            .map(|warning| warning.message.as_str())
+            .collect();
+        return Err(diagnostics::error(removed.join(", ")));
+    }
+    Ok(())
+}
+
+/// Add the discovered items to the openapi macro, returning the items it needs alongside
+pub fn expand_openapi(
+    mut openapi_macro: syn::ItemStruct,
+    params: &Parameters,
+    mut discovered: Discovered,
+) -> syn::Result<(syn::ItemStruct, proc_macro2::TokenStream)> {
+    // The paths listed by hand may be relative, compare them with the discovered ones once resolved
+    canonicalize_listed_paths(&mut openapi_macro.attrs, &discovered.doc_module(params));
+    let listed = listed_paths(&openapi_macro.attrs);
+    discovered.dedup(&listed);
+    let listed_modifiers = listed_modifiers(&openapi_macro.attrs);
+    discovered.modifiers.retain(|modifier| {
+        let name = modifier.segments.last().map(|segment| segment.ident.to_string());
+        !name.is_some_and(|name| listed_modifiers.contains(&name))
+    });
+    if let Some(previous_spec) = &params.previous_spec {
+        check_removed_endpoints(previous_spec, params.deny_removed_endpoints, &mut discovered, &listed)?;
+    }
+    // The other docs are composed with their own items, see `Discovered::retain_sub_docs`
+    let sub_docs = match params.sub_docs {
+        Some(sub_docs) => compose_sub_docs(sub_docs, std::mem::take(&mut discovered.sub_docs)),
+        None => vec![],
+    };
+
+    let doc_module = discovered.doc_module(params);
+    let Discovered {
+        mut fns,
+        mut models,
+        mut responses,
+        mut modifiers,
+        warnings,
+        ..
+    } = discovered;
+
+    // The findings of the classes of `fail_on` are errors
+    let failures: Vec<&str> = warnings
+        .iter()
+        .filter(|warning| params.fail_on.contains(&warning.kind))
+        .map(|warning| warning.message.as_str())
+        .collect();
+    if !failures.is_empty() {
+        return Err(diagnostics::error(failures.join(", ")));
+    }
+
+    if params.document_generated {
+        let lists = [
+            ("Paths", &fns),
+            ("Schemas", &models),
+            ("Responses", &responses),
+            ("Modifiers", &modifiers),
+        ];
+        openapi_macro.attrs.extend(build_generated_doc(&lists));
+    }
+
+    // Move the items of each module to its own doc, composed into the annotated one
+    let mut compose = params.compose.clone();
+    compose.extend(sub_docs);
+    let mut generated_docs = proc_macro2::TokenStream::new();
+    if let Some(hierarchy) = params.hierarchy {
+        let (module_docs, composed) = split_hierarchy(
+            &openapi_macro,
+            hierarchy,
+            &doc_module,
+            [&mut fns, &mut models, &mut responses],
+        )?;
+        generated_docs.extend(module_docs);
+        compose.extend(composed);
+    }
+    // Then split the remaining ones into chunks small enough for the derive
+    if let Some(chunk_size) = params.chunk_size {
+        let (chunk_docs, composed) = split_chunks(&openapi_macro, chunk_size, [&mut fns, &mut models, &mut responses])?;
+        generated_docs.extend(chunk_docs);
+        compose.extend(composed);
+    }
+
+    // We need to add a coma after each path
+    let uto_paths = quote!(#(#fns),*);
+    let (uto_models, uto_responses) = (quote!(#(#models),*), quote!(#(#responses),*));
+
+    // Merge the composed docs first, so that the other modifiers see the whole documentation
+    let compose_items = match compose.is_empty() {
+        true => proc_macro2::TokenStream::new(),
+        false => {
+            let (compose_items, compose_modifier) = build_compose_modifier(&openapi_macro.ident, &compose);
+            modifiers.insert(0, compose_modifier);
+            compose_items
+        }
+    };
+
+    // Import the discovered modifiers under names the openapi macro accepts
+    let (mut additional_items, uto_modifiers) = build_modifier_aliases(&openapi_macro.ident, &modifiers);
+    additional_items.extend(compose_items);
+    additional_items.extend(generated_docs);
+
+    // Report what was found suspicious during discovery
+    additional_items.extend(emit_warnings(&warnings));
+
+    // Move the discovered components to the `components_into` struct if requested
+    let (uto_models, uto_responses) = match &params.components_into {
+        Some(target) => {
+            additional_items.extend(build_components_doc(target, &uto_models, &uto_responses)?);
+            (proc_macro2::TokenStream::new(), proc_macro2::TokenStream::new())
+        }
+        None => (uto_models, uto_responses),
+    };
+
+    // extract the openapi macro attributes : #[openapi(openapi_macro_attibutes)]
+    let openapi_macro_attibutes = &mut openapi_macro.attrs;
+
+    // Expand the include_schemas!(...) written by hand in the openapi macro
+    if let Err(e) = expand_include_schemas(openapi_macro_attibutes, params) {
+        additional_items.extend(e.to_compile_error());
+    }
+
+    // Update the openapi macro attributes with the newly discovered paths
+    update_openapi_macro_attributes(
+        openapi_macro_attibutes,
+        &uto_paths,
+        &uto_models,
+        &uto_responses,
+        &uto_modifiers,
+    )?;
+
+    Ok((openapi_macro, additional_items))
+}
+
+/// The discovery depends on cfg predicates : emit a variant of the doc for each of their combinations.
+///
+/// The paths are discovered again for each of them but the first, none of the predicates holding : it is the
+/// `discovered` one, made without assuming any.
+pub fn expand_openapi_variants(
+    openapi_macro: syn::ItemStruct,
+    params: &mut Parameters,
+    paths: &[String],
+    discovered: Discovered,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut output = proc_macro2::TokenStream::new();
+    let cfgs = discovered.cfgs.clone();
+    let mut first = Some(discovered);
+    for (cfg_values, predicate) in cfg_variants(&cfgs)? {
+        for merged in &mut params.merged {
+            merged.cfg_values = cfg_values.clone();
+        }
+        params.cfg_values = cfg_values;
+        let discovered = match first.take() {
+            Some(first) => first,
+            None => discover_all(paths, params)?,
+        };
+        let (mut variant, additional_items) = expand_openapi(openapi_macro.clone(), params, discovered)?;
+        variant.attrs.insert(0, syn::parse_quote!(#[cfg(#predicate)]));
+        output.extend(quote!(#variant));
+        output.extend(with_cfg(additional_items, &predicate));
+    }
+    Ok(output)
+}
+
 #[cfg(test)]
 mod test {
     use proc_macro2::TokenStream;
@@ -126,6 +787,134 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_build_generated_doc() {
+        let fns: Vec<syn::Path> = vec![syn::parse_quote!(crate::api::get_user)];
+        let models: Vec<syn::Path> = vec![syn::parse_quote!(crate::User), syn::parse_quote!(crate::Pet)];
+        let attrs = super::build_generated_doc(&[("Paths", &fns), ("Schemas", &models), ("Responses", &vec![])]);
+        let lines: Vec<String> = attrs
+            .iter()
+            .map(|attr| match &attr.meta {
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    value:
+                        syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Str(line),
+                            ..
+                        }),
+                    ..
+                }) => line.value(),
+                _ => panic!("Expected a doc attribute"),
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                " ",
+                " Discovered by utoipauto :",
+                " ",
+                " Paths :",
+                " - `crate::api::get_user`",
+                " ",
+                " Schemas :",
+                " - `crate::User`",
+                " - `crate::Pet`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fail_on() {
+        use crate::diagnostics::{Warning, WarningKind};
+
+        let openapi_macro: syn::ItemStruct = syn::parse_quote! {
+            #[derive(utoipa::OpenApi)]
+            #[openapi()]
+            pub struct ApiDoc;
+        };
+        let discovered = crate::discover::Discovered {
+            warnings: vec![
+                Warning::new(WarningKind::ConflictingRoute, "`a` and `b` both declare `GET /`"),
+                Warning::new(WarningKind::PrivateItem, "`crate::Secret` is private"),
+            ],
+            ..Default::default()
+        };
+        let params = crate::token_utils::extract_attributes(quote::quote! { fail_on = ["private_item"] }).unwrap();
+        let error = super::expand_openapi(openapi_macro, &params, discovered)
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(error.to_string(), "utoipauto: `crate::Secret` is private");
+    }
+
+    #[test]
+    fn test_split_chunks() {
+        let openapi_macro: syn::ItemStruct = syn::parse_quote!(
+            pub struct ApiDoc;
+        );
+        let mut fns: Vec<syn::Path> = vec![
+            syn::parse_quote!(crate::a),
+            syn::parse_quote!(crate::b),
+            syn::parse_quote!(crate::c),
+        ];
+        let mut models: Vec<syn::Path> = vec![syn::parse_quote!(crate::A)];
+        let (docs, composed) = super::split_chunks(&openapi_macro, 2, [&mut fns, &mut models, &mut vec![]]).unwrap();
+        assert!(fns.is_empty() && models.is_empty());
+        let composed: Vec<String> = composed
+            .iter()
+            .map(|doc| crate::path_utils::path_to_string(&doc.doc))
+            .collect();
+        assert_eq!(composed, vec!["self::__ApiDocChunk0", "self::__ApiDocChunk1"]);
+        let docs = docs.to_string().replace(' ', "");
+        assert!(docs.contains("#[openapi(paths(crate::a,crate::b,),components(schemas(crate::A,),responses()),)]"));
+        assert!(docs.contains("#[openapi(paths(crate::c,),components(schemas(),responses()),)]"));
+
+        // Small enough docs are left as is
+        let mut fns: Vec<syn::Path> = vec![syn::parse_quote!(crate::a)];
+        let (docs, composed) = super::split_chunks(&openapi_macro, 2, [&mut fns, &mut vec![], &mut vec![]]).unwrap();
+        assert!(docs.is_empty() && composed.is_empty());
+        assert_eq!(fns.len(), 1);
+    }
+
+    #[test]
+    fn test_split_hierarchy() {
+        let openapi_macro: syn::ItemStruct = syn::parse_quote!(
+            pub struct ApiDoc;
+        );
+        let mut fns: Vec<syn::Path> = vec![
+            syn::parse_quote!(crate::api::health),
+            syn::parse_quote!(crate::api::user_admin::list_users),
+            syn::parse_quote!(crate::other::get),
+            syn::parse_quote!(pets::routes::list_pets),
+        ];
+        let mut models: Vec<syn::Path> = vec![syn::parse_quote!(crate::api::user_admin::models::User)];
+        let (docs, composed) = super::split_hierarchy(
+            &openapi_macro,
+            super::Hierarchy::Nest,
+            &syn::parse_quote!(crate::api),
+            [&mut fns, &mut models, &mut vec![]],
+        )
+        .unwrap();
+        let fns: Vec<String> = fns.iter().map(crate::path_utils::path_to_string).collect();
+        assert_eq!(fns, vec!["crate::api::health", "crate::other::get"]);
+        assert!(models.is_empty());
+
+        let composed: Vec<(Option<String>, String)> = composed
+            .iter()
+            .map(|doc| (doc.nest.clone(), crate::path_utils::path_to_string(&doc.doc)))
+            .collect();
+        assert_eq!(
+            composed,
+            vec![
+                (Some("/user_admin".to_string()), "self::ApiDocUserAdmin".to_string()),
+                (Some("/pets".to_string()), "self::ApiDocPets".to_string()),
+            ]
+        );
+        let docs = docs.to_string().replace(' ', "");
+        assert!(docs.contains(
+            "#[openapi(paths(crate::api::user_admin::list_users,),components(schemas(crate::api::user_admin::models::User,)"
+        ));
+        assert!(docs.contains("pubstructApiDocPets;"));
+    }
+
     #[test]
     fn test_extract_paths_empty() {
         assert_eq!(super::extract_paths(&Punctuated::new()).to_string(), "".to_string());
@@ -139,7 +928,9 @@ mod test {
                 &quote::quote!(crate::api::test),
                 &TokenStream::new(),
                 &TokenStream::new(),
+                &TokenStream::new(),
             )
+            .unwrap()
             .to_token_stream()
             .to_string()
             .replace(' ', ""),
@@ -155,7 +946,9 @@ mod test {
                 &quote::quote!(crate::api::test),
                 &TokenStream::new(),
                 &TokenStream::new(),
+                &TokenStream::new(),
             )
+            .unwrap()
             .to_token_stream()
             .to_string()
             .replace(' ', ""),
@@ -171,7 +964,9 @@ mod test {
                 &quote::quote!(crate::api::test),
                 &quote::quote!(model),
                 &TokenStream::new(),
+                &TokenStream::new(),
             )
+            .unwrap()
             .to_token_stream()
             .to_string()
             .replace(' ', ""),
@@ -187,7 +982,9 @@ mod test {
                 &quote::quote!(crate::api::test),
                 &quote::quote!(model),
                 &TokenStream::new(),
+                &TokenStream::new(),
             )
+            .unwrap()
             .to_token_stream()
             .to_string()
             .replace(' ', ""),
@@ -203,7 +1000,9 @@ mod test {
                 &quote::quote!(crate::api::test),
                 &TokenStream::new(),
                 &quote::quote!(response),
+                &TokenStream::new(),
             )
+            .unwrap()
             .to_token_stream()
             .to_string()
             .replace(' ', ""),
@@ -219,7 +1018,9 @@ mod test {
                 &quote::quote!(crate::api::test),
                 &quote::quote!(model),
                 &quote::quote!(response),
+                &TokenStream::new(),
             )
+            .unwrap()
             .to_token_stream()
             .to_string()
             .replace(' ', ""),
@@ -235,7 +1036,9 @@ mod test {
                 &quote::quote!(crate::api::test),
                 &TokenStream::new(),
                 &quote::quote!(response),
+                &TokenStream::new(),
             )
+            .unwrap()
             .to_token_stream()
             .to_string()
             .replace(' ', ""),
@@ -251,7 +1054,9 @@ mod test {
                 &quote::quote!(crate::api::test),
                 &quote::quote!(model),
                 &TokenStream::new(),
+                &TokenStream::new(),
             )
+            .unwrap()
             .to_token_stream()
             .to_string()
             .replace(' ', ""),
@@ -259,6 +1064,39 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_update_openapi_attributes_missing() {
+        let mut attrs = vec![syn::parse_quote!(#[derive(utoipa::OpenApi)])];
+        let error = super::update_openapi_macro_attributes(
+            &mut attrs,
+            &TokenStream::new(),
+            &TokenStream::new(),
+            &TokenStream::new(),
+            &TokenStream::new(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "utoipauto: No #[openapi(...)] attribute found under #[utoipauto]"
+        );
+    }
+
+    #[test]
+    fn test_update_openapi_attributes_invalid_components() {
+        let mut attrs = vec![syn::parse_quote!(#[openapi(components(schemas(User), 42))])];
+        let error = super::update_openapi_macro_attributes(
+            &mut attrs,
+            &TokenStream::new(),
+            &TokenStream::new(),
+            &TokenStream::new(),
+            &TokenStream::new(),
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("utoipauto: Expected a list of attributes inside components(...):"));
+    }
+
     #[test]
     fn test_update_openapi_attributes_empty() {
         let mut attrs = vec![syn::parse_quote!(#[openapi])];
@@ -267,7 +1105,9 @@ mod test {
             &quote::quote!(crate::api::test),
             &quote::quote!(model),
             &TokenStream::new(),
-        );
+            &TokenStream::new(),
+        )
+        .unwrap();
         assert_eq!(
             attrs[0].to_token_stream().to_string().replace(' ', ""),
             "#[openapi(paths(crate::api::test,),components(schemas(model,),responses()),)]".to_string()
@@ -282,10 +1122,153 @@ mod test {
             &quote::quote!(crate::api::test),
             &quote::quote!(model),
             &TokenStream::new(),
-        );
+            &TokenStream::new(),
+        )
+        .unwrap();
         assert_eq!(
             attrs[0].to_token_stream().to_string().replace(' ', ""),
             "#[openapi(paths(crate::api::test,p1),components(schemas(model,m1),responses(r1)),)]".to_string()
         );
     }
+
+    #[test]
+    fn test_canonicalize_listed_paths() {
+        let mut attrs = vec![syn::parse_quote!(
+            #[openapi(paths(self::get_user, super::admin::delete_user, crate::users::self_check), info(title = "API"))]
+        )];
+        super::canonicalize_listed_paths(&mut attrs, &syn::parse_quote!(crate::docs));
+        assert_eq!(
+            attrs[0].to_token_stream().to_string().replace(' ', ""),
+            "#[openapi(paths(crate::docs::get_user,crate::admin::delete_user,crate::users::self_check),info(title=\"API\"))]"
+        );
+    }
+
+    #[test]
+    fn test_listed_paths() {
+        let attrs = vec![syn::parse_quote!(
+            #[openapi(paths(crate::api::get_user), components(schemas(crate::User, Vec<u8>), responses(crate::NotFound)))]
+        )];
+        assert_eq!(
+            super::listed_paths(&attrs),
+            vec!["crate::api::get_user", "crate::User", "Vec<u8>", "crate::NotFound"]
+        );
+    }
+
+    #[test]
+    fn test_listed_paths_mixed() {
+        let attrs = vec![syn::parse_quote!(
+            #[openapi(paths(crate::api::get_user, routes!(list_users)), components(schemas(HashMap<String, u8>, crate::Pet)))]
+        )];
+        assert_eq!(
+            super::listed_paths(&attrs),
+            vec!["crate::api::get_user", "HashMap<String,u8>", "crate::Pet"]
+        );
+    }
+
+    #[test]
+    fn test_listed_modifiers() {
+        let attrs = vec![syn::parse_quote!(
+            #[openapi(modifiers(&SecurityAddon, &addons::TitleAddon, &ServersAddon { servers: vec![] }))]
+        )];
+        assert_eq!(
+            super::listed_modifiers(&attrs),
+            vec!["SecurityAddon", "TitleAddon", "ServersAddon"]
+        );
+    }
+
+    #[test]
+    fn test_build_components_doc() {
+        let doc = super::build_components_doc(
+            &syn::parse_quote!(crate::docs::SchemaDoc),
+            &quote::quote!(crate::models::User),
+            &TokenStream::new(),
+        )
+        .unwrap()
+        .to_string()
+        .replace(' ', "");
+        assert!(doc.contains("#[openapi(paths(),components(schemas(crate::models::User,),responses()),)]"));
+        assert!(doc.contains("implutoipa::OpenApiforcrate::docs::SchemaDoc"));
+    }
+
+    #[test]
+    fn test_expand_include_schemas_without_schemas() {
+        let params = crate::token_utils::extract_attributes(TokenStream::new()).unwrap();
+        let mut attrs = vec![syn::parse_quote!(
+            #[openapi(components(schemas(utoipauto::include_schemas!("./src/lib.rs"), m1)))]
+        )];
+        super::expand_include_schemas(&mut attrs, &params).unwrap();
+        assert_eq!(
+            attrs[0].to_token_stream().to_string().replace(' ', ""),
+            "#[openapi(components(schemas(m1)))]".to_string()
+        );
+    }
+
+    #[test]
+    fn test_build_modifier_aliases() {
+        let (aliases, modifiers) = super::build_modifier_aliases(
+            &syn::parse_quote!(ApiDoc),
+            &[syn::parse_quote!(crate::addons::SecurityAddon)],
+        );
+        assert_eq!(
+            aliases.to_string().replace(' ', ""),
+            "usecrate::addons::SecurityAddonas__ApiDocModifier0;".to_string()
+        );
+        assert_eq!(modifiers.to_string().replace(' ', ""), "&__ApiDocModifier0".to_string());
+    }
+
+    #[test]
+    fn test_build_new_openapi_attributes_modifiers() {
+        assert_eq!(
+            super::build_new_openapi_attributes(
+                Punctuated::new(),
+                &quote::quote!(crate::api::test),
+                &TokenStream::new(),
+                &TokenStream::new(),
+                &quote::quote!(&__ApiDocModifier0),
+            )
+            .unwrap()
+            .to_token_stream()
+            .to_string()
+            .replace(' ', ""),
+            "#[openapi(paths(crate::api::test,),components(schemas(),responses()),modifiers(&__ApiDocModifier0,),)]"
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_build_new_openapi_attributes_modifiers_replace() {
+        assert_eq!(
+            super::build_new_openapi_attributes(
+                syn::parse_quote!(paths(p1), modifiers(&ManualAddon)),
+                &quote::quote!(crate::api::test),
+                &TokenStream::new(),
+                &TokenStream::new(),
+                &quote::quote!(&__ApiDocModifier0),
+            )
+            .unwrap()
+            .to_token_stream()
+            .to_string()
+            .replace(' ', ""),
+            "#[openapi(paths(crate::api::test,p1),components(schemas(),responses()),modifiers(&__ApiDocModifier0,&ManualAddon),)]"
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_build_new_openapi_attributes_manual_modifiers_only() {
+        assert_eq!(
+            super::build_new_openapi_attributes(
+                syn::parse_quote!(modifiers(&ManualAddon)),
+                &TokenStream::new(),
+                &TokenStream::new(),
+                &TokenStream::new(),
+                &TokenStream::new(),
+            )
+            .unwrap()
+            .to_token_stream()
+            .to_string()
+            .replace(' ', ""),
+            "#[openapi(paths(),components(schemas(),responses()),modifiers(&ManualAddon),)]".to_string()
+        );
+    }
 }
@@ -0,0 +1,716 @@
+//! On-disk cache of the discovery of the crates of the registry.
+//!
+//! The sources of a published crate can't change without a version bump, so their discovery is stored keyed by the
+//! name and version of the crate, along with everything else it depends on. Path and git dependencies can change
+//! under the same version, they are never cached.
+//!
+//! With `parse_cache`, the items of every scanned file are also stored under `target/`, keyed by the hash of its
+//! content, so that the unchanged files of the crate itself aren't parsed again.
+//!
+//! The discoveries are also kept in memory for the whole process, so that the doc structs scanning the same paths with
+//! the same parameters in a crate share a single scan.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use quote::ToTokens;
+
+use crate::{
+    diagnostics::{Warning, WarningKind},
+    discover::{DiscoverType, Discovered, Reexport},
+    file_utils::{collect_files_matching_skipping, collect_files_skipping, glob_base, is_glob},
+    manifest::Manifest,
+    path_utils::path_to_string,
+    token_utils::Parameters,
+};
+
+/// The key of the discovery of `src_path`, if it is in a crate of the registry
+pub fn cache_key(src_path: &str, crate_name: &str, params: &Parameters) -> Option<String> {
+    if is_disabled() {
+        return None;
+    }
+    let base = fs::canonicalize(glob_base(src_path)).ok()?;
+    if !base.to_string_lossy().replace('\\', "/").contains("/registry/src/") {
+        return None;
+    }
+    let package_root = base.ancestors().find(|dir| dir.join("Cargo.toml").is_file())?;
+    let manifest = Manifest::from_path(package_root.join("Cargo.toml")).ok()?;
+    let name = manifest.package_name()?;
+    let version = manifest.get_str("package", "version")?;
+    Some(format!(
+        "{}-{}-{:016x}",
+        name,
+        version,
+        fingerprint(src_path, crate_name, params)
+    ))
+}
+
+/// The discoveries of the process, by fingerprint, along with the stamp of the files they were made from, and the
+/// files they include with their stamp.
+///
+/// They are kept as cache strings : the tokens of a discovery are only valid during the invocation making it.
+static MEMO: Mutex<BTreeMap<u64, MemoEntry>> = Mutex::new(BTreeMap::new());
+
+struct MemoEntry {
+    stamp: u64,
+    included: Vec<String>,
+    included_stamp: u64,
+    discovered: String,
+}
+
+/// The key of the discovery of `src_path` in the memory of the process
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoKey {
+    fingerprint: u64,
+    /// The hash of the paths, sizes and modification times of the files : the process may outlive a change of the
+    /// sources, e.g. the proc macro server of rust-analyzer
+    stamp: u64,
+}
+
+/// The key of the discovery of `src_path` in the memory of the process, if its files can all be read
+pub fn memo_key(src_path: &str, crate_name: &str, params: &Parameters) -> Option<MemoKey> {
+    if is_disabled() {
+        return None;
+    }
+    let mut skipped = vec![];
+    let files = match is_glob(src_path) {
+        true => collect_files_matching_skipping(src_path, &params.extensions, &params.exclude, &mut skipped),
+        false => collect_files_skipping(src_path, &params.extensions, &params.exclude, &mut skipped),
+    }
+    .ok()?;
+    if !skipped.is_empty() {
+        return None;
+    }
+    let mut hasher = DefaultHasher::new();
+    for file in files {
+        let metadata = fs::metadata(&file).ok()?;
+        file.hash(&mut hasher);
+        metadata.len().hash(&mut hasher);
+        metadata.modified().ok()?.hash(&mut hasher);
+    }
+    Some(MemoKey {
+        fingerprint: fingerprint(src_path, crate_name, params),
+        stamp: hasher.finish(),
+    })
+}
+
+/// The discovery made earlier in the process, if its files didn't change since
+pub fn recall(key: MemoKey) -> Option<Discovered> {
+    let memo = MEMO.lock().ok()?;
+    let entry = memo.get(&key.fingerprint)?;
+    // The included files are only known once discovered, they may be outside of the path
+    let unchanged = entry.stamp == key.stamp && entry.included_stamp == included_stamp(&entry.included);
+    unchanged.then(|| from_cache_string(&entry.discovered))?
+}
+
+/// The discovery made earlier in the process, even if its files changed since, for `rust_analyzer = "cached"`
+pub fn recall_stale(src_path: &str, crate_name: &str, params: &Parameters) -> Option<Discovered> {
+    if is_disabled() {
+        return None;
+    }
+    let memo = MEMO.lock().ok()?;
+    let entry = memo.get(&fingerprint(src_path, crate_name, params))?;
+    from_cache_string(&entry.discovered)
+}
+
+/// Keep the discovery for the rest of the process, replacing the one made from an older version of its files
+pub fn remember(key: MemoKey, discovered: &Discovered) {
+    if let Ok(mut memo) = MEMO.lock() {
+        let entry = MemoEntry {
+            stamp: key.stamp,
+            included: discovered.included.clone(),
+            included_stamp: included_stamp(&discovered.included),
+            discovered: to_cache_string(discovered),
+        };
+        memo.insert(key.fingerprint, entry);
+    }
+}
+
+/// The hash of the paths, sizes and modification times of the included files, a missing one being hashed as such
+fn included_stamp(included: &[String]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for file in included {
+        file.hash(&mut hasher);
+        fs::metadata(file)
+            .ok()
+            .map(|metadata| (metadata.len(), metadata.modified().ok()))
+            .hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Whether the caches are disabled with `UTOIPAUTO_NO_CACHE`
+pub fn is_disabled() -> bool {
+    std::env::var_os("UTOIPAUTO_NO_CACHE").is_some()
+}
+
+/// Hash everything the discovery of a path depends on, besides the sources
+fn fingerprint(src_path: &str, crate_name: &str, params: &Parameters) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    src_path.hash(&mut hasher);
+    crate_name.hash(&mut hasher);
+    hash_params(params, &mut hasher);
+    hasher.finish()
+}
+
+/// Hash the parameters the discovery depends on
+fn hash_params(params: &Parameters, hasher: &mut DefaultHasher) {
+    // The discovery itself may change with utoipauto
+    env!("CARGO_PKG_VERSION").hash(hasher);
+    params.fn_attribute_names.hash(hasher);
+    params.schema_attribute_name.hash(hasher);
+    params.response_attribute_name.hash(hasher);
+    params.schema_derives.hash(hasher);
+    params.utoipa_crate.hash(hasher);
+    params.response_derives.hash(hasher);
+    params.schema_source.hash(hasher);
+    params.custom_impls.hash(hasher);
+    params.qualified_impls.hash(hasher);
+    params.module_mapping.strip.hash(hasher);
+    params.module_mapping.replace.hash(hasher);
+    params.prefer_reexports.hash(hasher);
+    params.flatten.hash(hasher);
+    params.pedantic.hash(hasher);
+    params.fail_on.hash(hasher);
+    params.require_descriptions.hash(hasher);
+    params.require_response_bodies.hash(hasher);
+    params.require_documented_routes.hash(hasher);
+    params.route_attributes.hash(hasher);
+    params.max_item_depth.hash(hasher);
+    params.max_files.hash(hasher);
+    params.module_files.hash(hasher);
+    params.extensions.hash(hasher);
+    params.exclude.hash(hasher);
+    params.include_tests.hash(hasher);
+    params.respect_gitignore.hash(hasher);
+    params.lenient.hash(hasher);
+    params.cfg_values.hash(hasher);
+    for template in &params.macros {
+        template.name.hash(hasher);
+        template.kind.hash(hasher);
+        template.argument.hash(hasher);
+    }
+    // The features known to be enabled, see `is_enabled_by_features`
+    let mut features: Vec<String> = std::env::vars_os()
+        .filter_map(|(name, _)| name.into_string().ok())
+        .filter(|name| name.starts_with("CARGO_FEATURE_"))
+        .collect();
+    features.sort();
+    features.hash(hasher);
+}
+
+/// The cache directory, `UTOIPAUTO_CACHE_DIR` or a directory of the system temp dir
+fn cache_dir() -> PathBuf {
+    std::env::var_os("UTOIPAUTO_CACHE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join("utoipauto-cache"))
+}
+
+pub fn load(key: &str) -> Option<Discovered> {
+    load_from(&cache_dir(), key)
+}
+
+pub fn store(key: &str, discovered: &Discovered) {
+    store_in(&cache_dir(), key, discovered)
+}
+
+fn load_from(dir: &Path, key: &str) -> Option<Discovered> {
+    from_cache_string(&fs::read_to_string(dir.join(key)).ok()?)
+}
+
+/// Store the discovery, failing silently : the cache is only an optimization
+fn store_in(dir: &Path, key: &str, discovered: &Discovered) {
+    write_entry(dir, key, &to_cache_string(discovered))
+}
+
+fn write_entry(dir: &Path, key: &str, content: &str) {
+    if fs::create_dir_all(dir).is_err() {
+        return;
+    }
+    // Concurrent builds may store the same key, never let them read a partial file
+    let partial = dir.join(format!("{}.{}.partial", key, std::process::id()));
+    if fs::write(&partial, content).is_ok() && fs::rename(&partial, dir.join(key)).is_err() {
+        let _ = fs::remove_file(partial);
+    }
+}
+
+/// The directory of the parse cache, `utoipauto` in `CARGO_TARGET_DIR` or in the closest `target` directory
+pub(crate) fn parse_cache_dir() -> PathBuf {
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .or_else(|| {
+            let current_dir = std::env::current_dir().ok()?;
+            let mut targets = current_dir.ancestors().map(|dir| dir.join("target"));
+            targets.find(|target| target.is_dir())
+        })
+        .unwrap_or_else(|| PathBuf::from("target"))
+        .join("utoipauto")
+}
+
+/// The hash of the content of a scanned file, the key of its entries in the parse cache
+pub fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The key of the items of a file, which also depend on its module and on the parameters
+pub fn items_key(hash: u64, module_path: &syn::Path, params: &Parameters) -> String {
+    let mut hasher = DefaultHasher::new();
+    path_to_string(module_path).hash(&mut hasher);
+    hash_params(params, &mut hasher);
+    format!("{:016x}-{:016x}.items", hash, hasher.finish())
+}
+
+/// The `mod foo;` declarations of a file, with the value of their `#[path]` attribute
+pub fn load_declarations(hash: u64) -> Option<Vec<(syn::Ident, Option<String>)>> {
+    load_declarations_from(&parse_cache_dir(), hash)
+}
+
+pub fn store_declarations(hash: u64, declarations: &[(syn::Ident, Option<String>)]) {
+    store_declarations_in(&parse_cache_dir(), hash, declarations)
+}
+
+pub(crate) fn load_items(key: &str) -> Option<Vec<DiscoverType>> {
+    load_items_from(&parse_cache_dir(), key)
+}
+
+pub(crate) fn store_items(key: &str, items: &[DiscoverType]) {
+    store_items_in(&parse_cache_dir(), key, items)
+}
+
+fn declarations_key(hash: u64) -> String {
+    format!("{:016x}.mods", hash)
+}
+
+fn load_declarations_from(dir: &Path, hash: u64) -> Option<Vec<(syn::Ident, Option<String>)>> {
+    let content = fs::read_to_string(dir.join(declarations_key(hash))).ok()?;
+    content
+        .lines()
+        .map(|line| {
+            let (ident, path) = line.split_once('\t')?;
+            let path = path.strip_prefix('=').map(str::to_string);
+            Some((syn::parse_str(ident).ok()?, path))
+        })
+        .collect()
+}
+
+/// One declaration per line, the `#[path]` being written after a `=`
+fn store_declarations_in(dir: &Path, hash: u64, declarations: &[(syn::Ident, Option<String>)]) {
+    let lines: Vec<String> = declarations
+        .iter()
+        .map(|(ident, path)| match path {
+            Some(path) => format!("{}\t={}", ident, path),
+            None => format!("{}\t", ident),
+        })
+        .collect();
+    write_entry(dir, &declarations_key(hash), &lines.join("\n"))
+}
+
+fn load_items_from(dir: &Path, key: &str) -> Option<Vec<DiscoverType>> {
+    fs::read_to_string(dir.join(key))
+        .ok()?
+        .lines()
+        .map(item_from_line)
+        .collect()
+}
+
+fn store_items_in(dir: &Path, key: &str, items: &[DiscoverType]) {
+    let lines: Vec<String> = items.iter().map(item_to_line).collect();
+    write_entry(dir, key, &lines.join("\n"))
+}
+
+/// An item of a file, its fields separated by tabs
+fn item_to_line(item: &DiscoverType) -> String {
+    let path = |kind: &str, path: &syn::Path| format!("{}\t{}", kind, path_to_string(path));
+    match item {
+        DiscoverType::Reexport(reexport) => format!(
+            "reexport\t{}\t{}\t{}\t{}",
+            path_to_string(&reexport.source),
+            path_to_string(&reexport.public),
+            reexport.glob,
+            path_to_string(&reexport.written)
+        ),
+        DiscoverType::Fn(n) => path("fn", n),
+        DiscoverType::Model(n) => path("model", n),
+        DiscoverType::Response(n) => path("response", n),
+        DiscoverType::CustomModelImpl(n) => path("custom_model", n),
+        DiscoverType::CustomResponseImpl(n) => path("custom_response", n),
+        DiscoverType::GenericModel(n) => path("generic", n),
+        DiscoverType::Modifier(n) => path("modifier", n),
+        DiscoverType::ModifierImpl(n) => path("modifier_impl", n),
+        DiscoverType::UnitStruct(n) => path("unit_struct", n),
+        DiscoverType::GatedModule(n) => path("gated", n),
+        DiscoverType::DocStruct(n) => path("doc", n),
+        DiscoverType::SubDoc(n) => path("subdoc", n),
+        DiscoverType::ResponseBody(owner, body) => {
+            format!("body\t{}\t{}", path_to_string(owner), body.to_token_stream())
+        }
+        DiscoverType::Route(handler, route) => format!("route\t{}\t{}", path_to_string(handler), route),
+        DiscoverType::Tag(handler, tag) => format!("tag\t{}\t{}", path_to_string(handler), tag),
+        DiscoverType::Cfg(cfg) => format!("cfg\t{}", cfg),
+        DiscoverType::Scope(path, scope) => {
+            let scope = scope.as_ref().map(path_to_string).unwrap_or_default();
+            format!("scope\t{}\t{}", path_to_string(path), scope)
+        }
+        DiscoverType::Warning(warning) => format!(
+            "warning\t{}\t{}",
+            warning.kind.name(),
+            warning.message.replace('\n', " ")
+        ),
+    }
+}
+
+/// Read back `item_to_line`, None if it is invalid
+fn item_from_line(line: &str) -> Option<DiscoverType> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let path = |idx: usize| syn::parse_str::<syn::Path>(fields.get(idx)?).ok();
+    let item = match fields[0] {
+        "reexport" => DiscoverType::Reexport(Reexport {
+            source: path(1)?,
+            public: path(2)?,
+            glob: fields.get(3)?.parse().ok()?,
+            written: path(4)?,
+        }),
+        "fn" => DiscoverType::Fn(path(1)?),
+        "model" => DiscoverType::Model(path(1)?),
+        "response" => DiscoverType::Response(path(1)?),
+        "custom_model" => DiscoverType::CustomModelImpl(path(1)?),
+        "custom_response" => DiscoverType::CustomResponseImpl(path(1)?),
+        "generic" => DiscoverType::GenericModel(path(1)?),
+        "modifier" => DiscoverType::Modifier(path(1)?),
+        "modifier_impl" => DiscoverType::ModifierImpl(path(1)?),
+        "unit_struct" => DiscoverType::UnitStruct(path(1)?),
+        "gated" => DiscoverType::GatedModule(path(1)?),
+        "doc" => DiscoverType::DocStruct(path(1)?),
+        "subdoc" => DiscoverType::SubDoc(path(1)?),
+        "body" => DiscoverType::ResponseBody(path(1)?, syn::parse_str(fields.get(2)?).ok()?),
+        "route" => DiscoverType::Route(path(1)?, fields.get(2)?.to_string()),
+        "tag" => DiscoverType::Tag(path(1)?, fields.get(2)?.to_string()),
+        "cfg" => DiscoverType::Cfg(fields.get(1)?.to_string()),
+        "scope" => {
+            let scope = match *fields.get(2)? {
+                "" => None,
+                _ => Some(path(2)?),
+            };
+            DiscoverType::Scope(path(1)?, scope)
+        }
+        "warning" => DiscoverType::Warning(Warning::new(
+            WarningKind::from_name(fields.get(1)?)?,
+            fields.get(2)?.to_string(),
+        )),
+        _ => return None,
+    };
+    Some(item)
+}
+
+/// One entry per line, its fields separated by tabs
+fn to_cache_string(discovered: &Discovered) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let paths = [
+        ("fn", &discovered.fns),
+        ("model", &discovered.models),
+        ("response", &discovered.responses),
+        ("modifier", &discovered.modifiers),
+        ("custom", &discovered.custom_impls),
+        ("generic", &discovered.generic_models),
+        ("doc", &discovered.doc_structs),
+        ("subdoc", &discovered.sub_docs),
+    ];
+    for (kind, paths) in paths {
+        lines.extend(paths.iter().map(|path| format!("{}\t{}", kind, path_to_string(path))));
+    }
+    lines.extend(discovered.cfgs.iter().map(|cfg| format!("cfg\t{}", cfg)));
+    lines.extend(discovered.sources.iter().map(|source| format!("source\t{}", source)));
+    lines.extend(
+        discovered
+            .response_bodies
+            .iter()
+            .map(|(owner, body)| format!("body\t{}\t{}", path_to_string(owner), body.to_token_stream())),
+    );
+    lines.extend(
+        discovered
+            .routes
+            .iter()
+            .map(|(handler, route)| format!("route\t{}\t{}", path_to_string(handler), route)),
+    );
+    lines.extend(
+        discovered
+            .tags
+            .iter()
+            .map(|(handler, tag)| format!("tag\t{}\t{}", path_to_string(handler), tag)),
+    );
+    lines.extend(discovered.warnings.iter().map(|warning| {
+        format!(
+            "warning\t{}\t{}",
+            warning.kind.name(),
+            warning.message.replace('\n', " ")
+        )
+    }));
+    lines.extend(discovered.scopes.iter().map(|(path, scope)| {
+        let scope = scope.as_ref().map(path_to_string).unwrap_or_default();
+        format!("scope\t{}\t{}", path_to_string(path), scope)
+    }));
+    lines.join("\n")
+}
+
+/// Read back `to_cache_string`, None if any entry is invalid
+fn from_cache_string(content: &str) -> Option<Discovered> {
+    let mut discovered = Discovered::default();
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let path = |idx: usize| syn::parse_str::<syn::Path>(fields.get(idx)?).ok();
+        match fields[0] {
+            "fn" => discovered.fns.push(path(1)?),
+            "model" => discovered.models.push(path(1)?),
+            "response" => discovered.responses.push(path(1)?),
+            "modifier" => discovered.modifiers.push(path(1)?),
+            "custom" => discovered.custom_impls.push(path(1)?),
+            "generic" => discovered.generic_models.push(path(1)?),
+            "doc" => discovered.doc_structs.push(path(1)?),
+            "subdoc" => discovered.sub_docs.push(path(1)?),
+            "cfg" => discovered.cfgs.push(fields.get(1)?.to_string()),
+            "source" => discovered.sources.push(fields.get(1)?.to_string()),
+            "body" => discovered
+                .response_bodies
+                .push((path(1)?, syn::parse_str(fields.get(2)?).ok()?)),
+            "route" => discovered.routes.push((path(1)?, fields.get(2)?.to_string())),
+            "tag" => discovered.tags.push((path(1)?, fields.get(2)?.to_string())),
+            "warning" => discovered.warnings.push(Warning::new(
+                WarningKind::from_name(fields.get(1)?)?,
+                fields.get(2)?.to_string(),
+            )),
+            "scope" => {
+                let scope = match *fields.get(2)? {
+                    "" => None,
+                    _ => Some(path(2)?),
+                };
+                discovered.scopes.push((path(1)?, scope));
+            }
+            _ => return None,
+        }
+    }
+    Some(discovered)
+}
+
+#[cfg(test)]
+mod test {
+    use proc_macro2::TokenStream;
+    use quote::quote;
+
+    use super::*;
+    use crate::test_utils::TempDir;
+    use crate::token_utils::extract_attributes;
+
+    #[test]
+    fn test_cache_round_trip() {
+        let discovered = Discovered {
+            fns: vec![syn::parse_quote!(crate::api::get_user)],
+            models: vec![syn::parse_quote!(crate::User)],
+            cfgs: vec!["feature = \"private-api\"".to_string()],
+            sources: vec!["/src/api.rs".to_string()],
+            response_bodies: vec![(syn::parse_quote!(crate::UserResponses), syn::parse_quote!(Vec<User>))],
+            routes: vec![(syn::parse_quote!(crate::api::get_user), "GET /users/{id}".to_string())],
+            tags: vec![(syn::parse_quote!(crate::api::get_user), "users".to_string())],
+            warnings: vec![Warning::new(WarningKind::PrivateItem, "`crate::User` is private")],
+            scopes: vec![
+                (syn::parse_quote!(crate::api), None),
+                (syn::parse_quote!(crate::User), Some(syn::parse_quote!(crate))),
+            ],
+            ..Default::default()
+        };
+        let dir = TempDir::new("cache");
+        store_in(&dir, "api-1.0.0", &discovered);
+        let loaded = load_from(&dir, "api-1.0.0").unwrap();
+        assert_eq!(to_cache_string(&loaded), to_cache_string(&discovered));
+        assert_eq!(loaded.warnings, discovered.warnings);
+        assert!(from_cache_string("unknown\tentry").is_none());
+    }
+
+    #[test]
+    fn test_parse_cache_round_trip() {
+        let items = vec![
+            DiscoverType::Reexport(Reexport {
+                source: syn::parse_quote!(crate::models::User),
+                public: syn::parse_quote!(crate::User),
+                glob: false,
+                written: syn::parse_quote!(models::User),
+            }),
+            DiscoverType::Fn(syn::parse_quote!(crate::api::get_user)),
+            DiscoverType::ResponseBody(syn::parse_quote!(crate::UserResponses), syn::parse_quote!(Vec<User>)),
+            DiscoverType::Route(syn::parse_quote!(crate::api::get_user), "GET /users/{id}".to_string()),
+            DiscoverType::Scope(syn::parse_quote!(crate::api), None),
+            DiscoverType::Warning(Warning::new(WarningKind::PrivateItem, "`crate::User` is private")),
+        ];
+        let declarations = vec![
+            (syn::parse_quote!(api), None),
+            (syn::parse_quote!(r#type), Some("generated/type.rs".to_string())),
+        ];
+        let dir = TempDir::new("parse-cache");
+        let hash = content_hash("pub mod api;");
+        store_items_in(&dir, "api.items", &items);
+        store_declarations_in(&dir, hash, &declarations);
+        let loaded_items = load_items_from(&dir, "api.items").unwrap();
+        let loaded_declarations = load_declarations_from(&dir, hash).unwrap();
+        let lines = |items: &[DiscoverType]| items.iter().map(item_to_line).collect::<Vec<_>>();
+        assert_eq!(lines(&loaded_items), lines(&items));
+        assert_eq!(loaded_declarations, declarations);
+        assert_ne!(content_hash("pub mod api;"), content_hash("pub mod admin;"));
+        assert!(item_from_line("unknown\tentry").is_none());
+    }
+
+    #[test]
+    fn test_memo() {
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let root = TempDir::new("memo");
+        fs::write(root.join("lib.rs"), "pub struct User;").unwrap();
+        let src = root.to_string_lossy().to_string();
+        let key = memo_key(&src, "crate", &params).unwrap();
+        let discovered = Discovered {
+            models: vec![syn::parse_quote!(crate::User)],
+            ..Default::default()
+        };
+        remember(key, &discovered);
+        let recalled = recall(key).map(|discovered| to_cache_string(&discovered));
+
+        // The files changed since, the discovery is made again
+        fs::write(root.join("lib.rs"), "pub struct User; pub struct Pet;").unwrap();
+        let changed_key = memo_key(&src, "crate", &params).unwrap();
+        assert_eq!(recalled, Some(to_cache_string(&discovered)));
+        assert_ne!(changed_key, key);
+        assert!(recall(changed_key).is_none());
+        let stale = recall_stale(&src, "crate", &params).map(|discovered| to_cache_string(&discovered));
+        assert_eq!(stale, Some(to_cache_string(&discovered)));
+    }
+
+    #[test]
+    fn test_memo_included() {
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let root = TempDir::new("memo-included");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("out")).unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "pub mod types { include!(\"../out/types.rs\"); }",
+        )
+        .unwrap();
+        fs::write(
+            root.join("out/types.rs"),
+            "#[derive(utoipa::ToSchema)] pub struct User;",
+        )
+        .unwrap();
+        let src = root.join("src").to_string_lossy().to_string();
+        let models = || {
+            let discovered = crate::discover::discover_from_file(src.clone(), "crate".to_string(), &params).unwrap();
+            discovered.models.iter().map(path_to_string).collect::<Vec<_>>()
+        };
+        let before = models();
+        // The included file is outside of the path, its change must still be noticed
+        fs::write(
+            root.join("out/types.rs"),
+            "#[derive(utoipa::ToSchema)] pub struct User; #[derive(utoipa::ToSchema)] pub struct Pet;",
+        )
+        .unwrap();
+        let after = models();
+
+        assert_eq!(before, vec!["crate::types::User"]);
+        assert_eq!(after, vec!["crate::types::User", "crate::types::Pet"]);
+    }
+
+    /// The fields of the parameters read by the discovery, e.g. `params.macros`, in the source of the module
+    fn read_fields(source: &str) -> Vec<String> {
+        let source = source.split("#[cfg(test)]").next().unwrap();
+        let mut fields: Vec<String> = source
+            .match_indices("params.")
+            .filter(|(idx, _)| !source[..*idx].ends_with(|c: char| c.is_alphanumeric() || c == '_'))
+            .filter_map(|(idx, prefix)| {
+                let rest = &source[idx + prefix.len()..];
+                let end = rest
+                    .find(|c: char| !c.is_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                // Not a method, e.g. `params.checks(...)`, which reads hashed fields
+                (!rest[end..].starts_with('(')).then(|| rest[..end].to_string())
+            })
+            .collect();
+        fields.sort();
+        fields.dedup();
+        fields
+    }
+
+    #[test]
+    fn test_hash_params_complete() {
+        // Read by the discovery without changing what it discovers
+        const UNHASHED: &[&str] = &["parse_cache", "rust_analyzer"];
+        let hashing = include_str!("cache.rs").split("fn hash_params").nth(1).unwrap();
+        let hashing = hashing.split("\n}\n").next().unwrap();
+        let hashed = read_fields(hashing);
+        let unhashed: Vec<String> = read_fields(include_str!("discover.rs"))
+            .into_iter()
+            .filter(|field| !hashed.contains(field) && !UNHASHED.contains(&field.as_str()))
+            .collect();
+        // A field missing from `hash_params` lets the caches return a discovery made with another value of it
+        assert!(unhashed.is_empty(), "not hashed by hash_params: {:?}", unhashed);
+    }
+
+    #[test]
+    fn test_fingerprint_macros() {
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let with_macros = extract_attributes(quote! { macros = ["endpoint! => path(0)"] }).unwrap();
+        let other_argument = extract_attributes(quote! { macros = ["endpoint! => path(1)"] }).unwrap();
+        assert_ne!(
+            fingerprint("./src", "crate", &params),
+            fingerprint("./src", "crate", &with_macros)
+        );
+        assert_ne!(
+            fingerprint("./src", "crate", &with_macros),
+            fingerprint("./src", "crate", &other_argument)
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_shared_by_split_docs() {
+        let public = extract_attributes(quote! { only = ["crate::api::public"] }).unwrap();
+        let admin = extract_attributes(quote! { filter = "module.starts_with('crate::api::admin')" }).unwrap();
+        // The docs filter the same discovery, the path is scanned once for all of them
+        assert_eq!(
+            fingerprint("./src", "crate", &public),
+            fingerprint("./src", "crate", &admin)
+        );
+        let lenient = extract_attributes(quote! { lenient = true }).unwrap();
+        assert_ne!(
+            fingerprint("./src", "crate", &public),
+            fingerprint("./src", "crate", &lenient)
+        );
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let root = TempDir::new("registry");
+        let package = root.join("registry/src/index.crates.io-6f17d22bba15001f/api-1.2.0");
+        fs::create_dir_all(package.join("src")).unwrap();
+        fs::write(
+            package.join("Cargo.toml"),
+            "[package]\nname = \"api\"\nversion = \"1.2.0\"",
+        )
+        .unwrap();
+        let src = package.join("src").to_string_lossy().to_string();
+        let key = cache_key(&src, "api", &params);
+        let other_key = cache_key(
+            &src,
+            "api",
+            &extract_attributes(quote::quote!(prefer_reexports = true)).unwrap(),
+        );
+        assert!(key.as_ref().is_some_and(|key| key.starts_with("api-1.2.0-")));
+        assert_ne!(key, other_key);
+
+        // Not a crate of the registry
+        assert!(cache_key("./src", "crate", &params).is_none());
+    }
+}
@@ -0,0 +1,196 @@
+//! Write the discovery result to a source file, for the builds where the macro can't read the sources,
+//! e.g. sandboxed or offline builds of a downstream crate.
+
+use std::{fs, io, path::Path};
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+use crate::{
+    attribute_utils::{expand_openapi, expand_openapi_variants},
+    diagnostics,
+    string_utils::{discover_all, extract_paths},
+    token_utils::Parameters,
+};
+
+/// Generate the source of a `struct_name` deriving OpenApi with the discovered items, as `#[utoipauto]` would
+/// expand it. The paths are relative to the current directory, which should be the root of the package.
+pub fn generate(struct_name: &str, mut params: Parameters) -> syn::Result<String> {
+    let ident: syn::Ident = syn::parse_str(struct_name)
+        .map_err(|_| diagnostics::error(format!("Invalid struct name \"{}\"", struct_name)))?;
+    let openapi_macro: syn::ItemStruct = syn::parse_quote! {
+        #[derive(utoipa::OpenApi)]
+        #[openapi()]
+        pub struct #ident;
+    };
+    let paths = extract_paths(&params.paths)?;
+    let discovered = discover_all(&paths, &params)?;
+    let tokens = match discovered.cfgs.is_empty() {
+        true => {
+            let (openapi_macro, additional_items) = expand_openapi(openapi_macro, &params, discovered)?;
+            quote!(#openapi_macro #additional_items)
+        }
+        false => expand_openapi_variants(openapi_macro, &mut params, &paths, discovered)?,
+    };
+
+    to_source(
+        "// @generated by utoipauto, regenerate it rather than editing it",
+        tokens,
+    )
+}
+
+/// The items of the tokens, one per line, after the header
+fn to_source(header: &str, tokens: TokenStream) -> syn::Result<String> {
+    let file: syn::File = syn::parse2(tokens)?;
+    let mut source = format!("{}\n", header);
+    for item in file.items {
+        source.push_str(&item.to_token_stream().to_string());
+        source.push('\n');
+    }
+    Ok(source)
+}
+
+/// Write the expansion of `#[utoipauto]` on `item_name` to `output`, to inspect what was added to the `#[openapi]`
+/// attribute, see `Parameters::emit_to`. The file is left untouched if its content is the same.
+pub fn write_expanded(item_name: &str, tokens: TokenStream, output: impl AsRef<Path>) -> io::Result<()> {
+    let header = format!(
+        "// The expansion of #[utoipauto] on `{}`, written by utoipauto for inspection",
+        item_name
+    );
+    let source = to_source(&header, tokens).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    if let Some(directory) = output
+        .as_ref()
+        .parent()
+        .filter(|directory| !directory.as_os_str().is_empty())
+    {
+        fs::create_dir_all(directory)?;
+    }
+    write_if_changed(output, source)
+}
+
+/// Write `generate` to `output`, e.g. from a build script or `cargo utoipauto generate`.
+///
+/// The file is left untouched if its content is the same, so that it doesn't trigger a rebuild. The errors of the
+/// discovery are `InvalidData` errors.
+pub fn write_discovered(struct_name: &str, params: Parameters, output: impl AsRef<Path>) -> io::Result<()> {
+    let source =
+        generate(struct_name, params).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    write_if_changed(output, source)
+}
+
+fn write_if_changed(output: impl AsRef<Path>, source: String) -> io::Result<()> {
+    if fs::read_to_string(output.as_ref()).is_ok_and(|current| current == source) {
+        return Ok(());
+    }
+    fs::write(output, source)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    #[test]
+    fn test_generate() {
+        let root = TempDir::new("codegen");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            r#"
+            #[utoipa::path(get, path = "/users")]
+            pub fn list_users() {}
+
+            #[derive(utoipa::ToSchema)]
+            pub struct User;
+            "#,
+        )
+        .unwrap();
+        let params = Parameters::from_attributes(&format!("paths = \"{}\"", root.join("src").display())).unwrap();
+        let output = root.join("discovered.rs");
+        write_discovered("DiscoveredApi", params, &output).unwrap();
+        let source = fs::read_to_string(&output).unwrap().replace(' ', "");
+
+        assert!(source.starts_with("//@generatedbyutoipauto"));
+        assert!(source.contains("#[derive(utoipa::OpenApi)]"));
+        assert!(source.contains("paths(crate::list_users,)"));
+        assert!(source.contains("schemas(crate::User,)"));
+        assert!(source.contains("pubstructDiscoveredApi;"));
+    }
+
+    #[test]
+    fn test_generate_variants() {
+        let root = TempDir::new("codegen-variants");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            r#"
+            #[cfg(feature = "admin")]
+            pub mod admin;
+
+            #[utoipa::path(get, path = "/users")]
+            pub fn list_users() {}
+            "#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/admin.rs"),
+            "#[utoipa::path(get, path = \"/stats\")] pub fn get_stats() {}",
+        )
+        .unwrap();
+        let params = Parameters::from_attributes(&format!("paths = \"{}\"", root.join("src").display())).unwrap();
+        let source = generate("ApiDoc", params).unwrap().replace(' ', "");
+
+        let variants: Vec<&str> = source.lines().skip(1).collect();
+        assert_eq!(variants.len(), 2);
+        assert!(variants[0].starts_with("#[cfg(all(not(feature=\"admin\")))]"));
+        assert!(variants[0].contains("paths(crate::list_users,)"));
+        assert!(variants[1].starts_with("#[cfg(all(feature=\"admin\"))]"));
+        assert!(variants[1].contains("paths(crate::list_users,crate::admin::get_stats,)"));
+    }
+
+    #[test]
+    fn test_generate_strict_empty() {
+        let root = TempDir::new("codegen-strict");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn helper() {}").unwrap();
+        let src = root.join("src").display().to_string();
+        let lenient = generate(
+            "ApiDoc",
+            Parameters::from_attributes(&format!("paths = \"{}\"", src)).unwrap(),
+        );
+        let strict = Parameters::from_attributes(&format!("paths = \"{}\", strict = true", src)).unwrap();
+        let error = generate("ApiDoc", strict).unwrap_err();
+
+        assert!(lenient.is_ok());
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "utoipauto: `{}` contributed no discovered item, nothing was discovered in `{}`",
+                src, src
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_expanded() {
+        let root = TempDir::new("expanded");
+        let output = root.join("target/utoipauto/expanded.rs");
+        let tokens = quote! {
+            #[derive(utoipa::OpenApi)]
+            #[openapi(paths(crate::list_users,))]
+            pub struct ApiDoc;
+        };
+        write_expanded("ApiDoc", tokens, &output).unwrap();
+        let source = fs::read_to_string(&output).unwrap();
+
+        let mut lines = source.lines();
+        assert_eq!(
+            lines.next(),
+            Some("// The expansion of #[utoipauto] on `ApiDoc`, written by utoipauto for inspection")
+        );
+        assert_eq!(
+            lines.next().map(|line| line.replace(' ', "")),
+            Some("#[derive(utoipa::OpenApi)]#[openapi(paths(crate::list_users,))]pubstructApiDoc;".to_string())
+        );
+    }
+}
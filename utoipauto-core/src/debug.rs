@@ -0,0 +1,26 @@
+use std::fmt::Display;
+
+use crate::path_utils::path_to_string;
+
+/// Whether the `UTOIPAUTO_DEBUG` environment variable is set, e.g. `UTOIPAUTO_DEBUG=1 cargo build`
+pub fn is_enabled() -> bool {
+    std::env::var_os("UTOIPAUTO_DEBUG").is_some()
+}
+
+/// Print the message to stderr when the `UTOIPAUTO_DEBUG` environment variable is set
+pub fn log(message: impl Display) {
+    if is_enabled() {
+        eprintln!("utoipauto: {}", message);
+    }
+}
+
+/// Keep the items passing the test, logging the other ones along with the reason they are skipped
+pub fn retain_logged(items: &mut Vec<syn::Path>, reason: impl Display, mut is_kept: impl FnMut(&syn::Path) -> bool) {
+    items.retain(|item| {
+        let is_kept = is_kept(item);
+        if !is_kept {
+            log(format_args!("skipped {} ({})", path_to_string(item), reason));
+        }
+        is_kept
+    });
+}
@@ -0,0 +1,195 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+
+/// Class of a finding reported during discovery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// A type referenced by a discovered item is not a discovered schema
+    UndiscoveredSchema,
+    /// A discovered item is not visible from the doc struct
+    PrivateItem,
+    /// Two discovered handlers declare the same method and path
+    ConflictingRoute,
+    /// An endpoint of the previous documentation is no longer declared by a handler
+    RemovedEndpoint,
+    /// A discovered handler has neither a doc comment nor a description, with `require_descriptions`
+    UndescribedHandler,
+    /// A discovered handler documents no response body, with `require_response_bodies`
+    MissingResponseBody,
+    /// A function has a routing attribute of the framework but no `#[utoipa::path]`, with `require_documented_routes`
+    UndocumentedRoute,
+    /// A scanned file or directory can't be read, and was skipped
+    UnreadableFile,
+    /// A scanned file isn't valid Rust, and was skipped, with `lenient`
+    UnparsableFile,
+    /// The self type of a manual implementation of ToSchema or ToResponse can't be named
+    UnresolvedImpl,
+    /// An item is ignored, but wouldn't be discovered anyway, in pedantic mode
+    UnnecessaryIgnore,
+    /// A scan root contributed no item, in pedantic mode
+    EmptyRoot,
+    /// A scanned file is not declared as a module by its parent, in pedantic mode
+    UnreachableModule,
+    /// A path matched no files, in pedantic mode
+    UnmatchedPath,
+    /// Two discovered schemas have the same name, in pedantic mode
+    DuplicateSchema,
+    /// The discovery found no item at all, in pedantic mode
+    EmptyDiscovery,
+}
+
+impl WarningKind {
+    pub const ALL: &'static [WarningKind] = &[
+        WarningKind::UndiscoveredSchema,
+        WarningKind::PrivateItem,
+        WarningKind::ConflictingRoute,
+        WarningKind::RemovedEndpoint,
+        WarningKind::UndescribedHandler,
+        WarningKind::MissingResponseBody,
+        WarningKind::UndocumentedRoute,
+        WarningKind::UnreadableFile,
+        WarningKind::UnparsableFile,
+        WarningKind::UnresolvedImpl,
+        WarningKind::UnnecessaryIgnore,
+        WarningKind::EmptyRoot,
+        WarningKind::UnreachableModule,
+        WarningKind::UnmatchedPath,
+        WarningKind::DuplicateSchema,
+        WarningKind::EmptyDiscovery,
+    ];
+
+    /// The class with the given name, see `name`
+    pub fn from_name(name: &str) -> Option<WarningKind> {
+        WarningKind::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+
+    /// The name of the class, as written in the attributes of the macro
+    pub fn name(&self) -> &'static str {
+        match self {
+            WarningKind::UndiscoveredSchema => "undiscovered_schema",
+            WarningKind::PrivateItem => "private_item",
+            WarningKind::ConflictingRoute => "conflicting_route",
+            WarningKind::RemovedEndpoint => "removed_endpoint",
+            WarningKind::UndescribedHandler => "undescribed_handler",
+            WarningKind::MissingResponseBody => "missing_response_body",
+            WarningKind::UndocumentedRoute => "undocumented_route",
+            WarningKind::UnreadableFile => "unreadable_file",
+            WarningKind::UnparsableFile => "unparsable_file",
+            WarningKind::UnresolvedImpl => "unresolved_impl",
+            WarningKind::UnnecessaryIgnore => "unnecessary_ignore",
+            WarningKind::EmptyRoot => "empty_root",
+            WarningKind::UnreachableModule => "unreachable_module",
+            WarningKind::UnmatchedPath => "unmatched_path",
+            WarningKind::DuplicateSchema => "duplicate_schema",
+            WarningKind::EmptyDiscovery => "empty_discovery",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(kind: WarningKind, message: impl Into<String>) -> Self {
+        Warning {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+/// An error of the discovery, reported on the `#[utoipauto]` attribute, or the macro invocation
+pub fn error(message: impl std::fmt::Display) -> syn::Error {
+    syn::Error::new(Span::call_site(), format!("utoipauto: {}", message))
+}
+
+/// The values of the results, or all of their errors combined
+pub fn collect_results<T>(results: impl IntoIterator<Item = syn::Result<T>>) -> syn::Result<Vec<T>> {
+    let mut values = vec![];
+    let mut errors: Option<syn::Error> = None;
+    for result in results {
+        match (result, &mut errors) {
+            (Ok(value), _) => values.push(value),
+            (Err(error), Some(errors)) => errors.combine(error),
+            (Err(error), None) => errors = Some(error),
+        }
+    }
+    match errors {
+        Some(errors) => Err(errors),
+        None => Ok(values),
+    }
+}
+
+/// The error, with the file or module it comes from appended to each of its messages
+pub fn in_source(error: syn::Error, source: &str) -> syn::Error {
+    error
+        .into_iter()
+        .map(|error| syn::Error::new(error.span(), format!("{}, in `{}`", error, source)))
+        .reduce(|mut combined, error| {
+            combined.combine(error);
+            combined
+        })
+        .expect("a syn::Error has at least one message")
+}
+
+/// Emit the warnings to the compiler.
+///
+/// A proc macro can't emit warnings on stable, so each of them is the use of a deprecated constant
+/// named after the class of the warning, with the message as the deprecation note.
+pub fn emit_warnings(warnings: &[Warning]) -> TokenStream {
+    let warnings = warnings.iter().map(|warning| {
+        let name = format_ident!("{}", warning.kind.name());
+        let note = format!("utoipauto: {}", warning.message);
+        quote! {
+            const _: () = {
+                #[deprecated(note = #note)]
+                #[allow(non_upper_case_globals)]
+                const #name: () = ();
+                #name
+            };
+        }
+    });
+    quote!(#(#warnings)*)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_emit_warnings() {
+        let tokens = emit_warnings(&[Warning::new(WarningKind::UndiscoveredSchema, "`Foo` is not discovered")]);
+        let tokens = tokens.to_string();
+        assert!(tokens.contains("deprecated (note = \"utoipauto: `Foo` is not discovered\")"));
+        assert!(tokens.contains("const undiscovered_schema"));
+        assert!(emit_warnings(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_in_source() {
+        let mut combined = error("Invalid alias");
+        combined.combine(error("Invalid ignore"));
+        let messages: Vec<String> = in_source(combined, "./src/api.rs")
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "utoipauto: Invalid alias, in `./src/api.rs`",
+                "utoipauto: Invalid ignore, in `./src/api.rs`"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_name() {
+        for kind in WarningKind::ALL {
+            assert_eq!(WarningKind::from_name(kind.name()), Some(*kind));
+        }
+        assert_eq!(WarningKind::from_name("unknown"), None);
+    }
+}
@@ -1,209 +1,4474 @@
+use std::path::PathBuf;
 use std::vec;
 
-use crate::file_utils::{extract_module_name_from_path, parse_files};
-use crate::token_utils::Parameters;
-use quote::ToTokens;
+use crate::cache;
+use crate::debug;
+use crate::diagnostics::{self, Warning, WarningKind};
+use crate::file_utils::{
+    check_root, collect_files_matching_skipping, collect_files_skipping, extract_module_name_from_path_with, glob_base,
+    is_glob, parse_failures, parse_source, read_readable_files, retain_unignored, ParseFailure,
+};
+use crate::filter::{Filter, FilterItem};
+use crate::manifest::Manifest;
+use crate::path_utils::{canonicalize, path_to_string, starts_with};
+use crate::spec::Spec;
+use crate::token_utils::{is_rust_analyzer, IdeMode, ImplTarget, Order, Parameters, TemplateKind};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
 use syn::token::Comma;
 use syn::Ident;
-use syn::{punctuated::Punctuated, Attribute, GenericParam, Item, ItemFn, ItemImpl, Meta, Token};
+use syn::{
+    punctuated::Punctuated, Attribute, GenericParam, Item, ItemFn, ItemImpl, ItemUse, Meta, Token, UseTree, Visibility,
+};
 
-/// Discover everything from a file, will explore folder recursively
-pub fn discover_from_file(
-    src_path: String,
-    crate_name: String,
-    params: &Parameters,
-) -> (Vec<syn::Path>, Vec<syn::Path>, Vec<syn::Path>) {
-    let files = parse_files(&src_path).unwrap_or_else(|_| panic!("Failed to parse file {}", src_path));
+/// Everything discovered in a set of files
+#[derive(Default)]
+pub struct Discovered {
+    pub fns: Vec<syn::Path>,
+    pub models: Vec<syn::Path>,
+    pub responses: Vec<syn::Path>,
+    pub modifiers: Vec<syn::Path>,
+    /// The manual implementations routed to their own list, see `Parameters::custom_impls`
+    pub custom_impls: Vec<syn::Path>,
+    /// The schemas with generic parameters, only emitted through their `Parameters::generic_schemas` instantiations
+    pub generic_models: Vec<syn::Path>,
+    /// The cfg predicates the discovery depends on, see `Parameters::cfg_values`
+    pub cfgs: Vec<String>,
+    /// The body types of the discovered `IntoResponses`, with the path of their owner
+    pub response_bodies: Vec<(syn::Path, syn::Type)>,
+    /// The routes declared by the discovered handlers, e.g. `GET /users/{id}`, with the path of their handler
+    pub routes: Vec<(syn::Path, String)>,
+    /// The tags of the discovered handlers, with the path of their handler
+    pub tags: Vec<(syn::Path, String)>,
+    /// The structs deriving OpenApi, see `Discovered::retain_sub_docs`
+    pub sub_docs: Vec<syn::Path>,
+    pub warnings: Vec<Warning>,
+    /// Where the modules and items are visible from, see `visibility_scope`
+    pub(crate) scopes: Vec<(syn::Path, Option<syn::Path>)>,
+    /// The structs and functions annotated with #[utoipauto]
+    pub(crate) doc_structs: Vec<syn::Path>,
+    /// The scanned files and directories, with the `tracked-path` feature, see `tracked_sources`
+    pub(crate) sources: Vec<String>,
+    /// The files spliced with `include!`, which the path of the discovery may not contain, see `splice_includes`
+    pub(crate) included: Vec<String>,
+}
 
-    files
-        .into_iter()
-        .map(|e| parse_module_items(extract_module_name_from_path(&e.0, &crate_name), e.1.items, params))
-        .fold(Vec::<DiscoverType>::new(), |mut acc, mut v| {
-            acc.append(&mut v);
-            acc
-        })
-        .into_iter()
-        .fold(
-            (
-                Vec::<syn::Path>::new(),
-                Vec::<syn::Path>::new(),
-                Vec::<syn::Path>::new(),
-            ),
-            |mut acc, v| {
-                match v {
-                    DiscoverType::Fn(n) => acc.0.push(n),
-                    DiscoverType::Model(n) => acc.1.push(n),
-                    DiscoverType::Response(n) => acc.2.push(n),
-                    DiscoverType::CustomModelImpl(n) => acc.1.push(n),
-                    DiscoverType::CustomResponseImpl(n) => acc.2.push(n),
+impl Discovered {
+    pub fn extend(&mut self, other: Discovered) {
+        self.fns.extend(other.fns);
+        self.models.extend(other.models);
+        self.responses.extend(other.responses);
+        self.modifiers.extend(other.modifiers);
+        self.custom_impls.extend(other.custom_impls);
+        self.generic_models.extend(other.generic_models);
+        for cfg in other.cfgs {
+            if !self.cfgs.contains(&cfg) {
+                self.cfgs.push(cfg);
+            }
+        }
+        self.response_bodies.extend(other.response_bodies);
+        self.routes.extend(other.routes);
+        self.tags.extend(other.tags);
+        self.sub_docs.extend(other.sub_docs);
+        self.warnings.extend(other.warnings);
+        self.scopes.extend(other.scopes);
+        self.doc_structs.extend(other.doc_structs);
+        self.sources.extend(other.sources);
+        self.included.extend(other.included);
+    }
+
+    /// Whether nothing was discovered, besides the findings
+    pub fn is_empty(&self) -> bool {
+        self.fns.is_empty()
+            && self.models.is_empty()
+            && self.responses.is_empty()
+            && self.modifiers.is_empty()
+            && self.custom_impls.is_empty()
+    }
+
+    fn push_custom_impl(&mut self, target: ImplTarget, path: syn::Path) {
+        match target {
+            ImplTarget::Schemas => self.models.push(path),
+            ImplTarget::Responses => self.responses.push(path),
+            ImplTarget::Custom => self.custom_impls.push(path),
+            ImplTarget::None => {}
+        }
+    }
+
+    /// Keep the discovered docs to compose into the doc struct, and remove their items : the ones of their module,
+    /// or of its submodules, are left to them.
+    ///
+    /// The docs of the module of the doc struct, or of its parents, are not composed, they would take all its items.
+    pub fn retain_sub_docs(&mut self, params: &Parameters) {
+        let doc_module = self.doc_module(params);
+        self.sub_docs
+            .retain(|sub_doc| !starts_with(&doc_module, &module_of(sub_doc)));
+        let mut seen = vec![];
+        self.sub_docs.retain(|sub_doc| {
+            let sub_doc = path_to_string(sub_doc);
+            let is_new = !seen.contains(&sub_doc);
+            seen.push(sub_doc);
+            is_new
+        });
+
+        let sub_docs = &self.sub_docs;
+        let is_kept = |item: &syn::Path| !sub_docs.iter().any(|sub_doc| starts_with(item, &module_of(sub_doc)));
+        let reason = "left to the doc of its module";
+        debug::retain_logged(&mut self.fns, reason, is_kept);
+        debug::retain_logged(&mut self.models, reason, is_kept);
+        debug::retain_logged(&mut self.responses, reason, is_kept);
+        debug::retain_logged(&mut self.modifiers, reason, is_kept);
+        debug::retain_logged(&mut self.custom_impls, reason, is_kept);
+    }
+
+    /// The module of the doc struct, or `crate` if it was not discovered
+    pub fn doc_module(&self, params: &Parameters) -> syn::Path {
+        params
+            .doc_struct
+            .as_ref()
+            .and_then(|doc_struct| {
+                self.doc_structs
+                    .iter()
+                    .find(|path| path.segments.last().is_some_and(|segment| segment.ident == *doc_struct))
+            })
+            .map_or_else(|| syn::parse_quote!(crate), module_of)
+    }
+
+    /// Remove the discovered items found several times, or already `listed` by hand in the openapi macro
+    pub fn dedup(&mut self, listed: &[String]) {
+        // Modifiers can't be listed by name in the openapi macro, see `build_modifier_aliases`
+        let lists = [
+            (&mut self.fns, listed),
+            (&mut self.models, listed),
+            (&mut self.responses, listed),
+            (&mut self.modifiers, &[][..]),
+        ];
+        for (items, listed) in lists {
+            let mut seen = listed.to_vec();
+            items.retain(|item| {
+                let item = path_to_string(item);
+                let is_new = !seen.contains(&item);
+                if !is_new {
+                    let reason = match listed.contains(&item) {
+                        true => "already listed by hand",
+                        false => "discovered twice",
+                    };
+                    debug::log(format_args!("skipped {} ({})", item, reason));
+                }
+                seen.push(item);
+                is_new
+            });
+        }
+    }
+
+    /// Report the items that are not visible from the module of the doc struct, the emitted paths wouldn't resolve.
+    ///
+    /// The doc struct is looked up in the discovered files, and assumed to be at the crate root if it isn't found.
+    pub fn check_visibility(&mut self, params: &Parameters) {
+        let doc_module = self.doc_module(params);
+        // utoipa only needs the public `__path_*` struct of a handler, not the function itself
+        let handlers = self.fns.iter().map(|item| (item, item.segments.len() - 1));
+        let items = self.models.iter().chain(&self.responses).chain(&self.modifiers);
+        for (item, checked_len) in handlers.chain(items.map(|item| (item, item.segments.len()))) {
+            let private = (2..=checked_len).find_map(|len| {
+                let prefix = syn::Path {
+                    leading_colon: None,
+                    segments: item.segments.iter().take(len).cloned().collect(),
+                };
+                let prefix_string = path_to_string(&prefix);
+                self.scopes
+                    .iter()
+                    .find(|(path, _)| path_to_string(path) == prefix_string)
+                    .and_then(|(_, scope)| scope.as_ref())
+                    .filter(|scope| !starts_with(&doc_module, scope))
+                    .map(|_| prefix_string)
+            });
+            if let Some(private) = private {
+                let message = match private == path_to_string(item) {
+                    true => format!("`{}` is private", private),
+                    false => format!("`{}` is in the private module `{}`", path_to_string(item), private),
+                };
+                self.warnings.push(Warning::new(
+                    WarningKind::PrivateItem,
+                    format!(
+                        "{} and can't be used from `{}`, make it `pub(crate)` or re-export it",
+                        message,
+                        path_to_string(&doc_module)
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Emit the items of other crates through their re-exports by the facade crate, see `reroot`.
+    ///
+    /// The doc struct must be in the facade, the package in `facade_dir`, whose sources are in its `src`.
+    pub fn reroot(&mut self, facade: &str, facade_dir: &str, params: &Parameters) -> syn::Result<()> {
+        let package = Manifest::from_path(format!("{}/Cargo.toml", facade_dir))
+            .ok()
+            .and_then(|manifest| manifest.lib_name());
+        if let Some(package) = package.filter(|package| *package != facade.replace('-', "_")) {
+            return Err(diagnostics::error(format!(
+                "reroot = \"{}\" expects the doc struct in the facade crate, but it is in `{}`",
+                facade, package
+            )));
+        }
+        // Only the re-exports matter here, the unreadable files are left out silently
+        let facade_src = format!("{}/src", facade_dir);
+        let items = parse_modules(&facade_src, &ScanRoot::Crate("crate".to_string()), params)?
+            .0
+            .into_iter()
+            .map(|file| file.discover_items(params));
+        let reexports: Vec<Reexport> = diagnostics::collect_results(items)?
+            .into_iter()
+            .flatten()
+            .filter_map(|item| match item {
+                DiscoverType::Reexport(reexport) => Some(reexport),
+                _ => None,
+            })
+            .collect();
+        self.reroot_paths(facade, &reexports)
+    }
+
+    fn reroot_paths(&mut self, facade: &str, reexports: &[Reexport]) -> syn::Result<()> {
+        for path in self
+            .fns
+            .iter_mut()
+            .chain(self.models.iter_mut())
+            .chain(self.responses.iter_mut())
+            .chain(self.modifiers.iter_mut())
+            .chain(self.custom_impls.iter_mut())
+        {
+            if path.segments.first().is_some_and(|segment| segment.ident == "crate") {
+                continue;
+            }
+            *path = match reroot_path(path, reexports) {
+                // The facade may itself re-export its modules
+                Some(rerooted) => resolve_reexports(&rerooted, reexports),
+                None => {
+                    return Err(diagnostics::error(format!(
+                        "`{}` is not re-exported by the facade crate `{}`, add `pub use {};` to it",
+                        path_to_string(path),
+                        facade,
+                        path_to_string(path)
+                    )))
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// Add the instantiations of the generic schemas to the schemas, e.g. `Paginated<User>`.
+    ///
+    /// A path written as a single name, either the generic schema or one of its type arguments, is the discovered
+    /// schema with this name. The other paths are resolved from the module of the doc struct.
+    pub fn instantiate_generic_models(&mut self, instances: &[syn::Path], doc_module: &syn::Path) -> syn::Result<()> {
+        for instance in instances {
+            let instantiated = self.resolve_instance(instance, doc_module).ok_or_else(|| {
+                diagnostics::error(format!(
+                    "`{}` of generic_schemas is not a discovered generic schema",
+                    path_to_string(instance)
+                ))
+            })?;
+            self.models.push(instantiated);
+        }
+        Ok(())
+    }
+
+    fn resolve_instance(&self, path: &syn::Path, doc_module: &syn::Path) -> Option<syn::Path> {
+        let last = path.segments.last()?;
+        let is_generic = !matches!(last.arguments, syn::PathArguments::None);
+        let mut resolved = match path.segments.len() {
+            1 => {
+                let candidates = match is_generic {
+                    true => &self.generic_models,
+                    false => &self.models,
+                };
+                let same_name = |candidate: &&syn::Path| {
+                    candidate
+                        .segments
+                        .last()
+                        .is_some_and(|segment| segment.ident == last.ident)
                 };
+                match candidates.iter().find(same_name) {
+                    Some(candidate) => candidate.clone(),
+                    // A type argument may be a primitive or a type of the prelude, e.g. `String`
+                    None if !is_generic => return Some(path.clone()),
+                    None => return None,
+                }
+            }
+            _ => canonicalize(path, doc_module),
+        };
+        let mut arguments = last.arguments.clone();
+        if let syn::PathArguments::AngleBracketed(angle_bracketed) = &mut arguments {
+            for argument in angle_bracketed.args.iter_mut() {
+                if let syn::GenericArgument::Type(syn::Type::Path(type_path)) = argument {
+                    if type_path.qself.is_none() {
+                        type_path.path = self
+                            .resolve_instance(&type_path.path, doc_module)
+                            .unwrap_or_else(|| type_path.path.clone());
+                    }
+                }
+            }
+        }
+        resolved.segments.last_mut()?.arguments = arguments;
+        Some(resolved)
+    }
 
-                acc
-            },
-        )
+    /// Keep the discovered items matching the filter
+    pub fn retain_matching(&mut self, filter: &Filter) {
+        let strings = |pairs: &[(syn::Path, String)], handler: &str| -> Vec<String> {
+            pairs
+                .iter()
+                .filter(|(owner, _)| path_to_string(owner) == handler)
+                .map(|(_, value)| value.clone())
+                .collect()
+        };
+        let (routes, tags) = (&self.routes, &self.tags);
+        debug::retain_logged(&mut self.fns, "filter", |handler| {
+            let path = path_to_string(handler);
+            let (methods, routes): (Vec<String>, Vec<String>) = strings(routes, &path)
+                .iter()
+                .filter_map(|route| route.split_once(' '))
+                .map(|(method, route)| (method.to_lowercase(), route.to_string()))
+                .unzip();
+            filter.matches(&FilterItem {
+                kind: "handler",
+                tags: strings(tags, &path),
+                path,
+                methods,
+                routes,
+            })
+        });
+        let others = [
+            ("schema", &mut self.models),
+            ("response", &mut self.responses),
+            ("modifier", &mut self.modifiers),
+            ("custom_impl", &mut self.custom_impls),
+        ];
+        for (kind, items) in others {
+            debug::retain_logged(items, "filter", |item| {
+                filter.matches(&FilterItem {
+                    kind,
+                    path: path_to_string(item),
+                    ..Default::default()
+                })
+            });
+        }
+    }
+
+    /// Report the discovered schemas of different modules with the same name, the components would only keep one
+    pub fn check_schema_names(&mut self) {
+        let mut seen: Vec<(String, String)> = vec![];
+        for model in &self.models {
+            let (path, name) = (path_to_string(model), model.segments.last().unwrap().ident.to_string());
+            match seen.iter().find(|(_, other)| *other == name) {
+                Some((other, _)) if *other != path => self.warnings.push(Warning::new(
+                    WarningKind::DuplicateSchema,
+                    format!(
+                        "`{}` and `{}` are both named `{}`, rename one with `#[schema(as = ...)]`",
+                        other, path, name
+                    ),
+                )),
+                Some(_) => {}
+                None => seen.push((path, name)),
+            }
+        }
+    }
+
+    /// Report the discovered handlers declaring the same route, only one of them would be documented
+    pub fn check_routes(&mut self) {
+        let handlers: Vec<String> = self.fns.iter().map(path_to_string).collect();
+        let routes: Vec<(String, &String)> = self
+            .routes
+            .iter()
+            .map(|(handler, route)| (path_to_string(handler), route))
+            .filter(|(handler, _)| handlers.contains(handler))
+            .collect();
+        for (idx, (handler, route)) in routes.iter().enumerate() {
+            if let Some((first, _)) = routes[..idx]
+                .iter()
+                .find(|(other, other_route)| other_route == route && other != handler)
+            {
+                self.warnings.push(Warning::new(
+                    WarningKind::ConflictingRoute,
+                    format!("`{}` and `{}` both declare `{}`", first, handler, route),
+                ));
+            }
+        }
+    }
+
+    /// Report the endpoints of the `previous` documentation no handler declares anymore, among the discovered handlers
+    /// and the ones `listed` by hand.
+    ///
+    /// The previous documentation may have been built with prefixes added by `nest`, so an endpoint is still declared
+    /// if its path ends with the path of a route of the same method.
+    pub fn check_removed_endpoints(&mut self, previous: &Spec, listed: &[String]) {
+        let handlers: Vec<String> = self
+            .fns
+            .iter()
+            .map(path_to_string)
+            .chain(listed.iter().cloned())
+            .collect();
+        let routes: Vec<(&str, &str)> = self
+            .routes
+            .iter()
+            .filter(|(handler, _)| handlers.contains(&path_to_string(handler)))
+            .filter_map(|(_, route)| route.split_once(' '))
+            .collect();
+        for endpoint in &previous.endpoints {
+            let Some((method, path)) = endpoint.split_once(' ') else {
+                continue;
+            };
+            let is_declared = routes.iter().any(|(route_method, route_path)| {
+                *route_method == method && (*route_path == path || (*route_path != "/" && path.ends_with(route_path)))
+            });
+            if !is_declared {
+                self.warnings.push(Warning::new(
+                    WarningKind::RemovedEndpoint,
+                    format!("`{}` was removed since the previous documentation", endpoint),
+                ));
+            }
+        }
+    }
+
+    /// Report the types used as response bodies which aren't discovered schemas,
+    /// they would otherwise only show up as dangling references in the documentation
+    pub fn check_response_bodies(&mut self) {
+        let schemas: Vec<String> = self
+            .models
+            .iter()
+            .filter_map(|model| model.segments.last().map(|segment| segment.ident.to_string()))
+            .collect();
+        for (owner, body) in &self.response_bodies {
+            for name in schema_names(body) {
+                if !schemas.contains(&name) {
+                    self.warnings.push(Warning::new(
+                        WarningKind::UndiscoveredSchema,
+                        format!(
+                            "`{}` is a response body of `{}`, but is not a discovered schema",
+                            name,
+                            path_to_string(owner)
+                        ),
+                    ));
+                }
+            }
+        }
+    }
 }
 
-#[allow(unused)]
-enum DiscoverType {
-    Fn(syn::Path),
-    Model(syn::Path),
-    Response(syn::Path),
-    CustomModelImpl(syn::Path),
-    CustomResponseImpl(syn::Path),
+/// The names of the schemas referenced by a type, e.g. `User` for `Vec<User>`
+fn schema_names(ty: &syn::Type) -> Vec<String> {
+    const NOT_SCHEMAS: &[&str] = &[
+        "bool", "char", "str", "String", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128",
+        "usize", "f32", "f64", "Value",
+    ];
+    match ty {
+        syn::Type::Path(type_path) => {
+            let Some(segment) = type_path.path.segments.last() else {
+                return vec![];
+            };
+            match &segment.arguments {
+                syn::PathArguments::AngleBracketed(arguments) => arguments
+                    .args
+                    .iter()
+                    .filter_map(|argument| match argument {
+                        syn::GenericArgument::Type(ty) => Some(schema_names(ty)),
+                        _ => None,
+                    })
+                    .flatten()
+                    .collect(),
+                _ if NOT_SCHEMAS.contains(&segment.ident.to_string().as_str()) => vec![],
+                _ => vec![segment.ident.to_string()],
+            }
+        }
+        syn::Type::Array(array) => schema_names(&array.elem),
+        syn::Type::Slice(slice) => schema_names(&slice.elem),
+        syn::Type::Reference(reference) => schema_names(&reference.elem),
+        syn::Type::Paren(paren) => schema_names(&paren.elem),
+        syn::Type::Tuple(tuple) => tuple.elems.iter().flat_map(schema_names).collect(),
+        _ => vec![],
+    }
 }
 
-fn parse_module_items(module_path: syn::Path, items: Vec<Item>, params: &Parameters) -> Vec<DiscoverType> {
-    items
-        .into_iter()
-        .filter(|e| {
-            matches!(
-                e,
-                Item::Mod(_) | Item::Fn(_) | Item::Struct(_) | Item::Enum(_) | Item::Impl(_)
-            )
+/// Where the modules of the scanned files come from
+enum ScanRoot {
+    /// The path of each file in the crate, e.g. `crate::api` for `./src/api.rs`
+    Crate(String),
+    /// The module the files are spliced into with `include!`, whatever their path
+    Included(syn::Path),
+    /// The path of each file under the directory of the root file of the crate, or the root file itself
+    CrateRoot(String, String),
+}
+
+impl ScanRoot {
+    /// What distinguishes the root in the keys of the caches
+    fn key(&self) -> String {
+        match self {
+            ScanRoot::Crate(crate_name) => crate_name.clone(),
+            ScanRoot::Included(module) => format!("as {}", path_to_string(module)),
+            ScanRoot::CrateRoot(crate_name, _) => format!("in {}", crate_name),
+        }
+    }
+}
+
+/// Discover everything from a file, will explore folder recursively
+pub fn discover_from_file(src_path: String, crate_name: String, params: &Parameters) -> syn::Result<Discovered> {
+    discover_from_root(src_path, ScanRoot::Crate(crate_name), params)
+}
+
+/// Discover everything from the files of a path, all included into the module with `include!`, e.g. the code
+/// generated in `OUT_DIR`
+pub fn discover_from_included(src_path: String, module: &str, params: &Parameters) -> syn::Result<Discovered> {
+    let module = syn::parse_str(module).map_err(|_| {
+        diagnostics::error(format!(
+            "Invalid module `{}` of `{}`, expected a path",
+            module, src_path
+        ))
+    })?;
+    discover_from_root(src_path, ScanRoot::Included(module), params)
+}
+
+/// Discover everything from the root file of a crate, or the directory it is in, e.g. the one of a custom `[lib] path`
+/// or of a binary
+pub fn discover_from_crate_root(src_path: String, crate_name: String, params: &Parameters) -> syn::Result<Discovered> {
+    let root = ScanRoot::CrateRoot(crate_name, normalize_file_path(&src_path));
+    discover_from_root(src_path, root, params)
+}
+
+fn discover_from_root(src_path: String, root: ScanRoot, params: &Parameters) -> syn::Result<Discovered> {
+    // Under rust-analyzer, without even checking the files
+    if params.rust_analyzer == IdeMode::Cached && is_rust_analyzer() {
+        if let Some(discovered) = cache::recall_stale(&src_path, &root.key(), params) {
+            debug::log(format_args!("reused the discovery of {} for rust-analyzer", src_path));
+            return Ok(discovered);
+        }
+    }
+    // The other doc structs of the crate may scan the same path
+    let memo_key = cache::memo_key(&src_path, &root.key(), params);
+    if let Some(discovered) = memo_key.and_then(cache::recall) {
+        debug::log(format_args!("reused the discovery of {} made earlier", src_path));
+        return Ok(discovered);
+    }
+    // The crates of the registry can't change without a version bump
+    let cache_key = cache::cache_key(&src_path, &root.key(), params);
+    let discovered = match cache_key.as_deref().and_then(cache::load) {
+        Some(discovered) => {
+            debug::log(format_args!("loaded the discovery of {} from the cache", src_path));
+            discovered
+        }
+        None => {
+            let discovered = scan(src_path, &root, params)?;
+            // An included file may be generated in `OUT_DIR`, outside of the sources of the version
+            if let (Some(cache_key), true) = (&cache_key, discovered.included.is_empty()) {
+                cache::store(cache_key, &discovered);
+            }
+            discovered
+        }
+    };
+    if let Some(memo_key) = memo_key {
+        cache::remember(memo_key, &discovered);
+    }
+    #[cfg(feature = "tracked-path")]
+    crate::file_utils::track_sources(&discovered.sources);
+    Ok(discovered)
+}
+
+/// Discover everything from the files of the path
+fn scan(src_path: String, root: &ScanRoot, params: &Parameters) -> syn::Result<Discovered> {
+    let (files, skipped, included) = parse_modules(&src_path, root, params)?;
+    let modules: Vec<(String, syn::Path)> = files
+        .iter()
+        .map(|file| (file.file_path.clone(), file.module_path.clone()))
+        .collect();
+
+    let mut discovered = discover_from_modules(files, params)?;
+    if debug::is_enabled() {
+        let lists = [
+            ("handler", &discovered.fns),
+            ("schema", &discovered.models),
+            ("response", &discovered.responses),
+            ("modifier", &discovered.modifiers),
+            ("custom implementation", &discovered.custom_impls),
+        ];
+        for (kind, items) in lists {
+            for item in items {
+                debug::log(format_args!("discovered the {} {}", kind, path_to_string(item)));
+            }
+        }
+    }
+    discovered.warnings.extend(skipped);
+    discovered.included = included;
+    check_scan_root(&src_path, &modules, &mut discovered, params);
+    #[cfg(feature = "tracked-path")]
+    {
+        let files = modules.iter().map(|(file_path, _)| file_path.as_str());
+        discovered.sources = crate::file_utils::tracked_sources(&src_path, files);
+    }
+    Ok(discovered)
+}
+
+/// A scanned file, along with its path and the path of its module
+struct ModuleFile {
+    file_path: String,
+    module_path: syn::Path,
+    /// The `mod foo;` declarations of the file, with the value of their `#[path]` attribute
+    declarations: Vec<(Ident, Option<String>)>,
+    contents: FileContents,
+    /// The hash of the content of the file, with `Parameters::parse_cache`
+    hash: Option<u64>,
+}
+
+enum FileContents {
+    Parsed(syn::File),
+    /// The content of a file whose declarations were loaded from the parse cache, only parsed if its items aren't
+    Source(String),
+    /// A file which can't contribute anything, see `may_contribute`
+    Skipped,
+}
+
+impl ModuleFile {
+    fn parsed(file_path: String, module_path: syn::Path, file: syn::File) -> Self {
+        ModuleFile {
+            file_path,
+            module_path,
+            declarations: module_declarations(&file),
+            contents: FileContents::Parsed(file),
+            hash: None,
+        }
+    }
+
+    fn skipped(file_path: String, module_path: syn::Path) -> Self {
+        ModuleFile {
+            file_path,
+            module_path,
+            declarations: vec![],
+            contents: FileContents::Skipped,
+            hash: None,
+        }
+    }
+
+    /// The file, its declarations loaded from the parse cache, or parsed and stored in it
+    fn cached(file_path: String, module_path: syn::Path, content: String) -> Result<Self, ParseFailure> {
+        let hash = cache::content_hash(&content);
+        let mut file = match cache::load_declarations(hash) {
+            Some(declarations) => ModuleFile {
+                file_path,
+                module_path,
+                declarations,
+                contents: FileContents::Source(content),
+                hash: None,
+            },
+            None => {
+                let parsed = parse_source(&file_path, &content)?;
+                let file = ModuleFile::parsed(file_path, module_path, parsed);
+                cache::store_declarations(hash, &file.declarations);
+                file
+            }
+        };
+        file.hash = Some(hash);
+        Ok(file)
+    }
+
+    /// Discover the items of the file, or load them from the parse cache
+    fn discover_items(self, params: &Parameters) -> syn::Result<Vec<DiscoverType>> {
+        let key = self.hash.map(|hash| cache::items_key(hash, &self.module_path, params));
+        if let Some(items) = key.as_deref().and_then(cache::load_items) {
+            debug::log(format_args!(
+                "loaded the items of {} from the parse cache",
+                self.file_path
+            ));
+            return Ok(items);
+        }
+        let file_path = self.file_path;
+        let items = match self.contents {
+            FileContents::Parsed(file) => Ok(file),
+            FileContents::Source(content) => parse_source(&file_path, &content).map_err(syn::Error::from),
+            FileContents::Skipped => return Ok(vec![]),
+        }
+        .and_then(|file| parse_module_items(self.module_path, file.items, 0, params))
+        .map_err(|e| diagnostics::in_source(e, &file_path))?;
+        if let Some(key) = &key {
+            cache::store_items(key, &items);
+        }
+        Ok(items)
+    }
+}
+
+/// Whether the content of a file may contribute anything to the discovery, checked without parsing it.
+///
+/// A file can't if it mentions none of the attributes, derives, traits and macros looked for, and declares no module,
+/// no re-export, no unit struct, which may be a modifier, and includes no file. Comments and strings mentioning them keep the file.
+fn may_contribute(content: &str, params: &Parameters) -> bool {
+    let mut names = params
+        .fn_attribute_names
+        .iter()
+        .chain([&params.schema_attribute_name, &params.response_attribute_name])
+        .chain(params.schema_derives.iter().chain(&params.response_derives))
+        .map(String::as_str)
+        .chain(params.utoipa_crate.last().map(String::as_str))
+        .chain(["utoipa", "OpenApi", "IntoResponses", "Modify"])
+        .chain(params.macros.iter().map(|template| template.name.as_str()));
+    names.any(|name| content.contains(name))
+        || (params.checks(WarningKind::UndocumentedRoute)
+            && params
+                .route_attributes
+                .iter()
+                .any(|name| content.contains(name.as_str())))
+        || word_positions(content, "mod").next().is_some()
+        || content.contains("include!")
+        || word_positions(content, "use").any(|idx| {
+            // `pub use`, or `pub(crate) use`
+            let before = content[..idx].trim_end();
+            before.ends_with("pub") || before.ends_with(')')
         })
-        .map(|v| match v {
-            Item::Mod(m) => m.content.map_or(Vec::<DiscoverType>::new(), |cs| {
-                parse_module_items(build_path(&module_path, &m.ident), cs.1, params)
-            }),
-            Item::Fn(f) => parse_function(&f, &params.fn_attribute_name)
-                .into_iter()
-                .map(|item| DiscoverType::Fn(build_path(&module_path, &item)))
-                .collect(),
-            Item::Struct(s) => parse_from_attr(&s.attrs, build_path(&module_path, &s.ident), s.generics.params, params),
-            Item::Enum(e) => parse_from_attr(&e.attrs, build_path(&module_path, &e.ident), e.generics.params, params),
-            Item::Impl(im) => parse_from_impl(&im, &module_path, params),
-            _ => vec![],
+        || word_positions(content, "struct").any(|idx| {
+            let rest = content[idx + "struct".len()..].trim_start();
+            let name_len = rest
+                .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '#')
+                .unwrap_or(rest.len());
+            rest[name_len..].trim_start().starts_with(';')
         })
-        .fold(Vec::<DiscoverType>::new(), |mut acc, mut v| {
-            acc.append(&mut v);
-            acc
+}
+
+/// The positions of the word in the content, not as a part of an identifier
+fn word_positions<'a>(content: &'a str, word: &'a str) -> impl Iterator<Item = usize> + 'a {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    content.match_indices(word).map(|(idx, _)| idx).filter(move |idx| {
+        !content[..*idx].ends_with(is_ident_char) && !content[idx + word.len()..].starts_with(is_ident_char)
+    })
+}
+
+/// The `mod foo;` declarations of the file, with the value of their `#[path]` attribute
+fn module_declarations(file: &syn::File) -> Vec<(Ident, Option<String>)> {
+    file.items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Mod(m) if m.content.is_none() => Some((m.ident.clone(), path_attribute(&m.attrs))),
+            _ => None,
         })
+        .collect()
 }
 
-/// Search for ToSchema and ToResponse implementations in attr
-fn parse_from_attr(
-    a: &Vec<Attribute>,
-    name: syn::Path,
-    generic_params: Punctuated<GenericParam, Comma>,
+/// Parse the files of the path, along with the path of their module, and the files they include.
+///
+/// The files that can't be read are skipped, and reported in the returned warnings. The ones that aren't valid Rust
+/// are errors.
+fn parse_modules(
+    src_path: &str,
+    root: &ScanRoot,
     params: &Parameters,
-) -> Vec<DiscoverType> {
-    let mut out: Vec<DiscoverType> = vec![];
-    if !generic_params.is_empty() {
-        return out;
+) -> syn::Result<(Vec<ModuleFile>, Vec<Warning>, Vec<String>)> {
+    check_root(src_path).map_err(|e| diagnostics::error(format!("Can't scan the path, {}", e)))?;
+    let mut skipped = vec![];
+    let mut excluded = params.exclude.clone();
+    let base = match is_glob(src_path) {
+        true => glob_base(src_path),
+        false => src_path.to_string(),
+    };
+    if !params.include_tests {
+        // The `tests` directories under the path, the path itself may be one
+        excluded.push(format!("{}/**/tests", base.trim_end_matches('/')));
     }
-
-    for attr in a {
-        let meta = &attr.meta;
-        if meta.path().is_ident("utoipa_ignore") {
-            return vec![];
+    let files = match is_glob(src_path) {
+        true => collect_files_matching_skipping(src_path, &params.extensions, &excluded, &mut skipped),
+        false => collect_files_skipping(src_path, &params.extensions, &excluded, &mut skipped),
+    }
+    .map_err(|e| diagnostics::error(format!("Failed to read `{}`: {}", src_path, e)))?;
+    // A path given explicitly is scanned even if it is ignored, e.g. `$OUT_DIR`
+    let files = match params.respect_gitignore && !retain_unignored(vec![PathBuf::from(&base)]).is_empty() {
+        true => retain_unignored(files),
+        false => files,
+    };
+    // Fail before parsing everything under a mistakenly broad path
+    if files.len() > params.max_files {
+        return Err(diagnostics::error(format!(
+            "`{}` contains {} files, more than the limit of {}. Narrow the path, or raise the limit with \
+             `max_files = {}`",
+            src_path,
+            files.len(),
+            params.max_files,
+            files.len()
+        )));
+    }
+    // The files of an `include!` are not modules, they can't conflict
+    let files = match root {
+        ScanRoot::Crate(crate_name) => resolve_module_layout(files, crate_name, params)?,
+        ScanRoot::Included(_) | ScanRoot::CrateRoot(..) => files,
+    };
+    let module_path = |file_path: &str| match root {
+        ScanRoot::Crate(crate_name) => {
+            extract_module_name_from_path_with(file_path, crate_name, &params.module_mapping)
         }
-        if meta.path().is_ident("derive") {
-            let nested = attr
-                .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
-                .expect("Failed to parse derive attribute");
-            for nested_meta in nested {
-                if nested_meta.path().segments.len() == 2 && nested_meta.path().segments[0].ident == "utoipa" {
-                    match nested_meta.path().segments[1].ident.to_string().as_str() {
-                        "ToSchema" => out.push(DiscoverType::Model(name.clone())),
-                        "ToResponse" => out.push(DiscoverType::Response(name.clone())),
-                        _ => {}
-                    }
-                } else {
-                    if nested_meta.path().is_ident(&params.schema_attribute_name) {
-                        out.push(DiscoverType::Model(name.clone()));
-                    }
-                    if nested_meta.path().is_ident(&params.response_attribute_name) {
-                        out.push(DiscoverType::Response(name.clone()));
-                    }
+        ScanRoot::Included(module) => module.clone(),
+        // Laid out like `src`, relative to the directory
+        ScanRoot::CrateRoot(crate_name, root) => {
+            let file_path = normalize_file_path(file_path);
+            match file_path.strip_prefix(root.as_str()) {
+                Some(relative) if !relative.is_empty() => {
+                    extract_module_name_from_path_with(&format!("src{}", relative), crate_name, &params.module_mapping)
                 }
+                _ => extract_module_name_from_path_with("src/lib.rs", crate_name, &params.module_mapping),
             }
         }
+    };
+    let is_cached = params.parse_cache && !cache::is_disabled();
+    let mut included = vec![];
+    let mut include_warnings = vec![];
+    let mut failures = vec![];
+    let files = read_readable_files(files, &mut skipped)
+        .into_iter()
+        .map(|(file_path, content)| {
+            let module_path = module_path(&file_path);
+            debug::log(format_args!(
+                "scanning {} as {}",
+                file_path,
+                path_to_string(&module_path)
+            ));
+            if !may_contribute(&content, params) {
+                debug::log(format_args!("skipped {}, it can't contribute any item", file_path));
+                return Ok(ModuleFile::skipped(file_path, module_path));
+            }
+            // The items of an included file aren't part of the hash of the cached ones
+            let from_cache = is_cached && !content.contains("include!");
+            let parsed = match from_cache {
+                true => ModuleFile::cached(file_path, module_path.clone(), content),
+                false => parse_source(&file_path, &content)
+                    .map(|file| ModuleFile::parsed(file_path, module_path.clone(), file)),
+            };
+            // The other files are still parsed, to report all the failures at once
+            let mut file = match parsed {
+                Ok(file) => file,
+                Err(failure) => {
+                    let file = ModuleFile::skipped(failure.file.clone(), module_path);
+                    failures.push(failure);
+                    return Ok(file);
+                }
+            };
+            if let (false, FileContents::Parsed(parsed)) = (from_cache, &mut file.contents) {
+                let mut chain = vec![normalize_file_path(&file.file_path)];
+                let items = std::mem::take(&mut parsed.items);
+                parsed.items =
+                    splice_includes(items, &mut chain, &mut included, &mut include_warnings, params.lenient)?;
+            }
+            Ok(file)
+        });
+    let files = diagnostics::collect_results(files);
+    if params.lenient {
+        include_warnings.extend(failures.drain(..).map(skipped_failure));
     }
+    let mut files = match (files, failures.is_empty()) {
+        (files, true) => files?,
+        (Ok(_), false) => return Err(parse_failures(failures)),
+        (Err(error), false) => {
+            let mut failures = parse_failures(failures);
+            failures.combine(error);
+            return Err(failures);
+        }
+    };
+    // An included file is part of the module of the `include!`, not a module of its own
+    files.retain(|file| !included.contains(&normalize_file_path(&file.file_path)));
 
-    out
+    let warnings = skipped
+        .into_iter()
+        .map(|skipped| {
+            Warning::new(
+                WarningKind::UnreadableFile,
+                format!(
+                    "`{}` was skipped, it can't be read: {}",
+                    skipped.path.display(),
+                    skipped.error
+                ),
+            )
+        })
+        .chain(include_warnings)
+        .collect();
+    Ok((apply_path_attributes(files), warnings, included))
 }
 
-fn parse_from_impl(im: &ItemImpl, module_base_path: &syn::Path, params: &Parameters) -> Vec<DiscoverType> {
-    im.trait_
-        .as_ref()
-        .and_then(|trt| trt.1.segments.last().map(|p| p.ident.to_string()))
-        .and_then(|impl_name| {
-            if impl_name.eq(params.schema_attribute_name.as_str()) {
-                Some(vec![DiscoverType::CustomModelImpl(build_path(
-                    module_base_path,
-                    &im.self_ty,
-                ))])
-            } else if impl_name.eq(params.response_attribute_name.as_str()) {
-                Some(vec![DiscoverType::CustomResponseImpl(build_path(
-                    module_base_path,
-                    &im.self_ty,
-                ))])
-            } else {
-                None
-            }
-        })
-        .unwrap_or_default()
+/// The warning of a file skipped with `lenient`, as it isn't valid Rust
+fn skipped_failure(failure: ParseFailure) -> Warning {
+    Warning::new(
+        WarningKind::UnparsableFile,
+        format!("Failed to parse {}, the file was skipped", failure.describe()),
+    )
 }
 
-fn parse_function(f: &ItemFn, fn_attributes_name: &str) -> Vec<Ident> {
-    let mut fns_name: Vec<Ident> = vec![];
-    if should_parse_fn(f) {
-        for i in 0..f.attrs.len() {
-            if f.attrs[i]
-                .meta
-                .path()
-                .segments
-                .iter()
-                .any(|item| item.ident.eq(fn_attributes_name))
+/// Replace the `include!("...")` among the items by the items of the included file, recursively, like rustc does.
+///
+/// The path is relative to the directory of the including file, the last one of the `chain` of includes, and may be
+/// built with `concat!` and `env!`, e.g. `include!(concat!(env!("OUT_DIR"), "/proto.rs"))`. The included files are
+/// recorded in `included`, the unreadable ones too so that their creation is noticed, and reported in `warnings`, as
+/// well as the invalid ones if `lenient`.
+fn splice_includes(
+    items: Vec<Item>,
+    chain: &mut Vec<String>,
+    included: &mut Vec<String>,
+    warnings: &mut Vec<Warning>,
+    lenient: bool,
+) -> syn::Result<Vec<Item>> {
+    let mut spliced = vec![];
+    for item in items {
+        match item {
+            Item::Macro(m)
+                if m.mac
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "include") =>
             {
-                fns_name.push(f.sig.ident.clone());
+                let Some(target) = m.mac.parse_body().ok().as_ref().and_then(literal_string) else {
+                    debug::log(format_args!("skipped an `include!` which can't be resolved"));
+                    continue;
+                };
+                let including = chain.last().expect("the chain starts with the scanned file");
+                let directory = including.rsplit_once('/').map_or(".", |(directory, _)| directory);
+                let target = normalize_file_path(&PathBuf::from(directory).join(target).to_string_lossy());
+                // A cycle is an error of rustc
+                if chain.contains(&target) {
+                    continue;
+                }
+                let content = match std::fs::read_to_string(&target) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        warnings.push(Warning::new(
+                            WarningKind::UnreadableFile,
+                            format!("The `include!` of `{}` was skipped, it can't be read: {}", target, e),
+                        ));
+                        included.push(target);
+                        continue;
+                    }
+                };
+                debug::log(format_args!("scanning {} included by {}", target, including));
+                let file = match parse_source(&target, &content) {
+                    Ok(file) => file,
+                    Err(failure) if lenient => {
+                        warnings.push(skipped_failure(failure));
+                        included.push(target);
+                        continue;
+                    }
+                    Err(failure) => return Err(failure.into()),
+                };
+                chain.push(target.clone());
+                spliced.extend(splice_includes(file.items, chain, included, warnings, lenient)?);
+                chain.pop();
+                included.push(target);
+            }
+            Item::Mod(mut m) if m.content.is_some() => {
+                let (brace, items) = m.content.take().unwrap();
+                m.content = Some((brace, splice_includes(items, chain, included, warnings, lenient)?));
+                spliced.push(Item::Mod(m));
             }
+            item => spliced.push(item),
         }
     }
-    fns_name
+    Ok(spliced)
 }
 
-fn should_parse_fn(f: &ItemFn) -> bool {
-    !f.attrs.is_empty() && !is_ignored(f)
+/// The value of a string literal, possibly built with `concat!` and `env!`
+fn literal_string(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(string),
+            ..
+        }) => Some(string.value()),
+        syn::Expr::Macro(m) => {
+            let args = m
+                .mac
+                .parse_body_with(Punctuated::<syn::Expr, Comma>::parse_terminated)
+                .ok()?;
+            match m.mac.path.get_ident()?.to_string().as_str() {
+                "concat" => args.iter().map(literal_string).collect(),
+                "env" => std::env::var(literal_string(args.first()?)?).ok(),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
 }
 
-fn is_ignored(f: &ItemFn) -> bool {
-    f.attrs.iter().any(|attr| {
-        if let Some(name) = attr.path().get_ident() {
-            name.eq("utoipa_ignore")
-        } else {
-            false
+/// Fix the module of the files included with `#[path = "..."] mod foo;`, which can't be derived from their location.
+///
+/// The path is relative to the directory of the declaring file. The plain `mod bar;` of an included file are looked up
+/// next to it, as rustc does, and are fixed too.
+fn apply_path_attributes(mut files: Vec<ModuleFile>) -> Vec<ModuleFile> {
+    let normalized: Vec<String> = files.iter().map(|file| normalize_file_path(&file.file_path)).collect();
+    let mut included = vec![false; files.len()];
+    let mut pending: Vec<usize> = (0..files.len()).collect();
+    // Each pass follows the declarations of the files fixed by the previous one, a file being fixed at most once
+    while !pending.is_empty() {
+        let mut fixed = vec![];
+        for idx in pending {
+            let ModuleFile {
+                file_path,
+                module_path,
+                declarations,
+                ..
+            } = &files[idx];
+            let directory = file_path.replace('\\', "/");
+            let directory = directory.rsplit_once('/').map_or(".", |(directory, _)| directory);
+            for (ident, path) in declarations {
+                let candidates = match path {
+                    Some(path) => vec![format!("{}/{}", directory, path)],
+                    None if included[idx] => vec![
+                        format!("{}/{}.rs", directory, ident),
+                        format!("{}/{}/mod.rs", directory, ident),
+                    ],
+                    None => continue,
+                };
+                let target = candidates.iter().find_map(|candidate| {
+                    let candidate = normalize_file_path(candidate);
+                    normalized.iter().position(|file| *file == candidate)
+                });
+                if let Some(target) = target.filter(|target| !included[*target]) {
+                    fixed.push((target, build_path(module_path, ident)));
+                }
+            }
         }
-    })
+        pending = vec![];
+        for (target, module_path) in fixed {
+            if !included[target] {
+                debug::log(format_args!(
+                    "{} is the module {}",
+                    files[target].file_path,
+                    path_to_string(&module_path)
+                ));
+                files[target].module_path = module_path;
+                included[target] = true;
+                pending.push(target);
+            }
+        }
+    }
+    files
 }
 
-fn build_path(file_path: &syn::Path, fn_name: impl ToTokens) -> syn::Path {
-    syn::parse_quote!(#file_path::#fn_name)
+/// The value of `#[path = "..."]`
+fn path_attribute(attrs: &[Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| match &attr.meta {
+        Meta::NameValue(name_value) if name_value.path.is_ident("path") => match &name_value.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(path),
+                ..
+            }) => Some(path.value()),
+            _ => None,
+        },
+        _ => None,
+    })
 }
 
-#[cfg(test)]
-mod test {
-    use quote::quote;
-    use syn::ItemFn;
+/// The path with `/` separators, without its `.` segments and with its `..` ones applied, to compare file paths
+fn normalize_file_path(path: &str) -> String {
+    let mut segments: Vec<&str> = vec![];
+    for segment in path.split(['/', '\\']) {
+        match segment {
+            "." | "" if !segments.is_empty() || segment == "." => {}
+            ".." if segments.last().is_some_and(|last| *last != "..") => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
 
-    #[test]
-    fn test_parse_function() {
-        let quoted = quote! {
-            #[utoipa]
-            pub fn route_custom() {}
+/// Keep a single file for the modules backed by both `foo.rs` and `foo/mod.rs`.
+///
+/// rustc only accepts this layout if one of them is included elsewhere with `#[path]`, so the right one can't be
+/// guessed : it must be chosen with `module_files`.
+fn resolve_module_layout(files: Vec<PathBuf>, crate_name: &str, params: &Parameters) -> syn::Result<Vec<PathBuf>> {
+    let normalize = |file: &str| file.replace('\\', "/").trim_start_matches("./").to_string();
+    let normalized: Vec<String> = files.iter().map(|file| normalize(&file.to_string_lossy())).collect();
+    let mut skipped = Vec::new();
+    for (idx, file) in normalized.iter().enumerate() {
+        let Some((directory, file_name)) = file.rsplit_once('/') else {
+            continue;
+        };
+        let Some(extension) = file_name.strip_prefix("mod.") else {
+            continue;
+        };
+        let sibling = format!("{}.{}", directory, extension);
+        let Some(sibling_idx) = normalized.iter().position(|other| *other == sibling) else {
+            continue;
         };
+        let module = path_to_string(&extract_module_name_from_path_with(
+            file,
+            crate_name,
+            &params.module_mapping,
+        ));
+        let chosen = params
+            .module_files
+            .iter()
+            .find(|(chosen_module, _)| *chosen_module == module)
+            .map(|(_, chosen)| normalize(chosen));
+        match chosen {
+            Some(chosen) if chosen == *file => skipped.push(sibling_idx),
+            Some(chosen) if chosen == sibling => skipped.push(idx),
+            _ => {
+                return Err(diagnostics::error(format!(
+                    "`./{}` and `./{}` are both the module `{}`. Remove one of them, or choose it with \
+                     `module_files = [\"{} => ./{}\"]`",
+                    sibling, file, module, module, sibling
+                )))
+            }
+        }
+    }
+    Ok(files
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| !skipped.contains(idx))
+        .map(|(_, file)| file)
+        .collect())
+}
 
-        let item_fn: ItemFn = syn::parse2(quoted).unwrap();
-        let fn_name = super::parse_function(&item_fn, "utoipa");
-        assert_eq!(fn_name, vec!["route_custom"]);
+/// Discover everything from a source snippet, as if it were the content of the module `module_path`
+///
+/// # Example
+/// ```
+/// use utoipauto_core::{discover::discover_from_str, path_utils::path_to_string, token_utils::extract_attributes};
+/// let params = extract_attributes(proc_macro2::TokenStream::new()).unwrap();
+/// let discovered = discover_from_str(
+///     r#"
+///     #[utoipa::path(get, path = "/users")]
+///     pub fn get_users() {}
+///
+///     #[derive(utoipa::ToSchema)]
+///     pub struct User;
+///     "#,
+///     "crate::api",
+///     &params,
+/// )
+/// .unwrap();
+/// assert_eq!(path_to_string(&discovered.fns[0]), "crate::api::get_users");
+/// assert_eq!(path_to_string(&discovered.models[0]), "crate::api::User");
+/// ```
+pub fn discover_from_str(source: &str, module_path: &str, params: &Parameters) -> syn::Result<Discovered> {
+    let file: syn::File = syn::parse_str(source)?;
+    let module_path: syn::Path = syn::parse_str(module_path)?;
+    discover_from_syn(file, module_path, params)
+}
 
-        let quoted = quote! {
-            #[handler]
-            pub fn route_custom() {}
-        };
+/// Discover everything from tokens, e.g. the code a proc macro is about to emit, as if they were the content of the
+/// module `module_path`
+pub fn discover_from_tokens(
+    tokens: TokenStream,
+    module_path: syn::Path,
+    params: &Parameters,
+) -> syn::Result<Discovered> {
+    discover_from_syn(syn::parse2(tokens)?, module_path, params)
+}
 
-        let item_fn: ItemFn = syn::parse2(quoted).unwrap();
-        let fn_name = super::parse_function(&item_fn, "handler");
-        assert_eq!(fn_name, vec!["route_custom"]);
+/// Discover everything from a parsed file, as if it were the content of the module `module_path`
+pub fn discover_from_syn(file: syn::File, module_path: syn::Path, params: &Parameters) -> syn::Result<Discovered> {
+    discover_from_modules(
+        vec![ModuleFile::parsed(path_to_string(&module_path), module_path, file)],
+        params,
+    )
+}
+
+/// Discover everything from parsed files, along with their path and the path of their module.
+///
+/// The errors of all the files are combined, each naming its file.
+fn discover_from_modules(files: Vec<ModuleFile>, params: &Parameters) -> syn::Result<Discovered> {
+    let mut reexports = Vec::new();
+    let mut gated_modules = Vec::new();
+    let mut modifier_impls = Vec::new();
+    let mut unit_structs = Vec::new();
+    let items = files.into_iter().map(|file| file.discover_items(params));
+    let mut discovered =
+        diagnostics::collect_results(items)?
+            .into_iter()
+            .flatten()
+            .fold(Discovered::default(), |mut acc, v| {
+                match v {
+                    DiscoverType::Fn(n) => acc.fns.push(n),
+                    DiscoverType::Model(n) => acc.models.push(n),
+                    DiscoverType::Response(n) => acc.responses.push(n),
+                    DiscoverType::CustomModelImpl(n) => acc.push_custom_impl(params.custom_impls.schemas, n),
+                    DiscoverType::CustomResponseImpl(n) => acc.push_custom_impl(params.custom_impls.responses, n),
+                    DiscoverType::GenericModel(n) => acc.generic_models.push(n),
+                    DiscoverType::Modifier(n) => acc.modifiers.push(n),
+                    DiscoverType::ModifierImpl(n) => modifier_impls.push(n),
+                    DiscoverType::UnitStruct(n) => unit_structs.push(path_to_string(&n)),
+                    DiscoverType::Reexport(r) => reexports.push(r),
+                    DiscoverType::ResponseBody(owner, body) => acc.response_bodies.push((owner, body)),
+                    DiscoverType::Route(handler, route) => acc.routes.push((handler, route)),
+                    DiscoverType::Tag(handler, tag) => acc.tags.push((handler, tag)),
+                    DiscoverType::Scope(path, scope) => acc.scopes.push((path, scope)),
+                    DiscoverType::DocStruct(path) => acc.doc_structs.push(path),
+                    DiscoverType::SubDoc(path) => acc.sub_docs.push(path),
+                    DiscoverType::Warning(warning) => acc.warnings.push(warning),
+                    DiscoverType::Cfg(cfg) if !acc.cfgs.contains(&cfg) => acc.cfgs.push(cfg),
+                    DiscoverType::Cfg(_) => {}
+                    DiscoverType::GatedModule(module) => gated_modules.push(module),
+                };
+
+                acc
+            });
+
+    // `modifiers(&Addon)` needs a value, which only a unit struct has by its name
+    for modifier in modifier_impls {
+        match unit_structs.contains(&path_to_string(&modifier)) {
+            true => discovered.modifiers.push(modifier),
+            false => debug::log(format_args!(
+                "skipped the modifier {}, it is not a unit struct of the scanned files",
+                path_to_string(&modifier)
+            )),
+        }
+    }
+
+    if !gated_modules.is_empty() {
+        let is_enabled = |path: &syn::Path| !gated_modules.iter().any(|module| starts_with(path, module));
+        let reason = "its module is disabled";
+        debug::retain_logged(&mut discovered.fns, reason, is_enabled);
+        debug::retain_logged(&mut discovered.models, reason, is_enabled);
+        debug::retain_logged(&mut discovered.responses, reason, is_enabled);
+        debug::retain_logged(&mut discovered.modifiers, reason, is_enabled);
+        debug::retain_logged(&mut discovered.custom_impls, reason, is_enabled);
+        debug::retain_logged(&mut discovered.generic_models, reason, is_enabled);
+        debug::retain_logged(&mut discovered.sub_docs, reason, is_enabled);
+    }
+
+    if params.prefer_reexports || params.flatten {
+        for path in discovered
+            .fns
+            .iter_mut()
+            .chain(discovered.models.iter_mut())
+            .chain(discovered.responses.iter_mut())
+            .chain(discovered.modifiers.iter_mut())
+            .chain(discovered.custom_impls.iter_mut())
+            .chain(discovered.generic_models.iter_mut())
+            .chain(discovered.sub_docs.iter_mut())
+            .chain(discovered.routes.iter_mut().map(|(handler, _)| handler))
+            .chain(discovered.tags.iter_mut().map(|(handler, _)| handler))
+        {
+            let flattened = match params.flatten {
+                true => flatten_reexports(path, &reexports),
+                false => None,
+            };
+            *path = match flattened {
+                Some(flattened) => flattened,
+                None if params.prefer_reexports => resolve_reexports(path, &reexports),
+                None => path.clone(),
+            };
+        }
+    } else if !reexports.is_empty() {
+        // The path through a private module only compiles inside of it, the re-export is used instead if it is public
+        let scopes = std::mem::take(&mut discovered.scopes);
+        for path in discovered
+            .fns
+            .iter_mut()
+            .chain(discovered.models.iter_mut())
+            .chain(discovered.responses.iter_mut())
+            .chain(discovered.modifiers.iter_mut())
+            .chain(discovered.custom_impls.iter_mut())
+            .chain(discovered.generic_models.iter_mut())
+            .chain(discovered.sub_docs.iter_mut())
+            .chain(discovered.routes.iter_mut().map(|(handler, _)| handler))
+            .chain(discovered.tags.iter_mut().map(|(handler, _)| handler))
+        {
+            if !has_public_modules(path, &scopes) {
+                let resolved = resolve_reexports(path, &reexports);
+                if has_public_modules(&resolved, &scopes) {
+                    *path = resolved;
+                }
+            }
+        }
+        discovered.scopes = scopes;
+    }
+    Ok(discovered)
+}
+
+/// Whether the modules of the path are all public, so that the path can be used from anywhere
+fn has_public_modules(path: &syn::Path, scopes: &[(syn::Path, Option<syn::Path>)]) -> bool {
+    (2..path.segments.len()).all(|len| {
+        let module = syn::Path {
+            leading_colon: None,
+            segments: path.segments.iter().take(len).cloned().collect(),
+        };
+        let module = path_to_string(&module);
+        !scopes
+            .iter()
+            .any(|(path, scope)| scope.is_some() && path_to_string(path) == module)
+    })
+}
+
+/// Report the scan root if it matched no files or contributed no item,
+/// and its files whose module is not declared by their parent module
+fn check_scan_root(src_path: &str, modules: &[(String, syn::Path)], discovered: &mut Discovered, params: &Parameters) {
+    if modules.is_empty() && params.checks(WarningKind::UnmatchedPath) {
+        discovered.warnings.push(Warning::new(
+            WarningKind::UnmatchedPath,
+            format!("`{}` matched no files", src_path),
+        ));
+        return;
+    }
+    if modules.is_empty() {
+        return;
+    }
+    if discovered.is_empty() && params.checks(WarningKind::EmptyRoot) {
+        discovered.warnings.push(Warning::new(
+            WarningKind::EmptyRoot,
+            format!("`{}` contributed no discovered item", src_path),
+        ));
+    }
+    if !params.checks(WarningKind::UnreachableModule) {
+        return;
+    }
+    let module_strings: Vec<String> = modules.iter().map(|(_, module)| path_to_string(module)).collect();
+    let declared: Vec<String> = discovered.scopes.iter().map(|(path, _)| path_to_string(path)).collect();
+    for (file_path, module) in modules {
+        if module.segments.len() < 2 {
+            continue;
+        }
+        // The parent module may be declared out of the scan root
+        let parent_is_scanned = module_strings.contains(&path_to_string(&module_of(module)));
+        if parent_is_scanned && !declared.contains(&path_to_string(module)) {
+            discovered.warnings.push(Warning::new(
+                WarningKind::UnreachableModule,
+                format!(
+                    "`{}` is not declared by its parent module, `{}` is not part of the crate",
+                    path_to_string(module),
+                    file_path
+                ),
+            ));
+        }
+    }
+}
+
+/// A `pub use` of an item, possibly renamed, or of all the items of a module
+pub(crate) struct Reexport {
+    pub(crate) source: syn::Path,
+    pub(crate) public: syn::Path,
+    pub(crate) glob: bool,
+    /// The path as written in the `use`, which may start with the name of another crate
+    pub(crate) written: syn::Path,
+}
+
+/// The path of an item of another crate through the longest re-export of it or of one of its modules
+fn reroot_path(path: &syn::Path, reexports: &[Reexport]) -> Option<syn::Path> {
+    let segments: Vec<String> = path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+    reexports
+        .iter()
+        .filter_map(|reexport| {
+            let written: Vec<String> = reexport
+                .written
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect();
+            // A glob only re-exports the children of the module
+            let min_len = written.len() + usize::from(reexport.glob);
+            (segments.len() >= min_len && segments[..written.len()] == written[..]).then_some((written.len(), reexport))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(len, reexport)| {
+            let mut public = reexport.public.clone();
+            public.segments.extend(path.segments.iter().skip(len).cloned());
+            public
+        })
+}
+
+/// Follow the re-exports of the path, so the public path is emitted instead of the private one
+fn resolve_reexports(path: &syn::Path, reexports: &[Reexport]) -> syn::Path {
+    let mut path = path.clone();
+    // Re-exports can be chained, bail out after following all of them in case of a cycle
+    for _ in 0..=reexports.len() {
+        let module = {
+            let mut module = path.clone();
+            module.segments.pop();
+            path_to_string(&module).trim_end_matches("::").to_string()
+        };
+        let full_path = path_to_string(&path);
+        let reexport = reexports.iter().find(|reexport| match reexport.glob {
+            true => path_to_string(&reexport.source) == module,
+            false => path_to_string(&reexport.source) == full_path,
+        });
+        path = match reexport {
+            Some(reexport) if reexport.glob => {
+                let mut public = reexport.public.clone();
+                public.segments.push(path.segments.last().unwrap().clone());
+                public
+            }
+            Some(reexport) => reexport.public.clone(),
+            None => return path,
+        };
+    }
+    path
+}
+
+/// The path of a re-export of the item at the crate root, through any chain of re-exports
+fn flatten_reexports(path: &syn::Path, reexports: &[Reexport]) -> Option<syn::Path> {
+    let is_root = |path: &syn::Path| path.segments.len() == 2;
+    if is_root(path) {
+        return None;
+    }
+    // Unlike `resolve_reexports`, every re-export of the path is followed, as only one may reach the root
+    let mut seen = vec![path_to_string(path)];
+    let mut pending = vec![path.clone()];
+    while let Some(path) = pending.pop() {
+        let full_path = path_to_string(&path);
+        let module = module_to_string(&path);
+        for reexport in reexports {
+            let public = match reexport.glob {
+                true if path_to_string(&reexport.source) == module => {
+                    let mut public = reexport.public.clone();
+                    public.segments.push(path.segments.last().unwrap().clone());
+                    public
+                }
+                false if path_to_string(&reexport.source) == full_path => reexport.public.clone(),
+                _ => continue,
+            };
+            if is_root(&public) {
+                return Some(public);
+            }
+            let public_string = path_to_string(&public);
+            if !seen.contains(&public_string) {
+                seen.push(public_string);
+                pending.push(public);
+            }
+        }
+    }
+    None
+}
+
+/// Sort the discovered paths according to the requested order
+pub fn sort_paths(paths: &mut [syn::Path], order: Order) {
+    match order {
+        Order::Source => {}
+        Order::Alphabetical => paths.sort_by_cached_key(path_to_string),
+        // The sort is stable, so items of a module keep their discovery order
+        Order::Module => paths.sort_by_cached_key(module_to_string),
+    }
+}
+
+/// Where an item declared in `parent` with the visibility is visible from : everywhere when None,
+/// or inside the returned module
+fn visibility_scope(visibility: &Visibility, parent: &syn::Path) -> Option<syn::Path> {
+    match visibility {
+        Visibility::Public(_) => None,
+        Visibility::Inherited => Some(parent.clone()),
+        Visibility::Restricted(restricted) => {
+            let segments: Vec<Ident> = restricted
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.clone())
+                .collect();
+            Some(resolve_use_path(parent, &segments, None))
+        }
+    }
+}
+
+fn module_of(path: &syn::Path) -> syn::Path {
+    syn::Path {
+        leading_colon: None,
+        segments: path.segments.iter().take(path.segments.len() - 1).cloned().collect(),
+    }
+}
+
+fn module_to_string(path: &syn::Path) -> String {
+    let mut module = path.clone();
+    module.segments.pop();
+    path_to_string(&module).trim_end_matches("::").to_string()
+}
+
+#[allow(unused)]
+pub(crate) enum DiscoverType {
+    Reexport(Reexport),
+    Fn(syn::Path),
+    Model(syn::Path),
+    Response(syn::Path),
+    CustomModelImpl(syn::Path),
+    CustomResponseImpl(syn::Path),
+    /// A schema with generic parameters, see `Discovered::instantiate_generic_models`
+    GenericModel(syn::Path),
+    Modifier(syn::Path),
+    /// The self type of an `impl Modify`, a modifier if it is a `UnitStruct` of the scan root
+    ModifierImpl(syn::Path),
+    /// A struct without fields, which can be used as a value by its name
+    UnitStruct(syn::Path),
+    /// The body type of a discovered `IntoResponses`
+    ResponseBody(syn::Path, syn::Type),
+    /// A route declared by a discovered handler
+    Route(syn::Path, String),
+    /// A tag of a discovered handler
+    Tag(syn::Path, String),
+    /// A cfg predicate the discovery of an item depends on
+    Cfg(String),
+    /// A module declared behind a disabled feature, whose file is left out
+    GatedModule(syn::Path),
+    /// Where a module or item is visible from
+    Scope(syn::Path, Option<syn::Path>),
+    /// A struct or a function annotated with #[utoipauto]
+    DocStruct(syn::Path),
+    /// A struct deriving OpenApi
+    SubDoc(syn::Path),
+    Warning(Warning),
+}
+
+/// Discover the items of a module, `depth` being the number of inline modules it is nested in
+fn parse_module_items(
+    module_path: syn::Path,
+    items: Vec<Item>,
+    depth: usize,
+    params: &Parameters,
+) -> syn::Result<Vec<DiscoverType>> {
+    let mut gates = vec![];
+    let discovered = items
+        .into_iter()
+        .filter(|e| {
+            matches!(
+                e,
+                Item::Mod(_)
+                    | Item::Fn(_)
+                    | Item::Struct(_)
+                    | Item::Enum(_)
+                    | Item::Impl(_)
+                    | Item::Use(_)
+                    | Item::Macro(_)
+            )
+        })
+        .filter(|item| {
+            (params.include_tests || !is_test_module(item, &module_path, &mut gates))
+                && is_enabled_by_features(item, &module_path, params, &mut gates)
+        })
+        .map(|v| match v {
+            Item::Mod(m) => {
+                let scope = visibility_scope(&m.vis, &module_path);
+                let module_path = build_path(&module_path, &m.ident);
+                let mut out = vec![DiscoverType::Scope(module_path.clone(), scope)];
+                // Everything inside an ignored module is ignored, its file is left out like the one of a disabled module
+                if apply_ignore(&m.attrs, &module_path, params, &mut out)? {
+                    if m.content.is_none() {
+                        out.push(DiscoverType::GatedModule(module_path));
+                    }
+                    return Ok(out);
+                }
+                if let Some((_, items)) = m.content {
+                    if params
+                        .max_item_depth
+                        .is_some_and(|max_item_depth| depth >= max_item_depth)
+                    {
+                        debug::log(format_args!(
+                            "skipped {} (max_item_depth)",
+                            path_to_string(&module_path)
+                        ));
+                    } else {
+                        out.extend(parse_module_items(module_path, items, depth + 1, params)?);
+                    }
+                }
+                Ok(out)
+            }
+            Item::Fn(f) => {
+                let name = build_path(&module_path, &f.sig.ident);
+                let mut out = vec![DiscoverType::Scope(
+                    name.clone(),
+                    visibility_scope(&f.vis, &module_path),
+                )];
+                if is_doc_struct(&f.attrs) {
+                    out.push(DiscoverType::DocStruct(name.clone()));
+                }
+                let included = !parse_include(&f.attrs, &name, &["path"])?.is_empty();
+                let discoverable = included || has_fn_attribute(&f.attrs, &params.fn_attribute_names);
+                check_unnecessary_ignore(&f.attrs, &name, discoverable, params, &mut out);
+                if apply_ignore(&f.attrs, &name, params, &mut out)? {
+                    return Ok(out);
+                }
+                if !discoverable && params.checks(WarningKind::UndocumentedRoute) {
+                    if let Some(route) = route_attribute(&f.attrs, &params.route_attributes) {
+                        out.push(DiscoverType::Warning(Warning::new(
+                            WarningKind::UndocumentedRoute,
+                            format!(
+                                "`{}` is routed with `#[{}]` but has no `#[utoipa::path]`",
+                                path_to_string(&name),
+                                route
+                            ),
+                        )));
+                    }
+                }
+                let alias = parse_alias(&f.attrs)?;
+                let mut fns = parse_function(&f, &params.fn_attribute_names);
+                if included && fns.is_empty() {
+                    fns.push(f.sig.ident.clone());
+                }
+                if let Some(item) = fns.first() {
+                    let handler = alias.clone().unwrap_or_else(|| build_path(&module_path, item));
+                    if params.checks(WarningKind::UndescribedHandler)
+                        && !has_description(&f.attrs, &params.fn_attribute_names)
+                    {
+                        out.push(DiscoverType::Warning(Warning::new(
+                            WarningKind::UndescribedHandler,
+                            format!(
+                                "`{}` has no description, add a doc comment or `description = \"...\"`",
+                                path_to_string(&handler)
+                            ),
+                        )));
+                    }
+                    if params.checks(WarningKind::MissingResponseBody) {
+                        let coverage = response_coverage(&f.attrs, &params.fn_attribute_names);
+                        let missing = match coverage {
+                            ResponseCoverage::None => Some("documents no responses"),
+                            ResponseCoverage::StatusOnly => Some("only documents status codes, without a body"),
+                            ResponseCoverage::Bodies => None,
+                        };
+                        if let Some(missing) = missing {
+                            out.push(DiscoverType::Warning(Warning::new(
+                                WarningKind::MissingResponseBody,
+                                format!("`{}` {}", path_to_string(&handler), missing),
+                            )));
+                        }
+                    }
+                    out.extend(
+                        f.attrs
+                            .iter()
+                            .filter(|attr| is_fn_attribute(attr, &params.fn_attribute_names))
+                            .flat_map(parse_routes)
+                            .map(|route| DiscoverType::Route(handler.clone(), route)),
+                    );
+                    out.extend(
+                        f.attrs
+                            .iter()
+                            .filter(|attr| is_fn_attribute(attr, &params.fn_attribute_names))
+                            .flat_map(parse_tags)
+                            .map(|tag| DiscoverType::Tag(handler.clone(), tag)),
+                    );
+                }
+                out.extend(
+                    fns.into_iter()
+                        .map(|item| DiscoverType::Fn(alias.clone().unwrap_or_else(|| build_path(&module_path, &item)))),
+                );
+                Ok(out)
+            }
+            Item::Struct(s) => {
+                let name = build_path(&module_path, &s.ident);
+                let mut out = parse_from_attr(&s.attrs, name.clone(), s.generics.params.clone(), params)?;
+                out.push(DiscoverType::Scope(
+                    name.clone(),
+                    visibility_scope(&s.vis, &module_path),
+                ));
+                if is_doc_struct(&s.attrs) {
+                    out.push(DiscoverType::DocStruct(name.clone()));
+                }
+                if is_into_responses(&s.attrs) {
+                    out.extend(parse_response_bodies(&name, [(&s.attrs, &s.fields)]));
+                }
+                if !has_ignore(&s.attrs) && has_derive(&s.attrs, "OpenApi") {
+                    out.push(DiscoverType::SubDoc(name.clone()));
+                }
+                if matches!(s.fields, syn::Fields::Unit) && s.generics.params.is_empty() && !has_ignore(&s.attrs) {
+                    out.push(DiscoverType::UnitStruct(name));
+                }
+                Ok(out)
+            }
+            Item::Enum(e) => {
+                let name = build_path(&module_path, &e.ident);
+                let mut out = parse_from_attr(&e.attrs, name.clone(), e.generics.params.clone(), params)?;
+                out.push(DiscoverType::Scope(
+                    name.clone(),
+                    visibility_scope(&e.vis, &module_path),
+                ));
+                if is_into_responses(&e.attrs) {
+                    let variants = e.variants.iter().map(|variant| (&variant.attrs, &variant.fields));
+                    out.extend(parse_response_bodies(&name, variants));
+                }
+                Ok(out)
+            }
+            Item::Impl(im) => {
+                let found = parse_from_impl(&im, &module_path, params);
+                let name = match is_nameable_self_type(&im.self_ty) {
+                    true => build_path(&module_path, &im.self_ty),
+                    false => module_path.clone(),
+                };
+                let mut out = vec![];
+                check_unnecessary_ignore(&im.attrs, &name, !found.is_empty(), params, &mut out);
+                if !apply_ignore(&im.attrs, &name, params, &mut out)? {
+                    out.extend(found);
+                }
+                Ok(out)
+            }
+            Item::Use(u) => Ok(parse_use(&u, &module_path)),
+            Item::Macro(m) => parse_macro_template(&m, &module_path, params),
+            _ => Ok(vec![]),
+        });
+    let mut discovered: Vec<DiscoverType> = diagnostics::collect_results(discovered)?
+        .into_iter()
+        .flatten()
+        .collect();
+    discovered.append(&mut gates);
+    Ok(discovered)
+}
+
+/// Whether the `#[cfg(feature = "...")]` of the item hold, recording their predicates in `gates`.
+///
+/// A proc macro can't know the features of the crate, so the predicates are assumed like the ones of a conditional
+/// ignore, see `Parameters::cfg_values`. A feature is known to be enabled if its `CARGO_FEATURE_*` variable is set,
+/// e.g. in a build script. The file of a disabled `mod` declaration is left out with `DiscoverType::GatedModule`.
+fn is_enabled_by_features(
+    item: &Item,
+    module_path: &syn::Path,
+    params: &Parameters,
+    gates: &mut Vec<DiscoverType>,
+) -> bool {
+    let attrs = match item {
+        Item::Mod(m) => &m.attrs,
+        Item::Fn(f) => &f.attrs,
+        Item::Struct(s) => &s.attrs,
+        Item::Enum(e) => &e.attrs,
+        Item::Impl(im) => &im.attrs,
+        Item::Use(u) => &u.attrs,
+        Item::Macro(m) => &m.attrs,
+        _ => return true,
+    };
+    let mut enabled = true;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("cfg")) {
+        let Ok(predicate) = attr.parse_args::<Meta>() else {
+            continue;
+        };
+        if !is_feature_predicate(&predicate) || is_enabled_feature(&predicate) {
+            continue;
+        }
+        let cfg = predicate.to_token_stream().to_string();
+        if !params.cfg_value(&cfg) {
+            let name = match item {
+                Item::Mod(m) => path_to_string(&build_path(module_path, &m.ident)),
+                Item::Fn(f) => path_to_string(&build_path(module_path, &f.sig.ident)),
+                Item::Struct(s) => path_to_string(&build_path(module_path, &s.ident)),
+                Item::Enum(e) => path_to_string(&build_path(module_path, &e.ident)),
+                _ => format!("an item of {}", path_to_string(module_path)),
+            };
+            debug::log(format_args!("skipped {} (cfg({}) is assumed not to hold)", name, cfg));
+            enabled = false;
+        }
+        gates.push(DiscoverType::Cfg(cfg));
+    }
+    if let Item::Mod(m) = item {
+        if !enabled && m.content.is_none() {
+            gates.push(DiscoverType::GatedModule(build_path(module_path, &m.ident)));
+        }
+    }
+    enabled
+}
+
+/// Whether the item is a `#[cfg(test)]` module, whose file is left out with `DiscoverType::GatedModule`.
+///
+/// An inline test module declaring a doc struct is kept, the doc is meant to document the test items.
+fn is_test_module(item: &Item, module_path: &syn::Path, gates: &mut Vec<DiscoverType>) -> bool {
+    let Item::Mod(m) = item else {
+        return false;
+    };
+    let is_test = m.attrs.iter().any(|attr| {
+        attr.path().is_ident("cfg")
+            && attr
+                .parse_args::<syn::Ident>()
+                .is_ok_and(|predicate| predicate == "test")
+    }) && !m.content.as_ref().is_some_and(|(_, items)| declares_doc_struct(items));
+    if is_test {
+        let module = build_path(module_path, &m.ident);
+        debug::log(format_args!("skipped {} (cfg(test))", path_to_string(&module)));
+        if m.content.is_none() {
+            gates.push(DiscoverType::GatedModule(module));
+        }
+    }
+    is_test
+}
+
+fn declares_doc_struct(items: &[Item]) -> bool {
+    items.iter().any(|item| match item {
+        Item::Struct(s) => is_doc_struct(&s.attrs),
+        Item::Fn(f) => is_doc_struct(&f.attrs),
+        Item::Mod(m) => m.content.as_ref().is_some_and(|(_, items)| declares_doc_struct(items)),
+        _ => false,
+    })
+}
+
+/// Whether the predicate only depends on features, e.g. `any(feature = "a", not(feature = "b"))`
+fn is_feature_predicate(predicate: &Meta) -> bool {
+    match predicate {
+        Meta::NameValue(name_value) => name_value.path.is_ident("feature"),
+        Meta::List(list) if ["all", "any", "not"].iter().any(|op| list.path.is_ident(op)) => list
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            .is_ok_and(|predicates| predicates.iter().all(is_feature_predicate)),
+        _ => false,
+    }
+}
+
+/// Whether the predicate is a single `feature = "..."` whose `CARGO_FEATURE_*` variable is set
+fn is_enabled_feature(predicate: &Meta) -> bool {
+    let Meta::NameValue(syn::MetaNameValue {
+        value: syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(feature),
+            ..
+        }),
+        ..
+    }) = predicate
+    else {
+        return false;
+    };
+    let variable = format!("CARGO_FEATURE_{}", feature.value().to_uppercase().replace('-', "_"));
+    std::env::var_os(variable).is_some()
+}
+
+/// Discover the item named by the invocation of a declarative macro declared in `macros`,
+/// e.g. `crate::users::get_user` for `endpoint!(get_user, GET, "/users/{id}")` with `"endpoint! => path(0)"`
+fn parse_macro_template(
+    m: &syn::ItemMacro,
+    module_path: &syn::Path,
+    params: &Parameters,
+) -> syn::Result<Vec<DiscoverType>> {
+    let Some(template) = m
+        .mac
+        .path
+        .segments
+        .last()
+        .and_then(|segment| params.macros.iter().find(|template| segment.ident == template.name))
+    else {
+        return Ok(vec![]);
+    };
+    let Ok(arguments) = m
+        .mac
+        .parse_body_with(Punctuated::<syn::Expr, Token![,]>::parse_terminated)
+    else {
+        debug::log(format_args!(
+            "skipped {}! in {} (invalid arguments)",
+            template.name,
+            path_to_string(module_path)
+        ));
+        return Ok(vec![]);
+    };
+    let Some(syn::Expr::Path(argument)) = arguments.iter().nth(template.argument) else {
+        debug::log(format_args!(
+            "skipped {}! in {} (argument {} is not a path)",
+            template.name,
+            path_to_string(module_path),
+            template.argument
+        ));
+        return Ok(vec![]);
+    };
+    // Relative to the module of the invocation, unless it starts with `crate`, `self` or `super`
+    let path = &argument.path;
+    let name = match path
+        .segments
+        .first()
+        .map(|segment| segment.ident.to_string())
+        .as_deref()
+    {
+        Some("crate") => path.clone(),
+        Some("self" | "super") => canonicalize(path, module_path),
+        _ => build_path(module_path, path),
+    };
+    let mut out = vec![];
+    if apply_ignore(&m.attrs, &name, params, &mut out)? {
+        return Ok(out);
+    }
+    out.push(match template.kind {
+        TemplateKind::Path => DiscoverType::Fn(name),
+        TemplateKind::Schema => DiscoverType::Model(name),
+        TemplateKind::Response => DiscoverType::Response(name),
+    });
+    Ok(out)
+}
+
+/// Search for ToSchema and ToResponse implementations in attr,
+/// and for the #[utoipauto_modifier] marker
+fn parse_from_attr(
+    a: &Vec<Attribute>,
+    name: syn::Path,
+    generic_params: Punctuated<GenericParam, Comma>,
+    params: &Parameters,
+) -> syn::Result<Vec<DiscoverType>> {
+    let mut out: Vec<DiscoverType> = vec![];
+    let mut found: Vec<DiscoverType> = vec![];
+    for attr in a {
+        let meta = &attr.meta;
+        if is_modifier_marker(meta.path()) {
+            found.push(DiscoverType::Modifier(name.clone()));
+        }
+        if meta.path().is_ident("derive") {
+            let nested = attr
+                .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .map_err(|e| {
+                    diagnostics::error(format!(
+                        "Failed to parse the derive attribute of `{}`: {}",
+                        path_to_string(&name),
+                        e
+                    ))
+                })?;
+            for nested_meta in nested {
+                if is_utoipa_item(nested_meta.path(), &params.utoipa_crate) {
+                    match nested_meta.path().segments.last().unwrap().ident.to_string().as_str() {
+                        "ToSchema" if params.schema_source.derives() => found.push(DiscoverType::Model(name.clone())),
+                        "ToResponse" => found.push(DiscoverType::Response(name.clone())),
+                        _ => {}
+                    }
+                } else {
+                    let is_schema = nested_meta.path().is_ident(&params.schema_attribute_name)
+                        || is_derive_of(nested_meta.path(), &params.schema_derives);
+                    if is_schema && params.schema_source.derives() {
+                        found.push(DiscoverType::Model(name.clone()));
+                    }
+                    if nested_meta.path().is_ident(&params.response_attribute_name)
+                        || is_derive_of(nested_meta.path(), &params.response_derives)
+                    {
+                        found.push(DiscoverType::Response(name.clone()));
+                    }
+                }
+            }
+        }
+    }
+
+    for registration in parse_include(a, &name, &["schema", "response"])? {
+        let is_found = found.iter().any(|item| {
+            matches!(
+                (item, registration.as_str()),
+                (DiscoverType::Model(_), "schema") | (DiscoverType::Response(_), "response")
+            )
+        });
+        if !is_found {
+            found.push(match registration.as_str() {
+                "schema" => DiscoverType::Model(name.clone()),
+                _ => DiscoverType::Response(name.clone()),
+            });
+        }
+    }
+
+    check_unnecessary_ignore(a, &name, !found.is_empty(), params, &mut out);
+    match ignored_registrations(a, &name, params, &mut out)? {
+        Some(registrations) if registrations.is_empty() => return Ok(out),
+        // e.g. `#[utoipa_ignore(response)]` keeps the schema of a type deriving both
+        Some(registrations) => found.retain(|item| {
+            let registration = match item {
+                DiscoverType::Model(_) => "schema",
+                DiscoverType::Response(_) => "response",
+                _ => return true,
+            };
+            !registrations.iter().any(|ignored| ignored == registration)
+        }),
+        None => {}
+    }
+    // A generic type can't be emitted without its arguments, only its listed instantiations are. The lifetimes are
+    // elided, e.g. `Claims` for `Claims<'a>`
+    if generic_params
+        .iter()
+        .any(|param| !matches!(param, GenericParam::Lifetime(_)))
+    {
+        if found.iter().any(|item| matches!(item, DiscoverType::Model(_))) {
+            out.push(DiscoverType::GenericModel(name));
+        }
+        return Ok(out);
+    }
+    out.append(&mut found);
+    Ok(out)
+}
+
+/// Whether the path is the one of an item of the utoipa crate, e.g. `utoipa::ToSchema`, see `Parameters::utoipa_crate`
+fn is_utoipa_item(path: &syn::Path, utoipa_crate: &[String]) -> bool {
+    path.segments.len() == utoipa_crate.len() + 1
+        && path
+            .segments
+            .iter()
+            .zip(utoipa_crate)
+            .all(|(segment, name)| segment.ident == name)
+}
+
+/// Whether the derive is one of the names, e.g. `ApiModel` or `macros::ApiModel` for `ApiModel`
+fn is_derive_of(path: &syn::Path, names: &[String]) -> bool {
+    path.segments
+        .last()
+        .is_some_and(|segment| names.iter().any(|name| segment.ident == name))
+}
+
+/// Match both #[utoipauto] and #[utoipauto::utoipauto], on a struct or a function building the documentation
+fn is_doc_struct(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "utoipauto")
+    })
+}
+
+/// Check if the item derives `IntoResponses`, and isn't ignored
+fn is_into_responses(attrs: &[Attribute]) -> bool {
+    !has_ignore(attrs) && has_derive(attrs, "IntoResponses")
+}
+
+/// Check if the item derives the macro, with or without its path, e.g. `OpenApi` or `utoipa::OpenApi`
+fn has_derive(attrs: &[Attribute], name: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .any(|derive| derive.segments.last().is_some_and(|segment| segment.ident == name))
+}
+
+/// The body types of the responses of an `IntoResponses` : the `body = ...` of their `#[response(...)]`,
+/// or their single unnamed field, e.g. `Ok(User)`
+fn parse_response_bodies<'a>(
+    name: &syn::Path,
+    responses: impl IntoIterator<Item = (&'a Vec<Attribute>, &'a syn::Fields)>,
+) -> Vec<DiscoverType> {
+    let mut out = vec![];
+    for (attrs, fields) in responses {
+        let bodies: Vec<syn::Type> = attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("response"))
+            .filter_map(|attr| match &attr.meta {
+                Meta::List(list) => parse_body_argument(list.tokens.clone()),
+                _ => None,
+            })
+            .collect();
+        let bodies = match fields {
+            syn::Fields::Unnamed(fields) if bodies.is_empty() && fields.unnamed.len() == 1 => {
+                vec![fields.unnamed[0].ty.clone()]
+            }
+            _ => bodies,
+        };
+        out.extend(
+            bodies
+                .into_iter()
+                .map(|body| DiscoverType::ResponseBody(name.clone(), body)),
+        );
+    }
+    out
+}
+
+/// Parse the type of the `body = ...` argument, up to the next top level coma
+fn parse_body_argument(tokens: proc_macro2::TokenStream) -> Option<syn::Type> {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let is_body = matches!(&token, proc_macro2::TokenTree::Ident(ident) if ident == "body")
+            && matches!(tokens.peek(), Some(proc_macro2::TokenTree::Punct(punct)) if punct.as_char() == '=');
+        if !is_body {
+            continue;
+        }
+        tokens.next();
+        let mut depth = 0;
+        let body: proc_macro2::TokenStream = tokens
+            .take_while(|token| match token {
+                proc_macro2::TokenTree::Punct(punct) => match punct.as_char() {
+                    '<' => {
+                        depth += 1;
+                        true
+                    }
+                    '>' => {
+                        depth -= 1;
+                        true
+                    }
+                    ',' => depth > 0,
+                    _ => true,
+                },
+                _ => true,
+            })
+            .collect();
+        return syn::parse2(body).ok();
+    }
+    None
+}
+
+/// Match both #[utoipauto_modifier] and #[utoipauto::utoipauto_modifier]
+fn is_modifier_marker(path: &syn::Path) -> bool {
+    path.segments
+        .last()
+        .is_some_and(|segment| segment.ident == "utoipauto_modifier")
+}
+
+/// Search for the items made public by `pub use`
+fn parse_use(u: &ItemUse, module_path: &syn::Path) -> Vec<DiscoverType> {
+    if matches!(u.vis, Visibility::Inherited) || u.leading_colon.is_some() {
+        return vec![];
+    }
+    let mut reexports = vec![];
+    collect_reexports(&u.tree, &mut vec![], module_path, &mut reexports);
+    reexports.into_iter().map(DiscoverType::Reexport).collect()
+}
+
+fn collect_reexports(tree: &UseTree, prefix: &mut Vec<Ident>, module_path: &syn::Path, out: &mut Vec<Reexport>) {
+    match tree {
+        UseTree::Path(path) => {
+            prefix.push(path.ident.clone());
+            collect_reexports(&path.tree, prefix, module_path, out);
+            prefix.pop();
+        }
+        UseTree::Name(name) => out.push(Reexport {
+            source: resolve_use_path(module_path, prefix, Some(&name.ident)),
+            public: build_path(module_path, &name.ident),
+            glob: false,
+            written: written_use_path(prefix, Some(&name.ident)),
+        }),
+        UseTree::Rename(rename) => out.push(Reexport {
+            source: resolve_use_path(module_path, prefix, Some(&rename.ident)),
+            public: build_path(module_path, &rename.rename),
+            glob: false,
+            written: written_use_path(prefix, Some(&rename.ident)),
+        }),
+        UseTree::Glob(_) => out.push(Reexport {
+            source: resolve_use_path(module_path, prefix, None),
+            public: module_path.clone(),
+            glob: true,
+            written: written_use_path(prefix, None),
+        }),
+        UseTree::Group(group) => group
+            .items
+            .iter()
+            .for_each(|tree| collect_reexports(tree, prefix, module_path, out)),
+    }
+}
+
+fn written_use_path(prefix: &[Ident], name: Option<&Ident>) -> syn::Path {
+    syn::Path {
+        leading_colon: None,
+        segments: prefix
+            .iter()
+            .chain(name)
+            .map(|segment| syn::PathSegment::from(segment.clone()))
+            .collect(),
+    }
+}
+
+/// Resolve the path of a `use` relatively to the module it is declared in
+fn resolve_use_path(module_path: &syn::Path, prefix: &[Ident], name: Option<&Ident>) -> syn::Path {
+    let mut segments = prefix.iter().chain(name).peekable();
+    let mut path = module_path.clone();
+    match segments.peek().map(|segment| segment.to_string()).as_deref() {
+        Some("crate") => {
+            segments.next();
+            path.segments = path.segments.into_iter().take(1).collect();
+        }
+        Some("self") => {
+            segments.next();
+        }
+        Some("super") => {
+            while segments.peek().is_some_and(|segment| *segment == "super") {
+                segments.next();
+                path.segments.pop();
+                // Remove the trailing `::` left by pop
+                if let Some(last) = path.segments.pop() {
+                    path.segments.push(last.into_value());
+                }
+            }
+        }
+        _ => {}
+    }
+    for segment in segments {
+        path.segments.push(syn::PathSegment::from(segment.clone()));
+    }
+    path
+}
+
+/// The schema, response or modifier implemented by a trait impl block.
+///
+/// The methods of the impl blocks are never handlers : utoipa declares the struct documenting a handler next to it,
+/// which can't be done in an impl block, and `paths(Type::method)` would name an inherent associated type. The same
+/// goes for the default methods of the traits, which aren't scanned at all.
+fn parse_from_impl(im: &ItemImpl, module_base_path: &syn::Path, params: &Parameters) -> Vec<DiscoverType> {
+    im.trait_
+        .as_ref()
+        // With `qualified_impls`, `impl ToSchema for X` may be the one of another trait
+        .filter(|trt| !params.qualified_impls || is_utoipa_item(&trt.1, &params.utoipa_crate))
+        .and_then(|trt| trt.1.segments.last().map(|p| p.ident.to_string()))
+        .and_then(|impl_name| {
+            let (discover_type, extra): (fn(syn::Path) -> DiscoverType, _) =
+                if impl_name.eq(params.schema_attribute_name.as_str()) {
+                    // The manual implementations may be left out, e.g. for foreign types wrapped in another schema
+                    if !params.schema_source.impls() {
+                        return Some(vec![]);
+                    }
+                    (DiscoverType::CustomModelImpl, "extra_schemas")
+                } else if impl_name.eq(params.response_attribute_name.as_str()) {
+                    (DiscoverType::CustomResponseImpl, "extra_responses")
+                } else if impl_name == "Modify" {
+                    // A modifier the discovery can't name is simply left out, it may be listed by hand
+                    return Some(match is_nameable_self_type(&im.self_ty) {
+                        true => vec![DiscoverType::ModifierImpl(build_path(module_base_path, &im.self_ty))],
+                        false => vec![],
+                    });
+                } else {
+                    return None;
+                };
+            Some(vec![match is_nameable_self_type(&im.self_ty) {
+                true => discover_type(build_path(module_base_path, &im.self_ty)),
+                false => DiscoverType::Warning(Warning::new(
+                    WarningKind::UnresolvedImpl,
+                    format!(
+                        "the self type of `impl {} for {}` in `{}` can't be named from its module, register the \
+                         type with `{}` instead",
+                        impl_name,
+                        type_to_string(&im.self_ty),
+                        path_to_string(module_base_path),
+                        extra
+                    ),
+                )),
+            }])
+        })
+        .unwrap_or_default()
+}
+
+/// Whether the self type of an impl is a plain path that can be appended to the path of its module,
+/// unlike `Self`, `<Foo as Provider>::Output` or `&Foo`
+fn is_nameable_self_type(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(type_path) => {
+            type_path.qself.is_none() && type_path.path.segments.iter().all(|segment| segment.ident != "Self")
+        }
+        syn::Type::Paren(paren) => is_nameable_self_type(&paren.elem),
+        syn::Type::Group(group) => is_nameable_self_type(&group.elem),
+        _ => false,
+    }
+}
+
+fn type_to_string(ty: &syn::Type) -> String {
+    ty.to_token_stream()
+        .to_string()
+        .replace(" :: ", "::")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace("& ", "&")
+}
+
+fn parse_function(f: &ItemFn, fn_attributes_names: &[String]) -> Vec<Ident> {
+    let mut fns_name: Vec<Ident> = vec![];
+    if should_parse_fn(f) {
+        for i in 0..f.attrs.len() {
+            if is_fn_attribute(&f.attrs[i], fn_attributes_names) {
+                fns_name.push(f.sig.ident.clone());
+            }
+        }
+    }
+    fns_name
+}
+
+/// Whether the attribute is one of the handler attributes, e.g. `#[utoipa::path]` for `utoipa` or `path`
+fn is_fn_attribute(attr: &Attribute, fn_attributes_names: &[String]) -> bool {
+    attr.meta
+        .path()
+        .segments
+        .iter()
+        .any(|item| fn_attributes_names.iter().any(|name| item.ident == name))
+}
+
+fn has_fn_attribute(attrs: &[Attribute], fn_attributes_names: &[String]) -> bool {
+    attrs.iter().any(|attr| is_fn_attribute(attr, fn_attributes_names))
+}
+
+/// The routing attribute of the framework on a function, e.g. `get` or `actix_web::get`, compared by its last segment
+fn route_attribute(attrs: &[Attribute], route_attributes: &[String]) -> Option<String> {
+    attrs
+        .iter()
+        .map(|attr| attr.meta.path())
+        .find(|path| {
+            path.segments
+                .last()
+                .is_some_and(|segment| route_attributes.iter().any(|name| segment.ident == name))
+        })
+        .map(path_to_string)
+}
+
+/// Whether a handler is described by a non empty doc comment, or by #[utoipa::path(description = "...")]
+fn has_description(attrs: &[Attribute], fn_attribute_names: &[String]) -> bool {
+    attrs.iter().any(|attr| match &attr.meta {
+        Meta::NameValue(doc) if doc.path.is_ident("doc") => match &doc.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(doc),
+                ..
+            }) => !doc.value().trim().is_empty(),
+            _ => true,
+        },
+        // Only the top level of the arguments, as `description` may also be a key of the responses
+        Meta::List(list) if is_fn_attribute(attr, fn_attribute_names) => {
+            let tokens: Vec<TokenTree> = list.tokens.clone().into_iter().collect();
+            tokens.windows(2).any(|pair| match pair {
+                [TokenTree::Ident(ident), TokenTree::Punct(punct)] => ident == "description" && punct.as_char() == '=',
+                _ => false,
+            })
+        }
+        _ => false,
+    })
+}
+
+/// What the responses of a handler document
+#[derive(Debug, PartialEq)]
+enum ResponseCoverage {
+    /// No `responses(...)`, or an empty one
+    None,
+    /// Only responses without `body`, `content` or `response`
+    StatusOnly,
+    /// At least one response with a body, or an `IntoResponses` type
+    Bodies,
+}
+
+/// Read the `responses(...)` of #[utoipa::path(...)]
+fn response_coverage(attrs: &[Attribute], fn_attribute_names: &[String]) -> ResponseCoverage {
+    let responses = attrs
+        .iter()
+        .filter(|attr| is_fn_attribute(attr, fn_attribute_names))
+        .filter_map(|attr| match &attr.meta {
+            Meta::List(list) => Some(list.tokens.clone()),
+            _ => None,
+        })
+        .find_map(|tokens| {
+            let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+            tokens.windows(2).find_map(|pair| match pair {
+                [TokenTree::Ident(ident), TokenTree::Group(group)] if ident == "responses" => Some(group.stream()),
+                _ => None,
+            })
+        });
+    let Some(responses) = responses else {
+        return ResponseCoverage::None;
+    };
+    let mut coverage = ResponseCoverage::None;
+    for response in split_top_level(responses) {
+        match response.as_slice() {
+            // (status = 200, body = Pet)
+            [TokenTree::Group(group)] => {
+                let has_body = group.stream().into_iter().any(|token| {
+                    matches!(&token, TokenTree::Ident(ident) if ident == "body" || ident == "content" || ident == "response")
+                });
+                if has_body {
+                    return ResponseCoverage::Bodies;
+                }
+                coverage = ResponseCoverage::StatusOnly;
+            }
+            [] => {}
+            // A type implementing IntoResponses
+            _ => return ResponseCoverage::Bodies,
+        }
+    }
+    coverage
+}
+
+/// Split the tokens on the top level comas
+fn split_top_level(tokens: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut elements = vec![vec![]];
+    for token in tokens {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => elements.push(vec![]),
+            _ => elements.last_mut().unwrap().push(token),
+        }
+    }
+    elements
+}
+
+/// The tags of `#[utoipa::path(tag = "users")]`, or of `#[utoipa::path(tags = ["users", "admin"])]`
+fn parse_tags(attr: &Attribute) -> Vec<String> {
+    let Ok(args) = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) else {
+        return vec![];
+    };
+    let string = |expr: &syn::Expr| match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(value),
+            ..
+        }) => Some(value.value()),
+        _ => None,
+    };
+    args.iter()
+        .flat_map(|arg| match arg {
+            Meta::NameValue(name_value) if name_value.path.is_ident("tag") => {
+                string(&name_value.value).into_iter().collect()
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("tags") => match &name_value.value {
+                syn::Expr::Array(array) => array.elems.iter().filter_map(string).collect(),
+                _ => vec![],
+            },
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Read the routes of #[utoipa::path(get, path = "/users/{id}")], e.g. `GET /users/{id}`, one per method
+fn parse_routes(attr: &Attribute) -> Vec<String> {
+    const METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+    let Ok(args) = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) else {
+        return vec![];
+    };
+    let mut methods = Vec::new();
+    let (mut path, mut context_path) = (None, String::new());
+    for arg in &args {
+        match arg {
+            Meta::Path(method) if METHODS.iter().any(|m| method.is_ident(m)) => methods.push(path_to_string(method)),
+            // utoipa 5 : method(get, head)
+            Meta::List(list) if list.path.is_ident("method") => {
+                if let Ok(list) = list.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated) {
+                    methods.extend(list.iter().map(Ident::to_string));
+                }
+            }
+            Meta::NameValue(name_value) => {
+                let syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(value),
+                    ..
+                }) = &name_value.value
+                else {
+                    continue;
+                };
+                if name_value.path.is_ident("path") {
+                    path = Some(value.value());
+                } else if name_value.path.is_ident("context_path") {
+                    context_path = value.value();
+                }
+            }
+            _ => {}
+        }
+    }
+    let Some(path) = path else {
+        return vec![];
+    };
+    methods
+        .iter()
+        .map(|method| format!("{} {}{}", method.to_uppercase(), context_path, path))
+        .collect()
+}
+
+/// Read the path given by #[utoipauto::alias("crate::api::v2::get_user")], if any
+fn parse_alias(attrs: &[Attribute]) -> syn::Result<Option<syn::Path>> {
+    attrs
+        .iter()
+        .find(|attr| {
+            let segments = &attr.path().segments;
+            segments.last().is_some_and(|segment| segment.ident == "alias")
+                && (segments.len() == 1 || (segments.len() == 2 && segments[0].ident == "utoipauto"))
+        })
+        .map(|attr| {
+            let alias: syn::LitStr = attr
+                .parse_args()
+                .map_err(|_| diagnostics::error("Expected #[utoipauto::alias(\"PATH\")]"))?;
+            alias
+                .parse()
+                .map_err(|_| diagnostics::error(format!("Invalid path \"{}\" in alias", alias.value())))
+        })
+        .transpose()
+}
+
+/// The registrations forced by `#[utoipa_include(schema, response)]` or `#[utoipa_include(path)]`, e.g. for a
+/// `ToSchema` implemented by a macro the discovery can't see
+fn parse_include(attrs: &[Attribute], name: &syn::Path, allowed: &[&str]) -> syn::Result<Vec<String>> {
+    let Some(attr) = attrs.iter().find(|attr| {
+        let segments = &attr.path().segments;
+        segments.last().is_some_and(|segment| segment.ident == "utoipa_include")
+            && (segments.len() == 1 || (segments.len() == 2 && segments[0].ident == "utoipauto"))
+    }) else {
+        return Ok(vec![]);
+    };
+    let expected = || {
+        diagnostics::error(format!(
+            "Expected #[utoipa_include({})] on `{}`",
+            allowed.join(", "),
+            path_to_string(name)
+        ))
+    };
+    let registrations = attr
+        .parse_args_with(Punctuated::<syn::Ident, Token![,]>::parse_terminated)
+        .map_err(|_| expected())?;
+    if registrations.is_empty() {
+        return Err(expected());
+    }
+    registrations
+        .iter()
+        .map(|registration| {
+            let registration = registration.to_string();
+            match allowed.contains(&registration.as_str()) {
+                true => Ok(registration),
+                false => Err(expected()),
+            }
+        })
+        .collect()
+}
+
+fn should_parse_fn(f: &ItemFn) -> bool {
+    !f.attrs.is_empty() && !is_ignored(f)
+}
+
+fn is_ignored(f: &ItemFn) -> bool {
+    parse_ignore(&f.attrs).is_ok_and(|ignore| ignore.is_some_and(|ignore| ignore.cfg.is_none()))
+}
+
+/// The arguments of `#[utoipa_ignore(reason = "internal", cfg(feature = "private-api"))]`
+struct Ignore {
+    reason: Option<String>,
+    /// The ignore only applies when the predicate holds
+    cfg: Option<String>,
+    /// The registrations ignored, `schema` or `response`, the whole item when empty
+    registrations: Vec<String>,
+}
+
+fn parse_ignore(attrs: &[Attribute]) -> syn::Result<Option<Ignore>> {
+    let Some(attr) = attrs.iter().find(|attr| attr.path().is_ident("utoipa_ignore")) else {
+        return Ok(None);
+    };
+    let mut ignore = Ignore {
+        reason: None,
+        cfg: None,
+        registrations: vec![],
+    };
+    if let Meta::List(list) = &attr.meta {
+        let nested = list
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            .map_err(|e| diagnostics::error(format!("Failed to parse utoipa_ignore attribute: {}", e)))?;
+        for meta in nested {
+            match meta {
+                Meta::NameValue(meta) if meta.path.is_ident("reason") => match meta.value {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(reason),
+                        ..
+                    }) => ignore.reason = Some(reason.value()),
+                    _ => return Err(diagnostics::error("Expected a string as the reason of utoipa_ignore")),
+                },
+                Meta::List(meta) if meta.path.is_ident("cfg") => ignore.cfg = Some(meta.tokens.to_string()),
+                // The shorter `feature = "..."` of `cfg(feature = "...")`
+                Meta::NameValue(meta) if meta.path.is_ident("feature") => match meta.value {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(feature),
+                        ..
+                    }) => ignore.cfg = Some(quote!(feature = #feature).to_string()),
+                    _ => return Err(diagnostics::error("Expected a string as the feature of utoipa_ignore")),
+                },
+                Meta::Path(path) if path.is_ident("schema") || path.is_ident("response") => {
+                    ignore.registrations.push(path_to_string(&path))
+                }
+                meta => {
+                    return Err(diagnostics::error(format!(
+                        "Unknown utoipa_ignore argument \"{}\", expected `reason = \"...\"`, `cfg(...)`, \
+                         `feature = \"...\"`, `schema` or `response`",
+                        path_to_string(meta.path())
+                    )))
+                }
+            }
+        }
+    }
+    Ok(Some(ignore))
+}
+
+/// Whether the item has a `#[utoipa_ignore]` of the whole item, conditional or not
+fn has_ignore(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident("utoipa_ignore"))
+        && !parse_ignore(attrs).is_ok_and(|ignore| ignore.is_some_and(|ignore| !ignore.registrations.is_empty()))
+}
+
+/// Whether the item is ignored by its `#[utoipa_ignore]`, if any.
+///
+/// The predicate of a conditional ignore is recorded in `out`, and checked against the assumed cfg values. Only the
+/// schemas and responses may be partially ignored, see `ignored_registrations`
+fn apply_ignore(
+    attrs: &[Attribute],
+    name: &syn::Path,
+    params: &Parameters,
+    out: &mut Vec<DiscoverType>,
+) -> syn::Result<bool> {
+    if let Some(ignore) = parse_ignore(attrs)?.filter(|ignore| !ignore.registrations.is_empty()) {
+        return Err(diagnostics::error(format!(
+            "`utoipa_ignore({})` of `{}` only applies to a schema or a response",
+            ignore.registrations.join(", "),
+            path_to_string(name)
+        )));
+    }
+    Ok(ignored_registrations(attrs, name, params, out)?.is_some())
+}
+
+/// The registrations of the item ignored by its `#[utoipa_ignore]`, e.g. `schema`, or all of them when empty.
+/// None if the item isn't ignored
+fn ignored_registrations(
+    attrs: &[Attribute],
+    name: &syn::Path,
+    params: &Parameters,
+    out: &mut Vec<DiscoverType>,
+) -> syn::Result<Option<Vec<String>>> {
+    let Some(ignore) = parse_ignore(attrs)? else {
+        return Ok(None);
+    };
+    let ignored = match ignore.cfg {
+        Some(cfg) => {
+            let ignored = params.cfg_value(&cfg);
+            out.push(DiscoverType::Cfg(cfg));
+            ignored
+        }
+        None => true,
+    };
+    if !ignored {
+        return Ok(None);
+    }
+    let ignored = match ignore.registrations.is_empty() {
+        true => path_to_string(name),
+        false => format!("the {} of {}", ignore.registrations.join(" and "), path_to_string(name)),
+    };
+    debug::log(format_args!(
+        "ignored {} ({})",
+        ignored,
+        ignore.reason.as_deref().unwrap_or("no reason given")
+    ));
+    Ok(Some(ignore.registrations))
+}
+
+/// In pedantic mode, or with `fail_on`, report the `#[utoipa_ignore]` of an item which wouldn't be discovered anyway
+fn check_unnecessary_ignore(
+    attrs: &[Attribute],
+    name: &syn::Path,
+    discoverable: bool,
+    params: &Parameters,
+    out: &mut Vec<DiscoverType>,
+) {
+    if params.checks(WarningKind::UnnecessaryIgnore) && !discoverable && has_ignore(attrs) {
+        out.push(DiscoverType::Warning(Warning::new(
+            WarningKind::UnnecessaryIgnore,
+            format!(
+                "`{}` is ignored, but wouldn't be discovered anyway",
+                path_to_string(name)
+            ),
+        )));
+    }
+}
+
+fn build_path(file_path: &syn::Path, fn_name: impl ToTokens) -> syn::Path {
+    syn::parse_quote!(#file_path::#fn_name)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use proc_macro2::TokenStream;
+    use quote::quote;
+    use syn::{ItemFn, ItemStruct};
+
+    use super::DiscoverType;
+    use crate::cache;
+    use crate::diagnostics::WarningKind;
+    use crate::path_utils::path_to_string;
+    use crate::spec::Spec;
+    use crate::test_utils::TempDir;
+    use crate::token_utils::{extract_attributes, Order};
+
+    #[test]
+    fn test_discover_from_str() {
+        let params = extract_attributes(quote! { prefer_reexports = true }).unwrap();
+        let discovered = super::discover_from_str(
+            r#"
+            pub use self::v1::get_user;
+            mod v1 {
+                #[utoipa::path(get, path = "/users/{id}")]
+                pub fn get_user() {}
+            }
+            "#,
+            "crate::api",
+            &params,
+        )
+        .unwrap();
+        let fns: Vec<String> = discovered.fns.iter().map(path_to_string).collect();
+        assert_eq!(fns, vec!["crate::api::get_user"]);
+
+        assert!(super::discover_from_str("pub fn", "crate", &params).is_err());
+        assert!(super::discover_from_str("", "not a path", &params).is_err());
+    }
+
+    #[test]
+    fn test_private_modules_reexports() {
+        let discovered = super::discover_from_str(
+            r#"
+            pub use detail::User;
+            pub use models::Pet;
+            mod detail {
+                #[derive(utoipa::ToSchema)]
+                pub struct User;
+                #[derive(utoipa::ToSchema)]
+                pub struct Order;
+            }
+            pub mod models {
+                #[derive(utoipa::ToSchema)]
+                pub struct Pet;
+            }
+            "#,
+            "crate",
+            &extract_attributes(quote! {}).unwrap(),
+        )
+        .unwrap();
+        let models: Vec<String> = discovered.models.iter().map(path_to_string).collect();
+        assert_eq!(
+            models,
+            vec!["crate::User", "crate::detail::Order", "crate::models::Pet"]
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        let source = r#"
+            pub use self::api::*;
+            pub use self::api::users::User as ApiUser;
+            pub mod api {
+                pub use self::pets::get_pet;
+                pub mod pets {
+                    #[utoipa::path(get, path = "/pets/{id}")]
+                    pub fn get_pet() {}
+                }
+                pub mod users {
+                    #[derive(utoipa::ToSchema)]
+                    pub struct User;
+                }
+                pub mod internal {
+                    #[derive(utoipa::ToSchema)]
+                    pub struct Secret;
+                }
+            }
+            "#;
+        let params = extract_attributes(quote! { flatten = true }).unwrap();
+        let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        let fns: Vec<String> = discovered.fns.iter().map(path_to_string).collect();
+        let models: Vec<String> = discovered.models.iter().map(path_to_string).collect();
+        assert_eq!(fns, vec!["crate::get_pet"]);
+        assert_eq!(models, vec!["crate::ApiUser", "crate::api::internal::Secret"]);
+        assert_eq!(path_to_string(&discovered.routes[0].0), "crate::get_pet");
+    }
+
+    #[test]
+    fn test_discover_from_tokens() {
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let tokens = quote! {
+            #[derive(utoipa::ToSchema)]
+            pub struct User;
+
+            #[derive(utoipa::ToResponse)]
+            pub struct NotFound;
+        };
+        let discovered = super::discover_from_tokens(tokens, syn::parse_quote!(crate::users), &params).unwrap();
+        assert_eq!(path_to_string(&discovered.models[0]), "crate::users::User");
+        assert_eq!(path_to_string(&discovered.responses[0]), "crate::users::NotFound");
+
+        assert!(super::discover_from_tokens(quote!(pub struct), syn::parse_quote!(crate), &params).is_err());
+    }
+
+    #[test]
+    fn test_resolve_module_layout() {
+        let files: Vec<PathBuf> = vec!["./src/lib.rs".into(), "./src/api.rs".into(), "./src/api/mod.rs".into()];
+        let mut params = extract_attributes(quote! { module_files = ["crate::api => ./src/api/mod.rs"] }).unwrap();
+        let kept = super::resolve_module_layout(files.clone(), "crate", &params).unwrap();
+        assert_eq!(
+            kept,
+            vec![PathBuf::from("./src/lib.rs"), PathBuf::from("./src/api/mod.rs")]
+        );
+
+        params.module_files = vec![("crate::api".to_string(), "src/api.rs".to_string())];
+        let kept = super::resolve_module_layout(files, "crate", &params).unwrap();
+        assert_eq!(kept, vec![PathBuf::from("./src/lib.rs"), PathBuf::from("./src/api.rs")]);
+
+        let files: Vec<PathBuf> = vec!["./src/api.rs".into(), "./src/api/users.rs".into()];
+        assert_eq!(
+            super::resolve_module_layout(files.clone(), "crate", &params).unwrap(),
+            files
+        );
+    }
+
+    #[test]
+    fn test_resolve_module_layout_ambiguous() {
+        let files: Vec<PathBuf> = vec!["./src/api.rs".into(), "./src/api/mod.rs".into()];
+        let error =
+            super::resolve_module_layout(files, "crate", &extract_attributes(TokenStream::new()).unwrap()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "utoipauto: `./src/api.rs` and `./src/api/mod.rs` are both the module `crate::api`. Remove one of them, or \
+             choose it with `module_files = [\"crate::api => ./src/api.rs\"]`"
+        );
+    }
+
+    #[test]
+    fn test_parse_routes() {
+        let f: ItemFn = syn::parse_quote! {
+            #[utoipa::path(get, path = "/users/{id}", responses((status = 200, body = User)))]
+            pub fn get_user() {}
+        };
+        assert_eq!(super::parse_routes(&f.attrs[0]), vec!["GET /users/{id}"]);
+
+        let f: ItemFn = syn::parse_quote! {
+            #[utoipa::path(method(get, head), context_path = "/api", path = "/health", tag = "health")]
+            pub fn health() {}
+        };
+        assert_eq!(
+            super::parse_routes(&f.attrs[0]),
+            vec!["GET /api/health", "HEAD /api/health"]
+        );
+
+        let f: ItemFn = syn::parse_quote! {
+            #[handler]
+            pub fn custom() {}
+        };
+        assert!(super::parse_routes(&f.attrs[0]).is_empty());
+    }
+
+    #[test]
+    fn test_check_routes() {
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let mut discovered = super::discover_from_str(
+            r#"
+            #[utoipa::path(get, path = "/users")]
+            pub fn list_users() {}
+            #[utoipa::path(post, path = "/users")]
+            pub fn create_user() {}
+            mod v2 {
+                #[utoipa::path(get, path = "/users")]
+                pub fn list_users() {}
+            }
+            "#,
+            "crate",
+            &params,
+        )
+        .unwrap();
+        discovered.check_routes();
+        assert_eq!(discovered.warnings.len(), 1);
+        assert_eq!(
+            discovered.warnings[0].message,
+            "`crate::list_users` and `crate::v2::list_users` both declare `GET /users`"
+        );
+
+        // Only the handlers still discovered are checked
+        discovered.warnings.clear();
+        discovered.fns.retain(|handler| handler.segments.len() == 2);
+        discovered.check_routes();
+        assert!(discovered.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_schema_source() {
+        let source = r#"
+            #[derive(utoipa::ToSchema)]
+            pub struct Pet;
+            pub struct Money(foreign::Money);
+            impl utoipa::ToSchema for Money {}
+        "#;
+        let schemas = |attributes: TokenStream| -> Vec<String> {
+            let params = extract_attributes(attributes).unwrap();
+            let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+            discovered.models.iter().map(path_to_string).collect()
+        };
+        assert_eq!(schemas(quote! {}), vec!["crate::Pet", "crate::Money"]);
+        assert_eq!(schemas(quote! { schema_source = "derives" }), vec!["crate::Pet"]);
+        assert_eq!(schemas(quote! { schema_source = "impls" }), vec!["crate::Money"]);
+    }
+
+    #[test]
+    fn test_qualified_impls() {
+        let source = r#"
+            pub struct Money;
+            impl utoipa::ToSchema for Money {}
+            pub struct Score;
+            impl my_crate::ToSchema for Score {}
+            pub struct Rate;
+            impl ToSchema for Rate {}
+            pub struct NotFound;
+            impl openapi::ToResponse for NotFound {}
+        "#;
+        let discover = |attributes: TokenStream| {
+            let params = extract_attributes(attributes).unwrap();
+            let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+            let paths = |paths: &[syn::Path]| paths.iter().map(path_to_string).collect::<Vec<_>>();
+            (paths(&discovered.models), paths(&discovered.responses))
+        };
+        assert_eq!(
+            discover(quote! {}).0,
+            vec!["crate::Money", "crate::Score", "crate::Rate"]
+        );
+        assert_eq!(
+            discover(quote! { qualified_impls = true }),
+            (vec!["crate::Money".to_string()], vec![])
+        );
+        assert_eq!(
+            discover(quote! { qualified_impls = true, utoipa_crate = "openapi" }),
+            (vec![], vec!["crate::NotFound".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_schema_derives() {
+        let source = r#"
+            #[derive(ApiModel)]
+            pub struct Pet;
+            #[derive(macros::DomainSchema, ApiResponse)]
+            pub struct Order;
+            #[derive(Debug)]
+            pub struct Money;
+        "#;
+        let params = extract_attributes(quote! {
+            schema_derives = ["ApiModel", "DomainSchema"],
+            response_derives = ["ApiResponse"],
+        })
+        .unwrap();
+        let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        let models: Vec<String> = discovered.models.iter().map(path_to_string).collect();
+        assert_eq!(models, vec!["crate::Pet", "crate::Order"]);
+        let responses: Vec<String> = discovered.responses.iter().map(path_to_string).collect();
+        assert_eq!(responses, vec!["crate::Order"]);
+    }
+
+    #[test]
+    fn test_utoipa_crate() {
+        let source = r#"
+            #[derive(openapi::ToSchema)]
+            pub struct Pet;
+            #[derive(framework::openapi::ToSchema, framework::openapi::ToResponse)]
+            pub struct Order;
+            #[derive(utoipa::ToSchema)]
+            pub struct Money;
+            #[openapi::path(get, path = "/pets")]
+            pub fn list_pets() {}
+        "#;
+        let discover = |attributes: TokenStream| {
+            let params = extract_attributes(attributes).unwrap();
+            let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+            let paths = |paths: &[syn::Path]| paths.iter().map(path_to_string).collect::<Vec<_>>();
+            (
+                paths(&discovered.models),
+                paths(&discovered.responses),
+                paths(&discovered.fns),
+            )
+        };
+        assert_eq!(
+            discover(quote! { utoipa_crate = "openapi" }),
+            (
+                vec!["crate::Pet".to_string()],
+                vec![],
+                vec!["crate::list_pets".to_string()]
+            )
+        );
+        assert_eq!(
+            discover(quote! { utoipa_crate = "framework::openapi" }),
+            (
+                vec!["crate::Order".to_string()],
+                vec!["crate::Order".to_string()],
+                vec!["crate::list_pets".to_string()]
+            )
+        );
+        assert_eq!(discover(quote! {}).0, vec!["crate::Money"]);
+    }
+
+    #[test]
+    fn test_custom_impls() {
+        let source = r#"
+            #[derive(utoipa::ToSchema, utoipa::ToResponse)]
+            pub struct Pet;
+            pub struct Money(foreign::Money);
+            impl utoipa::ToSchema for Money {}
+            pub struct NotFound;
+            impl utoipa::ToResponse<'_> for NotFound {}
+        "#;
+        let params = extract_attributes(quote! { custom_impls = ["schemas => custom", "responses => none"] }).unwrap();
+        let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        let paths = |paths: &Vec<syn::Path>| paths.iter().map(path_to_string).collect::<Vec<_>>();
+        assert_eq!(paths(&discovered.models), vec!["crate::Pet"]);
+        assert_eq!(paths(&discovered.responses), vec!["crate::Pet"]);
+        assert_eq!(paths(&discovered.custom_impls), vec!["crate::Money"]);
+
+        let params = extract_attributes(quote! { custom_impls = ["responses => schemas"] }).unwrap();
+        let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        assert_eq!(
+            paths(&discovered.models),
+            vec!["crate::Pet", "crate::Money", "crate::NotFound"]
+        );
+        assert_eq!(paths(&discovered.responses), vec!["crate::Pet"]);
+    }
+
+    #[test]
+    fn test_feature_gates() {
+        let lib: syn::File = syn::parse_quote! {
+            #[cfg(feature = "admin")]
+            pub mod admin;
+            #[cfg(all(feature = "v2", not(feature = "legacy")))]
+            pub mod v2 {
+                #[utoipa::path(get, path = "/v2/users")]
+                pub fn get_users() {}
+            }
+            #[cfg(feature = "utoipauto-test-enabled")]
+            #[utoipa::path(get, path = "/enabled")]
+            pub fn enabled() {}
+            #[cfg(test)]
+            #[utoipa::path(get, path = "/test")]
+            pub fn test_only() {}
+        };
+        let admin: syn::File = syn::parse_quote! {
+            #[utoipa::path(get, path = "/admin")]
+            pub fn get_stats() {}
+        };
+        let files = || {
+            vec![
+                super::ModuleFile::parsed("./src/lib.rs".to_string(), syn::parse_quote!(crate), lib.clone()),
+                super::ModuleFile::parsed(
+                    "./src/admin.rs".to_string(),
+                    syn::parse_quote!(crate::admin),
+                    admin.clone(),
+                ),
+            ]
+        };
+        std::env::set_var("CARGO_FEATURE_UTOIPAUTO_TEST_ENABLED", "1");
+        let mut params = extract_attributes(TokenStream::new()).unwrap();
+        let discoveries = super::discover_from_modules(files(), &params).and_then(|discovered| {
+            params.cfg_values = discovered.cfgs.iter().map(|cfg| (cfg.clone(), true)).collect();
+            Ok((discovered, super::discover_from_modules(files(), &params)?))
+        });
+        // The fingerprints of the caches hash every `CARGO_FEATURE_*` variable
+        std::env::remove_var("CARGO_FEATURE_UTOIPAUTO_TEST_ENABLED");
+        let (discovered, variant) = discoveries.unwrap();
+        let fns: Vec<String> = discovered.fns.iter().map(path_to_string).collect();
+        assert_eq!(fns, vec!["crate::enabled", "crate::test_only"]);
+        assert_eq!(
+            discovered.cfgs,
+            vec![
+                "feature = \"admin\"",
+                "all (feature = \"v2\" , not (feature = \"legacy\"))"
+            ]
+        );
+
+        let fns: Vec<String> = variant.fns.iter().map(path_to_string).collect();
+        assert_eq!(
+            fns,
+            vec![
+                "crate::v2::get_users",
+                "crate::enabled",
+                "crate::test_only",
+                "crate::admin::get_stats"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generic_schemas() {
+        let source = r#"
+            pub mod models {
+                #[derive(utoipa::ToSchema)]
+                pub struct Paginated<T> { items: Vec<T> }
+                #[derive(utoipa::ToSchema)]
+                pub struct User;
+                #[derive(utoipa::ToResponse)]
+                pub struct Page<T>(T);
+            }
+        "#;
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let mut discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        let paths = |paths: &Vec<syn::Path>| paths.iter().map(path_to_string).collect::<Vec<_>>();
+        assert_eq!(paths(&discovered.models), vec!["crate::models::User"]);
+        assert_eq!(paths(&discovered.generic_models), vec!["crate::models::Paginated"]);
+        assert!(discovered.responses.is_empty());
+
+        let instances: Vec<syn::Path> = vec![
+            syn::parse_quote!(Paginated<User>),
+            syn::parse_quote!(Paginated<String>),
+            syn::parse_quote!(self::models::Paginated<crate::Other>),
+        ];
+        discovered
+            .instantiate_generic_models(&instances, &syn::parse_quote!(crate))
+            .unwrap();
+        assert_eq!(
+            paths(&discovered.models),
+            vec![
+                "crate::models::User",
+                "crate::models::Paginated<crate::models::User>",
+                "crate::models::Paginated<String>",
+                "crate::models::Paginated<crate::Other>",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generic_schemas_unknown() {
+        let mut discovered = super::Discovered::default();
+        let error = discovered
+            .instantiate_generic_models(&[syn::parse_quote!(Page<User>)], &syn::parse_quote!(crate))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "utoipauto: `Page<User>` of generic_schemas is not a discovered generic schema"
+        );
+    }
+
+    #[test]
+    fn test_retain_matching() {
+        let source = r#"
+            pub mod api {
+                #[utoipa::path(get, path = "/users", tag = "users")]
+                pub fn get_users() {}
+                #[utoipa::path(post, path = "/users", tags = ["users", "internal"])]
+                pub fn create_user() {}
+                #[utoipa::path(delete, path = "/users/{id}")]
+                pub fn delete_user() {}
+                #[derive(utoipa::ToSchema)]
+                pub struct User;
+            }
+            #[derive(utoipa::ToSchema)]
+            pub struct Stats;
+        "#;
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let mut discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        let filter = crate::filter::Filter::parse(
+            "module.starts_with('crate::api') && (kind != 'handler' || !tag('internal') && method in ['get', 'post'])",
+        )
+        .unwrap();
+        discovered.retain_matching(&filter);
+        let paths = |paths: &Vec<syn::Path>| paths.iter().map(path_to_string).collect::<Vec<_>>();
+        assert_eq!(paths(&discovered.fns), vec!["crate::api::get_users"]);
+        assert_eq!(paths(&discovered.models), vec!["crate::api::User"]);
+    }
+
+    #[test]
+    fn test_unresolved_impl() {
+        let source = r#"
+            pub struct Pet;
+            impl utoipa::ToSchema for Pet {}
+            impl utoipa::ToSchema for <Pet as Provider>::Output {}
+            impl<'a> utoipa::ToResponse<'a> for &'a Pet {}
+            impl utoipa::ToSchema for Self {}
+        "#;
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let discovered = super::discover_from_str(source, "crate::pets", &params).unwrap();
+        let models: Vec<String> = discovered.models.iter().map(path_to_string).collect();
+        assert_eq!(models, vec!["crate::pets::Pet"]);
+        assert!(discovered.responses.is_empty());
+        let messages: Vec<&str> = discovered.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "the self type of `impl ToSchema for <Pet as Provider>::Output` in `crate::pets` can't be named from \
+                 its module, register the type with `extra_schemas` instead",
+                "the self type of `impl ToResponse for &'a Pet` in `crate::pets` can't be named from its module, \
+                 register the type with `extra_responses` instead",
+                "the self type of `impl ToSchema for Self` in `crate::pets` can't be named from its module, register \
+                 the type with `extra_schemas` instead",
+            ]
+        );
+        assert!(discovered
+            .warnings
+            .iter()
+            .all(|warning| warning.kind == WarningKind::UnresolvedImpl));
+    }
+
+    #[test]
+    fn test_require_descriptions() {
+        let source = r#"
+            /// List the users
+            #[utoipa::path(get, path = "/users")]
+            pub fn list_users() {}
+            #[utoipa::path(post, path = "/users", description = "Create a user")]
+            pub fn create_user() {}
+            #[utoipa::path(delete, path = "/users/{id}", responses((status = 204, description = "Deleted")))]
+            pub fn delete_user() {}
+            ///
+            #[utoipa::path(get, path = "/health")]
+            pub fn health() {}
+        "#;
+        let params = extract_attributes(quote! { require_descriptions = true }).unwrap();
+        let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        let messages: Vec<&str> = discovered.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`crate::delete_user` has no description, add a doc comment or `description = \"...\"`",
+                "`crate::health` has no description, add a doc comment or `description = \"...\"`",
+            ]
+        );
+        assert!(discovered
+            .warnings
+            .iter()
+            .all(|w| w.kind == WarningKind::UndescribedHandler));
+
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        assert!(super::discover_from_str(source, "crate", &params)
+            .unwrap()
+            .warnings
+            .is_empty());
+    }
+
+    #[test]
+    fn test_impl_methods() {
+        let source = r#"
+            pub struct UserController;
+            impl UserController {
+                #[utoipa::path(get, path = "/users")]
+                pub async fn list() {}
+            }
+            pub trait PetController {
+                #[utoipa::path(get, path = "/pets")]
+                fn list() {}
+            }
+        "#;
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        assert!(super::discover_from_str(source, "crate", &params)
+            .unwrap()
+            .fns
+            .is_empty());
+    }
+
+    #[test]
+    fn test_require_documented_routes() {
+        let source = r#"
+            #[utoipa::path(get, path = "/users")]
+            #[get("/users")]
+            pub async fn list_users() {}
+            #[actix_web::post("/users")]
+            pub async fn create_user() {}
+            #[utoipa_ignore]
+            #[delete("/users/{id}")]
+            pub async fn delete_user() {}
+            #[cached]
+            pub fn helper() {}
+        "#;
+        let params = extract_attributes(quote! { require_documented_routes = true }).unwrap();
+        let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        let messages: Vec<&str> = discovered.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["`crate::create_user` is routed with `#[actix_web::post]` but has no `#[utoipa::path]`"]
+        );
+        assert_eq!(discovered.warnings[0].kind, WarningKind::UndocumentedRoute);
+
+        let params =
+            extract_attributes(quote! { require_documented_routes = true, route_attributes = ["cached"] }).unwrap();
+        let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        assert_eq!(
+            discovered.warnings[0].message,
+            "`crate::helper` is routed with `#[cached]` but has no `#[utoipa::path]`"
+        );
+
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        assert!(super::discover_from_str(source, "crate", &params)
+            .unwrap()
+            .warnings
+            .is_empty());
+    }
+
+    #[test]
+    fn test_require_response_bodies() {
+        let source = r#"
+            #[utoipa::path(get, path = "/users", responses((status = 200, body = Vec<User>), (status = 404)))]
+            pub fn list_users() {}
+            #[utoipa::path(get, path = "/users/{id}", responses(UserResponses))]
+            pub fn get_user() {}
+            #[utoipa::path(delete, path = "/users/{id}", responses((status = 204, description = "Deleted")))]
+            pub fn delete_user() {}
+            #[utoipa::path(get, path = "/health")]
+            pub fn health() {}
+        "#;
+        let params = extract_attributes(quote! { require_response_bodies = true }).unwrap();
+        let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        let messages: Vec<&str> = discovered.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`crate::delete_user` only documents status codes, without a body",
+                "`crate::health` documents no responses",
+            ]
+        );
+        assert!(discovered
+            .warnings
+            .iter()
+            .all(|w| w.kind == WarningKind::MissingResponseBody));
+    }
+
+    #[test]
+    fn test_check_removed_endpoints() {
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let mut discovered = super::discover_from_str(
+            r#"
+            #[utoipa::path(get, path = "/users")]
+            pub fn list_users() {}
+            #[utoipa::path(post, path = "/users")]
+            pub fn create_user() {}
+            #[utoipa::path(delete, path = "/users/{id}")]
+            pub fn delete_user() {}
+            "#,
+            "crate",
+            &params,
+        )
+        .unwrap();
+        // Listed by hand in the openapi macro
+        discovered.dedup(&["crate::delete_user".to_string()]);
+        let previous = Spec {
+            endpoints: vec![
+                "GET /api/users".to_string(),
+                "POST /users".to_string(),
+                "DELETE /users/{id}".to_string(),
+                "GET /health".to_string(),
+            ],
+        };
+        discovered.check_removed_endpoints(&previous, &[]);
+        let messages: Vec<&str> = discovered.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`DELETE /users/{id}` was removed since the previous documentation",
+                "`GET /health` was removed since the previous documentation",
+            ]
+        );
+
+        // The endpoints of the handlers listed by hand are still declared
+        discovered.warnings.clear();
+        discovered.check_removed_endpoints(&previous, &["crate::delete_user".to_string()]);
+        assert_eq!(discovered.warnings.len(), 1);
+        assert_eq!(discovered.warnings[0].kind, WarningKind::RemovedEndpoint);
+    }
+
+    #[test]
+    fn test_macro_templates() {
+        let params = extract_attributes(quote! { macros = ["endpoint! => path(0)", "model! => schema(1)"] }).unwrap();
+        let discovered = super::discover_from_str(
+            r#"
+            endpoint!(get_user, GET, "/users/{id}");
+            endpoint!(super::health::check, GET, "/health");
+            #[utoipa_ignore]
+            endpoint!(internal, GET, "/internal");
+            model!(derive(Clone), User);
+            other!(not_discovered);
+            "#,
+            "crate::users",
+            &params,
+        )
+        .unwrap();
+        let fns: Vec<String> = discovered.fns.iter().map(path_to_string).collect();
+        assert_eq!(fns, vec!["crate::users::get_user", "crate::health::check"]);
+        let models: Vec<String> = discovered.models.iter().map(path_to_string).collect();
+        assert_eq!(models, vec!["crate::users::User"]);
+    }
+
+    #[test]
+    fn test_macro_templates_invalid() {
+        let error = extract_attributes(quote! { macros = ["endpoint! => handler(0)"] })
+            .map(|_| ())
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("utoipauto: Invalid macros template \"endpoint! => handler(0)\""));
+    }
+
+    #[test]
+    fn test_reroot() {
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let facade: syn::File = syn::parse_quote! {
+            pub use users_internal::handlers::get_user;
+            pub use users_internal::models as user_models;
+            pub use billing_internal::*;
+        };
+        let reexports: Vec<super::Reexport> =
+            super::parse_module_items(syn::parse_quote!(crate), facade.items, 0, &params)
+                .unwrap()
+                .into_iter()
+                .filter_map(|item| match item {
+                    DiscoverType::Reexport(reexport) => Some(reexport),
+                    _ => None,
+                })
+                .collect();
+        let mut discovered = super::Discovered {
+            fns: vec![
+                syn::parse_quote!(users_internal::handlers::get_user),
+                syn::parse_quote!(billing_internal::invoices::list_invoices),
+                syn::parse_quote!(crate::health),
+            ],
+            models: vec![syn::parse_quote!(users_internal::models::User)],
+            ..Default::default()
+        };
+        discovered.reroot_paths("my_api_facade", &reexports).unwrap();
+        let fns: Vec<String> = discovered.fns.iter().map(path_to_string).collect();
+        assert_eq!(
+            fns,
+            vec!["crate::get_user", "crate::invoices::list_invoices", "crate::health"]
+        );
+        assert_eq!(path_to_string(&discovered.models[0]), "crate::user_models::User");
+    }
+
+    #[test]
+    fn test_reroot_facade_dir() {
+        let params = extract_attributes(TokenStream::new()).unwrap();
+        let root = TempDir::new("reroot");
+        fs::create_dir_all(root.join("facade/src")).unwrap();
+        fs::write(root.join("Cargo.toml"), "[workspace]\nmembers = [\"facade\"]").unwrap();
+        fs::write(root.join("facade/Cargo.toml"), "[package]\nname = \"my-facade\"").unwrap();
+        fs::write(root.join("facade/src/lib.rs"), "pub use users_internal::get_user;").unwrap();
+        let facade_dir = root.join("facade").to_string_lossy().to_string();
+        let discovered = || super::Discovered {
+            fns: vec![syn::parse_quote!(users_internal::get_user)],
+            ..Default::default()
+        };
+        let mut rerooted = discovered();
+        let result = rerooted.reroot("my-facade", &facade_dir, &params);
+        let other = discovered().reroot("my-api", &facade_dir, &params).unwrap_err();
+
+        result.unwrap();
+        assert_eq!(path_to_string(&rerooted.fns[0]), "crate::get_user");
+        assert_eq!(
+            other.to_string(),
+            "utoipauto: reroot = \"my-api\" expects the doc struct in the facade crate, but it is in `my_facade`"
+        );
+    }
+
+    #[test]
+    fn test_reroot_missing_reexport() {
+        let mut discovered = super::Discovered {
+            fns: vec![syn::parse_quote!(users_internal::handlers::delete_user)],
+            ..Default::default()
+        };
+        let error = discovered.reroot_paths("my_api_facade", &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "utoipauto: `users_internal::handlers::delete_user` is not re-exported by the facade crate \
+             `my_api_facade`, add `pub use users_internal::handlers::delete_user;` to it"
+        );
+    }
+
+    #[test]
+    fn test_parse_function() {
+        let quoted = quote! {
+            #[utoipa]
+            pub fn route_custom() {}
+        };
+
+        let item_fn: ItemFn = syn::parse2(quoted).unwrap();
+        let fn_name = super::parse_function(&item_fn, &["utoipa".to_string()]);
+        assert_eq!(fn_name, vec!["route_custom"]);
+
+        let quoted = quote! {
+            #[handler]
+            pub fn route_custom() {}
+        };
+
+        let item_fn: ItemFn = syn::parse2(quoted).unwrap();
+        let fn_name = super::parse_function(&item_fn, &["handler".to_string()]);
+        assert_eq!(fn_name, vec!["route_custom"]);
+
+        let names = ["path".to_string(), "endpoint".to_string()];
+        let quoted = quote! {
+            #[endpoint]
+            pub fn route_endpoint() {}
+        };
+        let item_fn: ItemFn = syn::parse2(quoted).unwrap();
+        assert_eq!(super::parse_function(&item_fn, &names), vec!["route_endpoint"]);
+        let quoted = quote! {
+            #[utoipa::path(get, path = "/")]
+            pub fn route_path() {}
+        };
+        let item_fn: ItemFn = syn::parse2(quoted).unwrap();
+        assert_eq!(super::parse_function(&item_fn, &names), vec!["route_path"]);
+    }
+
+    #[test]
+    fn test_parse_modifier_marker() {
+        let quoted = quote! {
+            #[utoipauto_modifier]
+            pub struct SecurityAddon;
+        };
+
+        let item_struct: ItemStruct = syn::parse2(quoted).unwrap();
+        let discovered = super::parse_from_attr(
+            &item_struct.attrs,
+            syn::parse_quote!(crate::SecurityAddon),
+            item_struct.generics.params,
+            &extract_attributes(quote! {}).unwrap(),
+        )
+        .unwrap();
+        assert!(matches!(
+            discovered.as_slice(),
+            [DiscoverType::Modifier(path)] if path_to_string(path) == "crate::SecurityAddon"
+        ));
+    }
+
+    #[test]
+    fn test_modifier_impls() {
+        let discovered = super::discover_from_str(
+            r#"
+            pub struct SecurityAddon;
+            impl utoipa::Modify for SecurityAddon {
+                fn modify(&self, openapi: &mut OpenApi) {}
+            }
+
+            #[utoipauto_modifier]
+            pub struct TitleAddon;
+            impl Modify for TitleAddon {
+                fn modify(&self, openapi: &mut OpenApi) {}
+            }
+
+            pub struct ServersAddon { servers: Vec<String> }
+            impl Modify for ServersAddon {
+                fn modify(&self, openapi: &mut OpenApi) {}
+            }
+            impl Modify for foreign::Addon {
+                fn modify(&self, openapi: &mut OpenApi) {}
+            }
+            "#,
+            "crate",
+            &extract_attributes(quote! {}).unwrap(),
+        )
+        .unwrap();
+        let mut modifiers: Vec<String> = discovered.modifiers.iter().map(path_to_string).collect();
+        modifiers.sort();
+        modifiers.dedup();
+        assert_eq!(modifiers, vec!["crate::SecurityAddon", "crate::TitleAddon"]);
+    }
+
+    /// Leave out the visibility of the parsed items, and the routes and tags of the handlers
+    fn discovered_items(discovered: Vec<DiscoverType>) -> Vec<DiscoverType> {
+        discovered
+            .into_iter()
+            .filter(|item| {
+                !matches!(
+                    item,
+                    DiscoverType::Scope(..) | DiscoverType::Route(..) | DiscoverType::Tag(..)
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_ignore() {
+        let item_fn: ItemFn = syn::parse_quote! {
+            #[utoipa_ignore(reason = "internal", cfg(feature = "private-api"))]
+            #[utoipa::path(get, path = "/")]
+            pub fn route() {}
+        };
+        let ignore = super::parse_ignore(&item_fn.attrs).unwrap().expect("not ignored");
+        assert_eq!(ignore.reason.as_deref(), Some("internal"));
+        assert_eq!(ignore.cfg.as_deref(), Some("feature = \"private-api\""));
+
+        let item_fn: ItemFn = syn::parse_quote! {
+            #[utoipa_ignore]
+            pub fn route() {}
+        };
+        let ignore = super::parse_ignore(&item_fn.attrs).unwrap().expect("not ignored");
+        assert!(ignore.reason.is_none() && ignore.cfg.is_none());
+    }
+
+    #[test]
+    fn test_conditional_ignore() {
+        let items: syn::File = syn::parse_quote! {
+            #[utoipa_ignore(cfg(feature = "private-api"))]
+            #[utoipa::path(get, path = "/")]
+            pub fn route() {}
+        };
+        let mut params = extract_attributes(quote! {}).unwrap();
+        let discovered = discovered_items(
+            super::parse_module_items(syn::parse_quote!(crate), items.items.clone(), 0, &params).unwrap(),
+        );
+        assert!(matches!(
+            discovered.as_slice(),
+            [DiscoverType::Cfg(cfg), DiscoverType::Fn(path)]
+                if cfg == "feature = \"private-api\"" && path_to_string(path) == "crate::route"
+        ));
+
+        params.cfg_values = vec![("feature = \"private-api\"".to_string(), true)];
+        let discovered =
+            discovered_items(super::parse_module_items(syn::parse_quote!(crate), items.items, 0, &params).unwrap());
+        assert!(matches!(discovered.as_slice(), [DiscoverType::Cfg(_)]));
+
+        // The shorter form is the same predicate
+        let items: syn::File = syn::parse_quote! {
+            #[utoipa_ignore(feature = "private-api")]
+            #[utoipa::path(get, path = "/")]
+            pub fn route() {}
+        };
+        let discovered =
+            discovered_items(super::parse_module_items(syn::parse_quote!(crate), items.items, 0, &params).unwrap());
+        assert!(matches!(discovered.as_slice(), [DiscoverType::Cfg(cfg)] if cfg == "feature = \"private-api\""));
+    }
+
+    #[test]
+    fn test_ignored_module() {
+        let items: syn::File = syn::parse_quote! {
+            #[utoipa_ignore(reason = "internal")]
+            mod internal {
+                #[utoipa::path(get, path = "/internal")]
+                pub fn route() {}
+            }
+            #[utoipa_ignore]
+            mod admin;
+            mod api {
+                #[utoipa::path(get, path = "/")]
+                pub fn route() {}
+            }
+        };
+        let params = extract_attributes(quote! {}).unwrap();
+        let discovered =
+            discovered_items(super::parse_module_items(syn::parse_quote!(crate), items.items, 0, &params).unwrap());
+        assert!(matches!(
+            discovered.as_slice(),
+            [DiscoverType::GatedModule(module), DiscoverType::Fn(path)]
+                if path_to_string(module) == "crate::admin" && path_to_string(path) == "crate::api::route"
+        ));
+    }
+
+    #[test]
+    fn test_ignored_impl() {
+        let source = r#"
+            pub struct LegacyType;
+            #[utoipa_ignore(reason = "kept out of the components")]
+            impl utoipa::ToSchema for LegacyType {}
+            pub struct Money;
+            impl utoipa::ToSchema for Money {}
+            #[utoipa_ignore]
+            impl std::fmt::Display for Money {}
+        "#;
+        let params = extract_attributes(quote! { pedantic = true }).unwrap();
+        let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        let models: Vec<String> = discovered.models.iter().map(path_to_string).collect();
+        assert_eq!(models, vec!["crate::Money"]);
+        let messages: Vec<&str> = discovered.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["`crate::Money` is ignored, but wouldn't be discovered anyway"]
+        );
+    }
+
+    #[test]
+    fn test_include() {
+        let source = r#"
+            #[utoipa_include(schema, response)]
+            pub struct Opaque;
+            #[utoipauto::utoipa_include(schema)]
+            #[derive(utoipa::ToSchema)]
+            pub struct Pet;
+            #[utoipa_include(path)]
+            #[generated_route]
+            pub fn route() {}
+        "#;
+        let params = extract_attributes(quote! {}).unwrap();
+        let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        let paths = |paths: &[syn::Path]| paths.iter().map(path_to_string).collect::<Vec<_>>();
+        assert_eq!(paths(&discovered.models), vec!["crate::Opaque", "crate::Pet"]);
+        assert_eq!(paths(&discovered.responses), vec!["crate::Opaque"]);
+        assert_eq!(paths(&discovered.fns), vec!["crate::route"]);
+
+        let error = |source: &str| {
+            super::discover_from_str(source, "crate", &params)
+                .map(|_| ())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("#[utoipa_include(path)] pub struct Opaque;"),
+            "utoipauto: Expected #[utoipa_include(schema, response)] on `crate::Opaque`, in `crate`"
+        );
+        assert_eq!(
+            error("#[utoipa_include] pub fn route() {}"),
+            "utoipauto: Expected #[utoipa_include(path)] on `crate::route`, in `crate`"
+        );
+    }
+
+    #[test]
+    fn test_partial_ignore() {
+        let source = r#"
+            #[utoipa_ignore(response)]
+            #[derive(utoipa::ToSchema, utoipa::ToResponse)]
+            pub struct Pet;
+            #[utoipa_ignore(schema, reason = "only a response")]
+            #[derive(utoipa::ToSchema, utoipa::ToResponse)]
+            pub struct NotFound;
+            #[utoipa_ignore(schema, response)]
+            #[derive(utoipa::ToSchema, utoipa::ToResponse)]
+            pub struct Internal;
+        "#;
+        let params = extract_attributes(quote! {}).unwrap();
+        let discovered = super::discover_from_str(source, "crate", &params).unwrap();
+        let models: Vec<String> = discovered.models.iter().map(path_to_string).collect();
+        assert_eq!(models, vec!["crate::Pet"]);
+        let responses: Vec<String> = discovered.responses.iter().map(path_to_string).collect();
+        assert_eq!(responses, vec!["crate::NotFound"]);
+
+        let error = super::discover_from_str(
+            r#"
+            #[utoipa_ignore(schema)]
+            #[utoipa::path(get, path = "/")]
+            pub fn route() {}
+            "#,
+            "crate",
+            &params,
+        )
+        .map(|_| ())
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "utoipauto: `utoipa_ignore(schema)` of `crate::route` only applies to a schema or a response, in `crate`"
+        );
+    }
+
+    #[test]
+    fn test_max_item_depth() {
+        let items: syn::File = syn::parse_quote! {
+            #[utoipa::path(get, path = "/")]
+            pub fn root() {}
+            mod a {
+                #[utoipa::path(get, path = "/a")]
+                pub fn route() {}
+                mod b {
+                    #[utoipa::path(get, path = "/a/b")]
+                    pub fn route() {}
+                }
+            }
+        };
+        let mut params = extract_attributes(quote! { max_item_depth = 1 }).unwrap();
+        let discovered = super::parse_module_items(syn::parse_quote!(crate), items.items.clone(), 0, &params).unwrap();
+        let fns: Vec<String> = discovered
+            .iter()
+            .filter_map(|item| match item {
+                DiscoverType::Fn(path) => Some(path_to_string(path)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(fns, vec!["crate::root", "crate::a::route"]);
+
+        params.max_item_depth = None;
+        let discovered =
+            discovered_items(super::parse_module_items(syn::parse_quote!(crate), items.items, 0, &params).unwrap());
+        assert_eq!(discovered.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_response_bodies() {
+        let items: syn::File = syn::parse_quote! {
+            #[derive(utoipa::IntoResponses)]
+            enum UserResponses {
+                #[response(status = 200, description = "The user", body = User)]
+                Success,
+                #[response(status = 207, body = HashMap<String, Vec<User>>, description = "Users")]
+                Several,
+                #[response(status = 404)]
+                NotFound(NotFoundError),
+                #[response(status = 500)]
+                Error { message: String },
+            }
+        };
+        let discovered = super::parse_module_items(
+            syn::parse_quote!(crate),
+            items.items,
+            0,
+            &extract_attributes(quote! {}).unwrap(),
+        )
+        .unwrap();
+        let bodies: Vec<String> = discovered
+            .iter()
+            .filter_map(|item| match item {
+                DiscoverType::ResponseBody(owner, body) => {
+                    assert_eq!(path_to_string(owner), "crate::UserResponses");
+                    Some(quote!(#body).to_string().replace(' ', ""))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(bodies, vec!["User", "HashMap<String,Vec<User>>", "NotFoundError"]);
+    }
+
+    #[test]
+    fn test_check_response_bodies() {
+        let mut discovered = super::Discovered {
+            models: vec![syn::parse_quote!(crate::models::User)],
+            response_bodies: vec![
+                (syn::parse_quote!(crate::UserResponses), syn::parse_quote!(Vec<User>)),
+                (
+                    syn::parse_quote!(crate::UserResponses),
+                    syn::parse_quote!(Option<Error>),
+                ),
+                (syn::parse_quote!(crate::UserResponses), syn::parse_quote!(String)),
+            ],
+            ..Default::default()
+        };
+        discovered.check_response_bodies();
+        assert_eq!(discovered.warnings.len(), 1);
+        assert_eq!(
+            discovered.warnings[0].message,
+            "`Error` is a response body of `crate::UserResponses`, but is not a discovered schema"
+        );
+    }
+
+    #[test]
+    fn test_check_visibility() {
+        let files: [(&str, syn::File); 2] = [
+            (
+                "crate",
+                syn::parse_quote! {
+                    mod api;
+                    pub(crate) mod internal;
+                    #[utoipauto]
+                    struct ApiDoc;
+                },
+            ),
+            (
+                "crate::api",
+                syn::parse_quote! {
+                    mod users {
+                        #[utoipa::path(get, path = "/users")]
+                        pub(in crate::api) fn get_users() {}
+                    }
+                    #[utoipa::path(get, path = "/")]
+                    fn index() {}
+                    #[derive(utoipa::ToSchema)]
+                    struct User;
+                    #[utoipa::path(get, path = "/health")]
+                    pub(super) fn health() {}
+                },
+            ),
+        ];
+        let mut params = extract_attributes(quote! {}).unwrap();
+        params.doc_struct = Some(syn::parse_quote!(ApiDoc));
+        let mut discovered = super::Discovered::default();
+        for (module, file) in files {
+            for item in super::parse_module_items(syn::parse_str(module).unwrap(), file.items, 0, &params).unwrap() {
+                match item {
+                    DiscoverType::Fn(path) => discovered.fns.push(path),
+                    DiscoverType::Model(path) => discovered.models.push(path),
+                    DiscoverType::Scope(path, scope) => discovered.scopes.push((path, scope)),
+                    DiscoverType::DocStruct(path) => discovered.doc_structs.push(path),
+                    _ => {}
+                }
+            }
+        }
+        discovered.check_visibility(&params);
+        let messages: Vec<&str> = discovered
+            .warnings
+            .iter()
+            .map(|warning| warning.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`crate::api::users::get_users` is in the private module `crate::api::users` and can't be used from \
+                 `crate`, make it `pub(crate)` or re-export it",
+                "`crate::api::User` is private and can't be used from `crate`, make it `pub(crate)` or re-export it",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut discovered = super::Discovered {
+            fns: vec![
+                syn::parse_quote!(crate::api::get_user),
+                syn::parse_quote!(crate::api::delete_user),
+                syn::parse_quote!(crate::api::get_user),
+            ],
+            models: vec![syn::parse_quote!(crate::User)],
+            ..Default::default()
+        };
+        discovered.dedup(&["crate::api::delete_user".to_string()]);
+        let fns: Vec<String> = discovered.fns.iter().map(path_to_string).collect();
+        assert_eq!(fns, vec!["crate::api::get_user"]);
+        assert_eq!(discovered.models.len(), 1);
+    }
+
+    #[test]
+    fn test_pedantic_unnecessary_ignore() {
+        let items: syn::File = syn::parse_quote! {
+            #[utoipa_ignore]
+            #[utoipa::path(get, path = "/")]
+            pub fn route() {}
+            #[utoipa_ignore]
+            pub fn helper() {}
+            #[utoipa_ignore]
+            #[derive(Debug)]
+            pub struct Helper;
+        };
+        let params = extract_attributes(quote! { pedantic = true }).unwrap();
+        let messages: Vec<String> = super::parse_module_items(syn::parse_quote!(crate), items.items, 0, &params)
+            .unwrap()
+            .into_iter()
+            .filter_map(|item| match item {
+                DiscoverType::Warning(warning) => Some(warning.message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`crate::helper` is ignored, but wouldn't be discovered anyway",
+                "`crate::Helper` is ignored, but wouldn't be discovered anyway"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discover_from_included() {
+        let root = TempDir::new("included");
+        fs::write(
+            root.join("helloworld.rs"),
+            "#[derive(utoipa::ToSchema)] pub struct HelloRequest {}\n\
+             pub mod greeter { #[derive(utoipa::ToSchema)] pub struct HelloReply {} }",
+        )
+        .unwrap();
+        // Unlike modules, the files could all be `mod.rs`
+        fs::create_dir_all(root.join("health")).unwrap();
+        fs::write(
+            root.join("health/mod.rs"),
+            "#[derive(utoipa::ToSchema)] pub struct Status {}",
+        )
+        .unwrap();
+        fs::write(root.join("health.rs"), "").unwrap();
+        let params = extract_attributes(quote! {}).unwrap();
+        let out_dir = root.to_string_lossy().to_string();
+        let discovered = super::discover_from_included(out_dir.clone(), "crate::proto", &params);
+        let invalid = super::discover_from_included(out_dir, "crate::", &params).map(|_| ());
+        let models: Vec<String> = discovered.unwrap().models.iter().map(path_to_string).collect();
+        assert_eq!(
+            models,
+            vec![
+                "crate::proto::Status",
+                "crate::proto::HelloRequest",
+                "crate::proto::greeter::HelloReply"
+            ]
+        );
+        assert!(invalid
+            .unwrap_err()
+            .to_string()
+            .starts_with("utoipauto: Invalid module `crate::` of `"));
+    }
+
+    #[test]
+    fn test_include_macro() {
+        let root = TempDir::new("include-macro");
+        fs::create_dir_all(root.join("src/gen")).unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "pub mod gen { include!(\"gen/types.rs\"); }\n\
+             include!(concat!(env!(\"UTOIPAUTO_TEST_GEN_DIR\"), \"/missing.rs\"));",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/gen/types.rs"),
+            "#[derive(utoipa::ToSchema)] pub struct Pet {}\ninclude!(\"toys.rs\");",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/gen/toys.rs"),
+            "#[derive(utoipa::ToSchema)] pub struct Toy {}",
+        )
+        .unwrap();
+        std::env::set_var("UTOIPAUTO_TEST_GEN_DIR", root.join("src/gen"));
+        let params = extract_attributes(quote! {}).unwrap();
+        let src = root.join("src").to_string_lossy().to_string();
+        let discovered = super::discover_from_file(src, "crate".to_string(), &params);
+        std::env::remove_var("UTOIPAUTO_TEST_GEN_DIR");
+        let discovered = discovered.unwrap();
+        let models: Vec<String> = discovered.models.iter().map(path_to_string).collect();
+        // Not `crate::gen::types::Pet`, the included files aren't modules
+        assert_eq!(models, vec!["crate::gen::Pet", "crate::gen::Toy"]);
+        assert_eq!(discovered.warnings.len(), 1);
+        assert_eq!(discovered.warnings[0].kind, WarningKind::UnreadableFile);
+        assert!(discovered.warnings[0].message.starts_with("The `include!` of `"));
+        assert!(discovered.warnings[0]
+            .message
+            .contains("/src/gen/missing.rs` was skipped"));
+    }
+
+    #[test]
+    fn test_lenient() {
+        let root = TempDir::new("lenient");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "pub mod api;\npub mod models;\ninclude!(\"half.rs.in\");",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/api.rs"),
+            "#[utoipa::path(get, path = \"/pets\")]\npub fn list_pets(",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/models.rs"),
+            "#[derive(utoipa::ToSchema)] pub struct Pet {}",
+        )
+        .unwrap();
+        fs::write(root.join("src/half.rs.in"), "pub struct").unwrap();
+        let src = root.join("src").to_string_lossy().to_string();
+        let strict = super::discover_from_file(
+            src.clone(),
+            "crate".to_string(),
+            &extract_attributes(quote! {}).unwrap(),
+        );
+        let params = extract_attributes(quote! { lenient = true }).unwrap();
+        let discovered = super::discover_from_file(src, "crate".to_string(), &params);
+
+        let Err(error) = strict else {
+            panic!("the invalid files are errors without `lenient`")
+        };
+        assert!(error.to_string().starts_with("utoipauto: Failed to parse `"));
+        let discovered = discovered.unwrap();
+        let models: Vec<String> = discovered.models.iter().map(path_to_string).collect();
+        assert_eq!(models, vec!["crate::models::Pet"]);
+        let mut messages: Vec<&str> = discovered
+            .warnings
+            .iter()
+            .filter(|warning| warning.kind == WarningKind::UnparsableFile)
+            .map(|warning| warning.message.as_str())
+            .collect();
+        messages.sort();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].contains("/src/api.rs`"));
+        assert!(messages[1].contains("/src/half.rs.in`"));
+        assert!(messages
+            .iter()
+            .all(|message| message.ends_with(", the file was skipped")));
+    }
+
+    #[test]
+    fn test_discover_from_crate_root() {
+        let root = TempDir::new("crate-root");
+        fs::create_dir_all(root.join("crate_folder/routes")).unwrap();
+        fs::write(root.join("crate_folder/lib.rs"), "pub mod routes;").unwrap();
+        fs::write(
+            root.join("crate_folder/routes/mod.rs"),
+            "#[utoipa::path(get, path = \"/users\")] pub fn list_users() {}",
+        )
+        .unwrap();
+        fs::write(
+            root.join("cli.rs"),
+            "#[utoipa::path(get, path = \"/status\")] pub fn status() {}",
+        )
+        .unwrap();
+        let params = extract_attributes(quote! {}).unwrap();
+        let path = |path: &str| root.join(path).to_string_lossy().to_string();
+        let lib = super::discover_from_crate_root(path("crate_folder"), "my_api".to_string(), &params);
+        let bin = super::discover_from_crate_root(path("cli.rs"), "crate".to_string(), &params);
+        let fns = |discovered: super::Discovered| discovered.fns.iter().map(path_to_string).collect::<Vec<_>>();
+        assert_eq!(fns(lib.unwrap()), vec!["my_api::routes::list_users"]);
+        assert_eq!(fns(bin.unwrap()), vec!["crate::status"]);
+    }
+
+    #[test]
+    fn test_missing_scan_root() {
+        let params = extract_attributes(quote! {}).unwrap();
+        let error = super::discover_from_file("./src/handelrs".to_string(), "crate".to_string(), &params)
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "utoipauto: Can't scan the path, `./src/handelrs` does not exist"
+        );
+    }
+
+    #[test]
+    fn test_pedantic_scan_root() {
+        let params = extract_attributes(quote! { pedantic = true }).unwrap();
+        let mut discovered = super::Discovered::default();
+        super::check_scan_root("./src/nothing/**", &[], &mut discovered, &params);
+        assert_eq!(discovered.warnings[0].message, "`./src/nothing/**` matched no files");
+
+        let mut discovered = super::Discovered {
+            scopes: vec![(syn::parse_quote!(crate::api), None)],
+            ..Default::default()
+        };
+        let modules = [
+            ("./src/lib.rs".to_string(), syn::parse_quote!(crate)),
+            ("./src/api.rs".to_string(), syn::parse_quote!(crate::api)),
+            ("./src/old.rs".to_string(), syn::parse_quote!(crate::old)),
+            ("./src/api/v1.rs".to_string(), syn::parse_quote!(crate::api::v1)),
+        ];
+        let mut only_modules = super::Discovered {
+            scopes: discovered.scopes.clone(),
+            ..Default::default()
+        };
+        super::check_scan_root("./src", &modules, &mut discovered, &params);
+        let messages: Vec<&str> = discovered
+            .warnings
+            .iter()
+            .map(|warning| warning.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`./src` contributed no discovered item",
+                "`crate::old` is not declared by its parent module, `./src/old.rs` is not part of the crate",
+                "`crate::api::v1` is not declared by its parent module, `./src/api/v1.rs` is not part of the crate",
+            ]
+        );
+
+        // Without pedantic mode, only the classes of fail_on are checked
+        let params = extract_attributes(quote! { fail_on = ["unreachable_module"] }).unwrap();
+        super::check_scan_root("./src", &modules, &mut only_modules, &params);
+        assert_eq!(only_modules.warnings.len(), 2);
+        assert!(only_modules
+            .warnings
+            .iter()
+            .all(|warning| warning.kind == WarningKind::UnreachableModule));
+    }
+
+    #[test]
+    fn test_retain_sub_docs() {
+        let mut params = extract_attributes(quote! { sub_docs = "merge" }).unwrap();
+        params.doc_struct = Some(syn::parse_quote!(ApiDoc));
+        let mut discovered = super::Discovered {
+            fns: vec![
+                syn::parse_quote!(crate::health),
+                syn::parse_quote!(crate::api::users::list_users),
+            ],
+            models: vec![syn::parse_quote!(crate::api::users::models::User)],
+            sub_docs: vec![
+                syn::parse_quote!(crate::docs::ApiDoc),
+                syn::parse_quote!(crate::api::users::UsersDoc),
+                syn::parse_quote!(crate::CrateDoc),
+            ],
+            doc_structs: vec![syn::parse_quote!(crate::docs::ApiDoc)],
+            ..Default::default()
+        };
+        discovered.retain_sub_docs(&params);
+        let sub_docs: Vec<String> = discovered.sub_docs.iter().map(path_to_string).collect();
+        assert_eq!(sub_docs, vec!["crate::api::users::UsersDoc"]);
+        let fns: Vec<String> = discovered.fns.iter().map(path_to_string).collect();
+        assert_eq!(fns, vec!["crate::health"]);
+        assert!(discovered.models.is_empty());
+    }
+
+    #[test]
+    fn test_check_schema_names() {
+        let mut discovered = super::Discovered {
+            models: vec![
+                syn::parse_quote!(crate::v1::User),
+                syn::parse_quote!(crate::Pet),
+                syn::parse_quote!(crate::v1::User),
+                syn::parse_quote!(crate::v2::User),
+            ],
+            ..Default::default()
+        };
+        discovered.check_schema_names();
+        assert_eq!(discovered.warnings.len(), 1);
+        assert_eq!(discovered.warnings[0].kind, WarningKind::DuplicateSchema);
+        assert_eq!(
+            discovered.warnings[0].message,
+            "`crate::v1::User` and `crate::v2::User` are both named `User`, rename one with `#[schema(as = ...)]`"
+        );
+    }
+
+    #[test]
+    fn test_path_attributes() {
+        let root = TempDir::new("path-attributes");
+        fs::create_dir_all(root.join("src/generated/api")).unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "#[path = \"generated/api/routes.rs\"] pub mod routes;",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/generated/api/routes.rs"),
+            "pub mod users; #[utoipa::path(get, path = \"/health\")] pub fn health() {}",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/generated/api/users.rs"),
+            "#[utoipa::path(get, path = \"/users\")] pub fn list_users() {}",
+        )
+        .unwrap();
+
+        let params = extract_attributes(quote! {}).unwrap();
+        let src = root.join("src").to_string_lossy().to_string();
+        let discovered = super::discover_from_file(src, "crate".to_string(), &params).unwrap();
+        let mut fns: Vec<String> = discovered.fns.iter().map(path_to_string).collect();
+        fns.sort();
+        assert_eq!(fns, vec!["crate::routes::health", "crate::routes::users::list_users"]);
+    }
+
+    #[test]
+    fn test_may_contribute() {
+        let params = extract_attributes(quote! { macros = ["endpoint! => path(0)"] }).unwrap();
+        let may_contribute = |content: &str| super::may_contribute(content, &params);
+        assert!(!may_contribute(
+            "use std::fmt; pub fn helper() {} struct Model { id: u32 }"
+        ));
+        assert!(!may_contribute("fn module() { let used = 1; } // modules"));
+        assert!(may_contribute("#[derive(ToSchema)] struct User;"));
+        assert!(may_contribute("#[utoipa::path(get, path = \"/\")] fn index() {}"));
+        assert!(may_contribute("endpoint!(get_users);"));
+        assert!(may_contribute("pub mod api;"));
+        assert!(may_contribute("pub use api::User;"));
+        assert!(may_contribute("pub(crate) use api::*;"));
+        assert!(may_contribute("pub struct SecurityAddon ;"));
+
+        // The files which can't contribute aren't parsed at all
+        let root = TempDir::new("may-contribute");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub mod api; mod util;").unwrap();
+        fs::write(root.join("src/api.rs"), "#[derive(utoipa::ToSchema)] pub struct User;").unwrap();
+        fs::write(root.join("src/util.rs"), "fn invalid( {").unwrap();
+        let src = root.join("src").to_string_lossy().to_string();
+        let discovered = super::discover_from_file(src, "crate".to_string(), &params);
+        let models: Vec<String> = discovered.unwrap().models.iter().map(path_to_string).collect();
+        assert_eq!(models, vec!["crate::api::User"]);
+    }
+
+    #[test]
+    fn test_parse_cache() {
+        let root = TempDir::new("parse-cache-scan");
+        fs::create_dir_all(root.join("src/routes")).unwrap();
+        fs::write(root.join("src/lib.rs"), "#[path = \"routes/api.rs\"] pub mod api;").unwrap();
+        // Unique to the run, not to share the entries of the previous ones
+        let api = format!(
+            "// {:?}\n#[utoipa::path(get, path = \"/users\")] pub fn list_users() {{}}",
+            std::time::SystemTime::now()
+        );
+        fs::write(root.join("src/routes/api.rs"), &api).unwrap();
+
+        let src = root.join("src").to_string_lossy().to_string();
+        let fns = |params: &crate::token_utils::Parameters| {
+            let discovered = super::discover_from_file(src.clone(), "crate".to_string(), params).unwrap();
+            discovered.fns.iter().map(path_to_string).collect::<Vec<_>>()
+        };
+        let params = extract_attributes(quote! { parse_cache = true }).unwrap();
+        let parsed = fns(&params);
+        // The unchanged files aren't parsed again, their items are read back
+        let key = cache::items_key(cache::content_hash(&api), &syn::parse_quote!(crate::api), &params);
+        cache::store_items(&key, &[DiscoverType::Fn(syn::parse_quote!(crate::api::cached))]);
+        // Not to reuse the whole discovery made earlier in the process
+        let touch = |spaces: usize| {
+            let lib = format!("#[path = \"routes/api.rs\"] pub mod api;{}", " ".repeat(spaces));
+            fs::write(root.join("src/lib.rs"), lib).unwrap();
+        };
+        touch(1);
+        let cached = fns(&params);
+        touch(2);
+        let uncached = fns(&extract_attributes(quote! {}).unwrap());
+        fs::remove_file(cache::parse_cache_dir().join(&key)).unwrap();
+        fs::remove_file(cache::parse_cache_dir().join(format!("{:016x}.mods", cache::content_hash(&api)))).unwrap();
+        assert_eq!(parsed, vec!["crate::api::list_users"]);
+        assert_eq!(cached, vec!["crate::api::cached"]);
+        assert_eq!(uncached, vec!["crate::api::list_users"]);
+    }
+
+    #[test]
+    fn test_skip_tests() {
+        let root = TempDir::new("skip-tests");
+        fs::create_dir_all(root.join("src/tests")).unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "pub mod api; #[cfg(test)] mod helpers; \
+             #[cfg(test)] mod test { #[derive(utoipa::ToSchema)] pub struct Fixture; }",
+        )
+        .unwrap();
+        fs::write(root.join("src/api.rs"), "#[derive(utoipa::ToSchema)] pub struct User;").unwrap();
+        fs::write(
+            root.join("src/helpers.rs"),
+            "#[derive(utoipa::ToSchema)] pub struct Helper;",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/tests/samples.rs"),
+            "#[derive(utoipa::ToSchema)] pub struct Sample;",
+        )
+        .unwrap();
+
+        let src = root.join("src").to_string_lossy().to_string();
+        let models = |params: &crate::token_utils::Parameters| {
+            let discovered = super::discover_from_file(src.clone(), "crate".to_string(), params).unwrap();
+            let mut models: Vec<String> = discovered.models.iter().map(path_to_string).collect();
+            models.sort();
+            models
+        };
+        let skipped = models(&extract_attributes(quote! {}).unwrap());
+        let included = models(&extract_attributes(quote! { include_tests = true }).unwrap());
+        assert_eq!(skipped, vec!["crate::api::User"]);
+        assert_eq!(
+            included,
+            vec![
+                "crate::api::User",
+                "crate::helpers::Helper",
+                "crate::samples::Sample",
+                "crate::test::Fixture"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discovery_errors() {
+        let root = TempDir::new("errors");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "pub mod api; pub mod models;").unwrap();
+        fs::write(
+            root.join("src/api.rs"),
+            "#[utoipa_ignore(owner = \"me\")] #[utoipa::path(get, path = \"/\")] pub fn route() {}",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/models.rs"),
+            "#[utoipauto::alias(crate)] #[utoipa::path(get, path = \"/\")] pub fn route() {}",
+        )
+        .unwrap();
+
+        let params = extract_attributes(quote! {}).unwrap();
+        let src = root.join("src").to_string_lossy().to_string();
+        let discovered = super::discover_from_file(src, "crate".to_string(), &params);
+        let Err(error) = discovered else {
+            panic!("the attributes are invalid");
+        };
+        let mut messages: Vec<String> = error.into_iter().map(|error| error.to_string()).collect();
+        messages.sort();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("utoipauto: Expected #[utoipauto::alias(\"PATH\")], in `"));
+        assert!(messages[0].ends_with("models.rs`"));
+        assert!(messages[1].starts_with("utoipauto: Unknown utoipa_ignore argument \"owner\""));
+        assert!(messages[1].ends_with("api.rs`"));
+
+        let discovered = super::discover_from_str("#[derive(ToSchema, =)] pub struct User;", "crate::api", &params);
+        assert!(discovered.is_err_and(|error| error
+            .to_string()
+            .starts_with("utoipauto: Failed to parse the derive attribute of `crate::api::User`")));
+    }
+
+    #[test]
+    fn test_normalize_file_path() {
+        assert_eq!(super::normalize_file_path("./src/a/../b.rs"), "src/b.rs");
+        assert_eq!(super::normalize_file_path("/tmp/src//a.rs"), "/tmp/src/a.rs");
+        assert_eq!(
+            super::normalize_file_path("../other\\src/lib.rs"),
+            "../other/src/lib.rs"
+        );
+    }
+
+    #[test]
+    fn test_max_files() {
+        let params = extract_attributes(quote! { max_files = 2 }).unwrap();
+        let discovered = super::discover_from_file("./src".to_string(), "crate".to_string(), &params);
+        assert!(discovered.is_err_and(|error| error.to_string().starts_with("utoipauto: `./src` contains")));
+    }
+
+    fn sorted(order: Order) -> Vec<String> {
+        let mut paths: Vec<syn::Path> = vec![
+            syn::parse_quote!(crate::users::get_user),
+            syn::parse_quote!(crate::admin::delete_user),
+            syn::parse_quote!(crate::users::create_user),
+        ];
+        super::sort_paths(&mut paths, order);
+        paths.iter().map(crate::path_utils::path_to_string).collect()
+    }
+
+    #[test]
+    fn test_sort_paths_source() {
+        assert_eq!(
+            sorted(Order::Source),
+            vec![
+                "crate::users::get_user",
+                "crate::admin::delete_user",
+                "crate::users::create_user"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_paths_alphabetical() {
+        assert_eq!(
+            sorted(Order::Alphabetical),
+            vec![
+                "crate::admin::delete_user",
+                "crate::users::create_user",
+                "crate::users::get_user"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_paths_module() {
+        assert_eq!(
+            sorted(Order::Module),
+            vec![
+                "crate::admin::delete_user",
+                "crate::users::get_user",
+                "crate::users::create_user"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_alias() {
+        let item_fn: ItemFn = syn::parse_quote! {
+            #[utoipauto::alias("crate::api::v2::get_user")]
+            #[utoipa::path(get, path = "/user")]
+            pub fn get_user() {}
+        };
+        assert_eq!(
+            super::parse_alias(&item_fn.attrs)
+                .unwrap()
+                .map(|alias| path_to_string(&alias)),
+            Some("crate::api::v2::get_user".to_string())
+        );
+
+        let item_fn: ItemFn = syn::parse_quote! {
+            #[utoipa::path(get, path = "/user")]
+            pub fn get_user() {}
+        };
+        assert!(super::parse_alias(&item_fn.attrs).unwrap().is_none());
+    }
+
+    fn reexports(module_path: syn::Path, item: syn::ItemUse) -> Vec<super::Reexport> {
+        super::parse_use(&item, &module_path)
+            .into_iter()
+            .map(|discovered| match discovered {
+                DiscoverType::Reexport(reexport) => reexport,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_use() {
+        let reexports = reexports(
+            syn::parse_quote!(crate::api),
+            syn::parse_quote!(
+                pub use handlers::{get_user as fetch_user, models::*};
+            ),
+        );
+        assert_eq!(reexports.len(), 2);
+        assert_eq!(path_to_string(&reexports[0].source), "crate::api::handlers::get_user");
+        assert_eq!(path_to_string(&reexports[0].public), "crate::api::fetch_user");
+        assert!(!reexports[0].glob);
+        assert_eq!(path_to_string(&reexports[1].source), "crate::api::handlers::models");
+        assert_eq!(path_to_string(&reexports[1].public), "crate::api");
+        assert!(reexports[1].glob);
+    }
+
+    #[test]
+    fn test_parse_use_super_and_crate() {
+        let from_super = reexports(
+            syn::parse_quote!(crate::api::v1),
+            syn::parse_quote!(
+                pub(crate) use super::super::models::User;
+            ),
+        );
+        assert_eq!(path_to_string(&from_super[0].source), "crate::models::User");
+
+        let from_crate = reexports(
+            syn::parse_quote!(crate::api::v1),
+            syn::parse_quote!(
+                pub use crate::models::User;
+            ),
+        );
+        assert_eq!(path_to_string(&from_crate[0].source), "crate::models::User");
+        assert_eq!(path_to_string(&from_crate[0].public), "crate::api::v1::User");
+    }
+
+    #[test]
+    fn test_parse_use_private() {
+        assert!(reexports(
+            syn::parse_quote!(crate),
+            syn::parse_quote!(
+                use handlers::get_user;
+            )
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_resolve_reexports() {
+        let reexports = vec![
+            super::Reexport {
+                source: syn::parse_quote!(crate::api::handlers::get_user),
+                public: syn::parse_quote!(crate::api::fetch_user),
+                glob: false,
+                written: syn::parse_quote!(handlers::get_user),
+            },
+            super::Reexport {
+                source: syn::parse_quote!(crate::api),
+                public: syn::parse_quote!(crate),
+                glob: true,
+                written: syn::parse_quote!(api),
+            },
+        ];
+        assert_eq!(
+            path_to_string(&super::resolve_reexports(
+                &syn::parse_quote!(crate::api::handlers::get_user),
+                &reexports
+            )),
+            "crate::fetch_user"
+        );
+        assert_eq!(
+            path_to_string(&super::resolve_reexports(
+                &syn::parse_quote!(crate::other::get_user),
+                &reexports
+            )),
+            "crate::other::get_user"
+        );
     }
 }
@@ -7,53 +7,651 @@ use std::{
 
 use proc_macro2::Span;
 
+use crate::diagnostics;
+use crate::manifest::{Manifest, Value};
+
 pub fn parse_file<T: Into<PathBuf>>(filepath: T) -> Result<syn::File, io::Error> {
     let pb: PathBuf = filepath.into();
+    let content = read_file(&pb)?;
+    syn::parse_file(&content).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to parse file {:?}: {}", pb, e),
+        )
+    })
+}
 
+fn read_file(pb: &Path) -> Result<String, io::Error> {
     if !pb.is_file() {
-        panic!("File not found: {:?}", pb);
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("File not found: {:?}", pb),
+        ));
     }
 
-    let mut file = File::open(&pb)?;
+    let mut file = File::open(pb)?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
-
-    Ok(syn::parse_file(&content).unwrap_or_else(move |_| panic!("Failed to parse file {:?}", pb)))
+    Ok(content)
 }
 
 /// Parse all the files in the given path
 pub fn parse_files<T: Into<PathBuf>>(path: T) -> Result<Vec<(String, syn::File)>, io::Error> {
-    let mut files: Vec<(String, syn::File)> = vec![];
+    parse_files_with(path, &["rs"])
+}
+
+/// Parse all the files with one of the extensions in the given path, e.g. `["rs", "rs.in"]`
+pub fn parse_files_with<T: Into<PathBuf>>(
+    path: T,
+    extensions: &[impl AsRef<str>],
+) -> Result<Vec<(String, syn::File)>, io::Error> {
+    parse_collected_files(collect_files_with(path, extensions)?)
+}
+
+/// A file or directory left out, as it couldn't be read
+#[derive(Debug)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+/// Collect the files with one of the extensions in the given path, without parsing them
+pub fn collect_files_with<T: Into<PathBuf>>(
+    path: T,
+    extensions: &[impl AsRef<str>],
+) -> Result<Vec<PathBuf>, io::Error> {
+    let mut skipped = vec![];
+    let files = collect_files_skipping(path, extensions, &[], &mut skipped)?;
+    first_error(files, skipped)
+}
+
+/// Collect the files with one of the extensions in the given path, setting aside the entries that can't be read.
+///
+/// Only fails if the path itself can't be read. The `excluded` files and directories are left out without being
+/// walked, see `is_excluded`.
+pub fn collect_files_skipping<T: Into<PathBuf>>(
+    path: T,
+    extensions: &[impl AsRef<str>],
+    excluded: &[String],
+    skipped: &mut Vec<SkippedFile>,
+) -> Result<Vec<PathBuf>, io::Error> {
+    Ok(retain_packaged(collect_rust_files(
+        path.into(),
+        extensions,
+        excluded,
+        skipped,
+    )?))
+}
+
+/// Parse the collected files
+pub fn parse_collected_files(files: Vec<PathBuf>) -> Result<Vec<(String, syn::File)>, io::Error> {
+    let mut skipped = vec![];
+    let files = parse_readable_files(files, &mut skipped)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    first_error(files, skipped)
+}
+
+/// Parse the collected files, setting aside the ones that can't be read, e.g. the ones that aren't valid UTF-8.
+///
+/// Fails with a single error listing all the files that aren't valid Rust.
+pub fn parse_readable_files(
+    files: Vec<PathBuf>,
+    skipped: &mut Vec<SkippedFile>,
+) -> syn::Result<Vec<(String, syn::File)>> {
+    let mut failures = vec![];
+    let parsed: Vec<(String, syn::File)> = read_readable_files(files, skipped)
+        .into_iter()
+        .filter_map(|(name, content)| match parse_source(&name, &content) {
+            Ok(file) => Some((name, file)),
+            Err(failure) => {
+                failures.push(failure);
+                None
+            }
+        })
+        .collect();
+    match failures.is_empty() {
+        true => Ok(parsed),
+        false => Err(parse_failures(failures)),
+    }
+}
+
+/// Read the collected files, along with their name, setting aside the ones that can't be read
+pub fn read_readable_files(files: Vec<PathBuf>, skipped: &mut Vec<SkippedFile>) -> Vec<(String, String)> {
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let Some(name) = path.to_str().map(str::to_string) else {
+                let error = io::Error::new(io::ErrorKind::InvalidData, "the file name is not valid UTF-8");
+                skipped.push(SkippedFile { path, error });
+                return None;
+            };
+            match read_file(&path) {
+                Ok(content) => Some((name, content)),
+                Err(error) => {
+                    skipped.push(SkippedFile { path, error });
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// A file that isn't valid Rust, along with where the parsing failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseFailure {
+    pub file: String,
+    /// The line, starting at 1, and the column, starting at 1, if they are known
+    pub location: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl ParseFailure {
+    /// The file, where it fails and why, e.g. `` `./src/api.rs` at line 3, column 5: expected `,` ``
+    pub fn describe(&self) -> String {
+        match self.location {
+            Some((line, column)) => format!("`{}` at line {}, column {}: {}", self.file, line, column, self.message),
+            None => format!("`{}`: {}", self.file, self.message),
+        }
+    }
+}
+
+impl From<ParseFailure> for syn::Error {
+    fn from(failure: ParseFailure) -> Self {
+        diagnostics::error(format!("Failed to parse {}", failure.describe()))
+    }
+}
+
+/// The failures of all the files, as a single error : they are often caused together, e.g. by a bad merge
+pub fn parse_failures(mut failures: Vec<ParseFailure>) -> syn::Error {
+    if failures.len() == 1 {
+        return failures.remove(0).into();
+    }
+    let list: Vec<String> = failures
+        .iter()
+        .map(|failure| format!("\n  - {}", failure.describe()))
+        .collect();
+    diagnostics::error(format!("Failed to parse {} files:{}", failures.len(), list.concat()))
+}
+
+/// Parse the content of the file `name`
+pub fn parse_source(name: &str, content: &str) -> Result<syn::File, ParseFailure> {
+    syn::parse_file(content).map_err(|e| ParseFailure {
+        file: name.to_string(),
+        location: failure_location(content),
+        message: e.to_string(),
+    })
+}
+
+/// Where parsing the content fails, with the `parse-locations` feature.
+///
+/// The tokens lexed by the compiler all have the span of the macro invocation, so the content is parsed again with
+/// the implementation of proc-macro2, whose spans are located in the content with its `span-locations` feature. Its
+/// tokens can't be mixed with the ones of the compiler, and are dropped.
+#[cfg(feature = "parse-locations")]
+fn failure_location(content: &str) -> Option<(usize, usize)> {
+    let error = {
+        let _fallback = FallbackGuard::force();
+        syn::parse_file(content).err()
+    };
+    let start = error?.span().start();
+    // The end of the input has no location
+    (start.line > 0).then_some((start.line, start.column + 1))
+}
+
+/// Without the `parse-locations` feature, the failure is only described by the file and the message of syn
+#[cfg(not(feature = "parse-locations"))]
+fn failure_location(_content: &str) -> Option<(usize, usize)> {
+    None
+}
+
+/// Switches proc-macro2 to its own implementation until dropped, even if parsing panics.
+///
+/// The switch is global to the process, the other expansions would create tokens of the wrong implementation
+/// meanwhile : it is only held while parsing again a file that already failed.
+#[cfg(feature = "parse-locations")]
+struct FallbackGuard;
+
+#[cfg(feature = "parse-locations")]
+impl FallbackGuard {
+    fn force() -> FallbackGuard {
+        proc_macro2::fallback::force();
+        FallbackGuard
+    }
+}
+
+#[cfg(feature = "parse-locations")]
+impl Drop for FallbackGuard {
+    fn drop(&mut self) {
+        proc_macro2::fallback::unforce();
+    }
+}
+
+fn first_error<T>(items: Vec<T>, skipped: Vec<SkippedFile>) -> Result<Vec<T>, io::Error> {
+    match skipped.into_iter().next() {
+        Some(skipped) => Err(skipped.error),
+        None => Ok(items),
+    }
+}
+
+/// Parse all the files matching the glob pattern, e.g. `./src/models/**` or `./src/*/models.rs`
+///
+/// `**` matches any number of directories, `*` and `?` match inside a single file or directory name.
+pub fn parse_files_matching(pattern: &str) -> Result<Vec<(String, syn::File)>, io::Error> {
+    parse_files_matching_with(pattern, &["rs"])
+}
+
+/// Parse all the files with one of the extensions matching the glob pattern
+pub fn parse_files_matching_with(
+    pattern: &str,
+    extensions: &[impl AsRef<str>],
+) -> Result<Vec<(String, syn::File)>, io::Error> {
+    parse_collected_files(collect_files_matching_with(pattern, extensions)?)
+}
+
+/// Collect the files with one of the extensions matching the glob pattern, without parsing them
+pub fn collect_files_matching_with(pattern: &str, extensions: &[impl AsRef<str>]) -> Result<Vec<PathBuf>, io::Error> {
+    let mut skipped = vec![];
+    let files = collect_files_matching_skipping(pattern, extensions, &[], &mut skipped)?;
+    first_error(files, skipped)
+}
+
+/// Collect the files with one of the extensions matching the glob pattern, setting aside the entries that can't be read
+pub fn collect_files_matching_skipping(
+    pattern: &str,
+    extensions: &[impl AsRef<str>],
+    excluded: &[String],
+    skipped: &mut Vec<SkippedFile>,
+) -> Result<Vec<PathBuf>, io::Error> {
+    let pattern = pattern.replace('\\', "/");
+    // Only walk the part of the tree that can match
+    let base = glob_base(&pattern);
+    // Nothing matches the pattern
+    if !Path::new(&base).exists() {
+        return Ok(vec![]);
+    }
+
+    Ok(
+        retain_packaged(collect_rust_files(PathBuf::from(base), extensions, excluded, skipped)?)
+            .into_iter()
+            .filter(|path| matches_glob(&pattern, &path.to_string_lossy().replace('\\', "/")))
+            .collect(),
+    )
+}
+
+/// Check if the path contains glob wildcards
+pub fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?'])
+}
+
+/// The directory every path matching the glob pattern is in, e.g. `./src/models` for `./src/models/**/*.rs`
+pub fn glob_base(pattern: &str) -> String {
+    let pattern = pattern.replace('\\', "/");
+    let base: Vec<&str> = pattern.split('/').take_while(|segment| !is_glob(segment)).collect();
+    if base.is_empty() {
+        ".".to_string()
+    } else {
+        base.join("/")
+    }
+}
+
+/// Replace the environment variables of the path, written `$NAME` or `${NAME}`, by their value, e.g. `$OUT_DIR` or
+/// `${CARGO_MANIFEST_DIR}/generated`
+pub fn expand_env_vars(path: &str) -> syn::Result<String> {
+    let mut expanded = String::new();
+    let mut rest = path;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, len) = match after.strip_prefix('{') {
+            Some(braced) => {
+                let end = braced
+                    .find('}')
+                    .ok_or_else(|| diagnostics::error(format!("Unclosed `${{` in `{}`, expected `${{NAME}}`", path)))?;
+                (&braced[..end], end + 2)
+            }
+            None => {
+                let end = after
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        if name.is_empty() {
+            return Err(diagnostics::error(format!(
+                "Expected the name of an environment variable after `$` in `{}`",
+                path
+            )));
+        }
+        let value = std::env::var(name).map_err(|_| {
+            let hint = match name {
+                "OUT_DIR" => ". It is only set for the crates with a build script",
+                _ => "",
+            };
+            diagnostics::error(format!("`${}` of `{}` is not set{}", name, path, hint))
+        })?;
+        expanded.push_str(&value);
+        rest = &after[len..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Check that the path, or the directory of the glob pattern, exists and can be read, to report a mistyped path
+/// rather than discovering nothing
+pub fn check_root(src_path: &str) -> Result<(), io::Error> {
+    let root = match is_glob(src_path) {
+        true => glob_base(src_path),
+        false => src_path.to_string(),
+    };
+    let unreadable = |error: io::Error| match error.kind() {
+        io::ErrorKind::NotFound => io::Error::new(error.kind(), format!("`{}` does not exist", root)),
+        _ => io::Error::new(error.kind(), format!("`{}` can't be read: {}", root, error)),
+    };
+    if fs::metadata(&root).map_err(unreadable)?.is_dir() {
+        fs::read_dir(&root).map_err(unreadable)?;
+    }
+    Ok(())
+}
+
+/// The scanned files of a path, along with the directories they are in up to the base of the path, absolute.
+///
+/// The directories change when a file is added to or removed from them, so a new file is noticed too.
+pub fn tracked_sources<'a>(src_path: &str, files: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let base = match is_glob(src_path) {
+        true => glob_base(src_path),
+        false => src_path.to_string(),
+    };
+    let Ok(base) = fs::canonicalize(base) else {
+        return vec![];
+    };
+    let mut sources = vec![base.to_string_lossy().to_string()];
+    for file in files.into_iter().filter_map(|file| fs::canonicalize(file).ok()) {
+        let directories = file
+            .ancestors()
+            .skip(1)
+            .take_while(|directory| directory.starts_with(&base));
+        for source in iter::once(file.as_path()).chain(directories) {
+            let source = source.to_string_lossy().to_string();
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+    }
+    sources
+}
+
+/// Have the compiler rebuild the macro when one of the sources changes, on nightly with the `tracked-path` feature
+#[cfg(feature = "tracked-path")]
+pub fn track_sources(sources: &[String]) {
+    // The compiler can only be told from the macro invocation, not e.g. from cargo-utoipauto
+    if proc_macro::is_available() {
+        for source in sources {
+            proc_macro::tracked::path(source);
+        }
+    }
+}
 
-    let pb: PathBuf = path.into();
+/// Collect all the rust files in the given path, recursively.
+///
+/// The nested entries that can't be read are set aside in `skipped`, along with the special files, e.g. broken
+/// symlinks, with one of the extensions. The hidden entries and the build output are not walked, see
+/// `is_skipped_entry`.
+fn collect_rust_files(
+    pb: PathBuf,
+    extensions: &[impl AsRef<str>],
+    excluded: &[String],
+    skipped: &mut Vec<SkippedFile>,
+) -> Result<Vec<PathBuf>, io::Error> {
+    let mut files: Vec<PathBuf> = vec![];
+
+    if is_excluded(&pb, excluded) {
+        return Ok(files);
+    }
     if pb.is_file() {
         // we only parse rust files
-        if is_rust_file(&pb) {
-            files.push((pb.to_str().unwrap().to_string(), parse_file(pb)?));
+        if is_rust_file(&pb, extensions) {
+            files.push(pb);
         }
     } else {
-        for entry in fs::read_dir(pb)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_file() && is_rust_file(&path) {
-                files.push((path.to_str().unwrap().to_string(), parse_file(path)?));
-            } else {
-                files.append(&mut parse_files(path)?);
+        let mut paths = vec![];
+        for entry in fs::read_dir(&pb)? {
+            match entry {
+                Ok(entry) => paths.push(entry.path()),
+                Err(error) => skipped.push(SkippedFile {
+                    path: pb.clone(),
+                    error,
+                }),
+            }
+        }
+        // The order of the entries depends on the platform and the file system
+        paths.sort();
+        for path in paths {
+            if is_excluded(&path, excluded) || is_skipped_entry(&path) {
+                continue;
+            }
+            if path.is_file() {
+                if is_rust_file(&path, extensions) {
+                    files.push(path);
+                }
+            } else if path.is_dir() {
+                match collect_rust_files(path.clone(), extensions, excluded, skipped) {
+                    Ok(mut nested) => files.append(&mut nested),
+                    Err(error) => skipped.push(SkippedFile { path, error }),
+                }
+            } else if has_rust_extension(&path, extensions) {
+                let error = io::Error::other("not a regular file");
+                skipped.push(SkippedFile { path, error });
             }
         }
     }
     Ok(files)
 }
 
-fn is_rust_file(path: &Path) -> bool {
-    path.is_file()
-        && match path.extension() {
-            Some(ext) => match ext.to_str() {
-                Some(ext) => ext.eq("rs"),
-                None => false,
-            },
-            None => false,
+/// Whether the path is one of the `excluded` paths, or under one of them. An excluded path may be a glob pattern,
+/// e.g. `./src/**/generated`
+fn is_excluded(path: &Path, excluded: &[String]) -> bool {
+    let normalize = |path: &str| {
+        path.replace('\\', "/")
+            .trim_start_matches("./")
+            .trim_end_matches('/')
+            .to_string()
+    };
+    let path = normalize(&path.to_string_lossy());
+    excluded.iter().any(|excluded| {
+        let excluded = normalize(excluded);
+        match is_glob(&excluded) {
+            true => matches_glob(&excluded, &path),
+            false => path == excluded || path.starts_with(&format!("{}/", excluded)),
+        }
+    })
+}
+
+/// Whether the nested entry is left out of a walk : a hidden file or directory, e.g. `.git` or the backups of an
+/// editor, or the `target` directory of Cargo. A `target` module, without a manifest next to it, is kept
+fn is_skipped_entry(path: &Path) -> bool {
+    let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+        return false;
+    };
+    let is_build_output =
+        || path.join("CACHEDIR.TAG").is_file() || path.parent().is_some_and(|dir| dir.join("Cargo.toml").is_file());
+    name.starts_with('.') || (name == "target" && path.is_dir() && is_build_output())
+}
+
+/// Remove the files ignored by the `.gitignore` of their directory or of its parents, up to the root of the
+/// repository.
+///
+/// The patterns are those of git : the last one matching the file decides, a `!` pattern includes it back.
+pub fn retain_unignored(files: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut gitignores: Vec<(PathBuf, Vec<String>)> = vec![];
+    files
+        .into_iter()
+        .filter(|file| {
+            let Ok(file) = fs::canonicalize(file) else {
+                return true;
+            };
+            let mut dirs: Vec<&Path> = vec![];
+            for dir in file.ancestors().skip(1) {
+                dirs.push(dir);
+                if dir.join(".git").exists() {
+                    break;
+                }
+            }
+            let mut ignored = false;
+            // The patterns of the inner directories come last
+            for dir in dirs.into_iter().rev() {
+                let patterns = match gitignores.iter().position(|(known, _)| known == dir) {
+                    Some(idx) => &gitignores[idx].1,
+                    None => {
+                        let patterns = read_file(&dir.join(".gitignore")).map_or(vec![], |content| {
+                            content
+                                .lines()
+                                .map(str::trim)
+                                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                                .map(str::to_string)
+                                .collect()
+                        });
+                        gitignores.push((dir.to_path_buf(), patterns));
+                        &gitignores.last().unwrap().1
+                    }
+                };
+                let relative = file
+                    .strip_prefix(dir)
+                    .unwrap_or(&file)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                for pattern in patterns {
+                    let (included, pattern) = match pattern.strip_prefix('!') {
+                        Some(pattern) => (true, pattern),
+                        None => (false, pattern.as_str()),
+                    };
+                    if matches_package_glob(pattern, &relative) {
+                        ignored = !included;
+                    }
+                }
+            }
+            !ignored
+        })
+        .collect()
+}
+
+/// Remove the files Cargo wouldn't package, according to the `include` and `exclude` of their package manifest
+fn retain_packaged(files: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut manifests: Vec<(PathBuf, Option<Manifest>)> = vec![];
+    files
+        .into_iter()
+        .filter(|file| {
+            let Some(package_root) = file.ancestors().skip(1).find(|dir| dir.join("Cargo.toml").is_file()) else {
+                return true;
+            };
+            let manifest = match manifests.iter().position(|(root, _)| root == package_root) {
+                Some(idx) => &manifests[idx].1,
+                None => {
+                    let manifest = Manifest::from_path(package_root.join("Cargo.toml")).ok();
+                    manifests.push((package_root.to_path_buf(), manifest));
+                    &manifests.last().unwrap().1
+                }
+            };
+            let relative = file.strip_prefix(package_root).unwrap_or(file);
+            manifest.as_ref().map_or(true, |manifest| {
+                is_packaged(manifest, &relative.to_string_lossy().replace('\\', "/"))
+            })
+        })
+        .collect()
+}
+
+/// Check if the file, relative to the package root, is packaged : it must match `include` if any,
+/// otherwise not match `exclude`
+fn is_packaged(manifest: &Manifest, path: &str) -> bool {
+    let matches = |key: &str| match manifest.get("package", key) {
+        Some(Value::Array(patterns)) => Some(patterns.iter().any(|pattern| matches_package_glob(pattern, path))),
+        _ => None,
+    };
+    match matches("include") {
+        Some(included) => included,
+        None => !matches("exclude").unwrap_or(false),
+    }
+}
+
+/// Match a path against a gitignore-like pattern of the manifest : a pattern without `/` matches at any level,
+/// a leading `/` anchors it to the package root, and the content of a matching directory matches too
+fn matches_package_glob(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+    let pattern = match pattern.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if !pattern.contains('/') => format!("**/{}", pattern),
+        None => pattern.to_string(),
+    };
+    matches_glob(&pattern, path) || matches_glob(&format!("{}/**", pattern), path)
+}
+
+/// Match a `/` separated path against a glob pattern
+fn matches_glob(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_start_matches("./").split('/').collect();
+    let path: Vec<&str> = path.trim_start_matches("./").split('/').collect();
+    matches_segments(&pattern, &path)
+}
+
+fn matches_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            matches_segments(&pattern[1..], path) || (!path.is_empty() && matches_segments(pattern, &path[1..]))
+        }
+        (Some(segment_pattern), Some(segment)) => {
+            matches_segment(segment_pattern.as_bytes(), segment.as_bytes())
+                && matches_segments(&pattern[1..], &path[1..])
         }
+        _ => false,
+    }
+}
+
+fn matches_segment(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            matches_segment(&pattern[1..], name) || (!name.is_empty() && matches_segment(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(_)) => matches_segment(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) => p == n && matches_segment(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+fn is_rust_file(path: &Path, extensions: &[impl AsRef<str>]) -> bool {
+    path.is_file() && has_rust_extension(path, extensions)
+}
+
+fn has_rust_extension(path: &Path, extensions: &[impl AsRef<str>]) -> bool {
+    match path.file_name().map(|name| name.to_string_lossy()) {
+        Some(name) => extensions.iter().any(|ext| {
+            name.strip_suffix(ext.as_ref())
+                .is_some_and(|stem| stem.len() > 1 && stem.ends_with('.'))
+        }),
+        None => false,
+    }
+}
+
+/// Rules tweaking how the directories and files of a path map to module names
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModuleMapping {
+    /// Segments that don't correspond to a module level, e.g. a `routes/` directory
+    pub strip: Vec<String>,
+    /// Segments whose module name differs from the directory or file name
+    pub replace: Vec<(String, String)>,
+}
+
+impl ModuleMapping {
+    fn apply<'a>(&'a self, segment: &'a str) -> Option<&'a str> {
+        if self.strip.iter().any(|strip| strip == segment) {
+            return None;
+        }
+        Some(
+            self.replace
+                .iter()
+                .find(|(from, _)| from == segment)
+                .map_or(segment, |(_, to)| to.as_str()),
+        )
+    }
 }
 
 /// Extract the module name from the file path
@@ -71,9 +669,21 @@ fn is_rust_file(path: &Path) -> bool {
 /// );
 /// ```
 pub fn extract_module_name_from_path(path: &str, crate_name: &str) -> syn::Path {
+    extract_module_name_from_path_with(path, crate_name, &ModuleMapping::default())
+}
+
+/// Extract the module name from the file path, applying the mapping rules to its segments
+pub fn extract_module_name_from_path_with(path: &str, crate_name: &str, mapping: &ModuleMapping) -> syn::Path {
     let path = path.replace('\\', "/");
+    // Remove the extension of the file, which may have several parts, e.g. `.rs.in`
+    let path = match path.rsplit_once('/') {
+        Some((directory, file)) => match file.find('.').filter(|idx| *idx > 0) {
+            Some(idx) => format!("{}/{}", directory, &file[..idx]),
+            None => path.clone(),
+        },
+        None => path.trim_end_matches(".rs").to_string(),
+    };
     let path = path
-        .trim_end_matches(".rs")
         .trim_end_matches("/mod")
         .trim_end_matches("/lib")
         .trim_end_matches("/main")
@@ -99,7 +709,11 @@ pub fn extract_module_name_from_path(path: &str, crate_name: &str) -> syn::Path
     };
 
     let full_crate_path = iter::once(first_crate_fragment)
-        .chain(segments_inside_crate.iter().copied())
+        .chain(
+            segments_inside_crate
+                .iter()
+                .filter_map(|segment| mapping.apply(segment)),
+        )
         .map(|segment| syn::PathSegment::from(syn::Ident::new(&segment.replace('-', "_"), Span::mixed_site())));
     syn::Path {
         leading_colon: None,
@@ -107,6 +721,166 @@ pub fn extract_module_name_from_path(path: &str, crate_name: &str) -> syn::Path
     }
 }
 
+/// The scan paths of the library crates of the workspace whose manifest is in `root`, e.g. `./api/src from api`.
+///
+/// The package named `current` is the crate of the doc struct, scanned as `crate` even if it is a binary.
+pub fn workspace_paths(root: &str, current: Option<&str>) -> Result<Vec<String>, String> {
+    let manifest_path = Path::new(root).join("Cargo.toml");
+    let manifest = Manifest::from_path(&manifest_path)
+        .map_err(|e| format!("Failed to read {}: {}", manifest_path.display(), e))?;
+    let Some(Value::Array(members)) = manifest.get("workspace", "members") else {
+        return Err(format!("{} has no [workspace] members", manifest_path.display()));
+    };
+    let excluded: Vec<&str> = match manifest.get("workspace", "exclude") {
+        Some(Value::Array(excluded)) => excluded.iter().map(|dir| dir.trim_end_matches('/')).collect(),
+        _ => vec![],
+    };
+    // The root manifest may also be a package
+    let mut dirs: Vec<String> = manifest.package_name().map(|_| ".".to_string()).into_iter().collect();
+    for member in members {
+        match is_glob(member) {
+            true => dirs.extend(matching_dirs(Path::new(root), member)),
+            false => dirs.push(member.trim_end_matches('/').to_string()),
+        }
+    }
+    dirs.retain(|dir| !excluded.contains(&dir.as_str()));
+
+    let mut paths = vec![];
+    for dir in dirs {
+        let package_root = Path::new(root).join(&dir);
+        // A glob may match directories that are not packages
+        let Ok(package) = Manifest::from_path(package_root.join("Cargo.toml")) else {
+            continue;
+        };
+        let src = match dir.as_str() {
+            "." => format!("{}/src", root),
+            _ => format!("{}/{}/src", root, dir),
+        };
+        if current.is_some() && package.package_name() == current {
+            paths.push(src);
+            continue;
+        }
+        // Only the libraries can be used from the crate of the doc struct
+        let is_lib = package.has_table("lib") || package_root.join("src/lib.rs").is_file();
+        if let Some(lib_name) = package.lib_name().filter(|_| is_lib) {
+            paths.push(format!("{} from {}", src, lib_name));
+        }
+    }
+    Ok(paths)
+}
+
+/// The crates of a package, with the path of their root file relative to the package root
+#[derive(Debug, PartialEq)]
+pub struct PackageTargets {
+    /// The name of the library, as used in paths
+    pub lib: Option<(String, PathBuf)>,
+    pub bins: Vec<(String, PathBuf)>,
+}
+
+/// The crates of the package in the directory, read from its `Cargo.toml` : the `[lib]` and `[[bin]]` with their
+/// `path`, and the ones Cargo finds by itself, `src/lib.rs`, `src/main.rs`, `src/bin/*.rs` and `src/bin/*/main.rs`
+pub fn package_targets(package_dir: &Path) -> Option<PackageTargets> {
+    let manifest = Manifest::from_path(package_dir.join("Cargo.toml")).ok()?;
+    let package_name = manifest.package_name()?.to_string();
+    let exists = |path: &PathBuf| package_dir.join(path).is_file();
+    let lib_path = PathBuf::from(manifest.get_str("lib", "path").unwrap_or("src/lib.rs"));
+    let lib = (manifest.has_table("lib") || exists(&lib_path))
+        .then(|| manifest.lib_name())
+        .flatten()
+        .map(|name| (name, lib_path));
+
+    let mut bins = vec![];
+    let names = manifest.get_each("bin", "name");
+    for (name, path) in names.into_iter().zip(manifest.get_each("bin", "path")) {
+        let Some(Value::String(name)) = name else {
+            continue;
+        };
+        let path = match path {
+            Some(Value::String(path)) => PathBuf::from(path),
+            _ => [format!("src/bin/{}.rs", name), format!("src/bin/{}/main.rs", name)]
+                .into_iter()
+                .map(PathBuf::from)
+                .find(exists)
+                .unwrap_or_else(|| PathBuf::from("src/main.rs")),
+        };
+        bins.push((name.clone(), path));
+    }
+    if !matches!(manifest.get("package", "autobins"), Some(Value::Other(value)) if value == "false") {
+        let mut found = vec![(package_name, PathBuf::from("src/main.rs"))];
+        if let Ok(entries) = fs::read_dir(package_dir.join("src/bin")) {
+            let mut entries: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+            entries.sort();
+            for entry in entries {
+                let Some(name) = entry.file_stem().map(|name| name.to_string_lossy().to_string()) else {
+                    continue;
+                };
+                match entry.is_dir() {
+                    true => found.push((name.clone(), PathBuf::from(format!("src/bin/{}/main.rs", name)))),
+                    false if entry.extension().is_some_and(|extension| extension == "rs") => {
+                        found.push((name.clone(), PathBuf::from(format!("src/bin/{}.rs", name))))
+                    }
+                    false => {}
+                }
+            }
+        }
+        for (name, path) in found {
+            if exists(&path) && !bins.iter().any(|(bin, bin_path)| *bin == name || *bin_path == path) {
+                bins.push((name, path));
+            }
+        }
+    }
+    Some(PackageTargets { lib, bins })
+}
+
+/// The name of the library the files of the path belong to, as used in paths, read from the closest `Cargo.toml`.
+///
+/// `None` for the package of the crate being compiled, named `current`, whose items are under `crate`, or if the
+/// package can't be known.
+pub fn root_crate_name(src_path: &str, current: Option<&str>) -> Option<String> {
+    let base = match is_glob(src_path) {
+        true => glob_base(src_path),
+        false => src_path.to_string(),
+    };
+    let manifest = Path::new(&base)
+        .ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| manifest.is_file())?;
+    let package = Manifest::from_path(manifest).ok()?;
+    match package.package_name() {
+        Some(name) if Some(name) != current => package.lib_name(),
+        _ => None,
+    }
+}
+
+/// The directories under `root` matching the glob pattern of a workspace member, relative to `root`
+fn matching_dirs(root: &Path, pattern: &str) -> Vec<String> {
+    let pattern = pattern.trim_end_matches('/');
+    let depth = pattern.split('/').count();
+    let mut pending = vec![(String::new(), 0)];
+    let mut matching = vec![];
+    while let Some((dir, dir_depth)) = pending.pop() {
+        if dir_depth == depth {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(root.join(&dir)) else {
+            continue;
+        };
+        for entry in entries.flatten().filter(|entry| entry.path().is_dir()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let relative = match dir.is_empty() {
+                true => name,
+                false => format!("{}/{}", dir, name),
+            };
+            if matches_glob(pattern, &relative) {
+                matching.push(relative.clone());
+            }
+            pending.push((relative, dir_depth + 1));
+        }
+    }
+    matching.sort();
+    matching
+}
+
 fn find_segment_and_skip<'a>(segments: &'a [&str], to_find: &[&str], to_skip: usize) -> &'a [&'a str] {
     match segments.iter().rposition(|segment| to_find.contains(segment)) {
         Some(idx) => &segments[(idx + to_skip)..],
@@ -119,6 +893,337 @@ mod tests {
     use quote::ToTokens;
 
     use super::*;
+    use crate::test_utils::TempDir;
+
+    #[test]
+    fn test_matches_glob() {
+        assert!(matches_glob("./src/models/**", "./src/models/user.rs"));
+        assert!(matches_glob("./src/models/**", "./src/models/admin/user.rs"));
+        assert!(!matches_glob("./src/models/**", "./src/routes/user.rs"));
+        assert!(matches_glob("./src/*/models.rs", "./src/users/models.rs"));
+        assert!(!matches_glob("./src/*/models.rs", "./src/users/admin/models.rs"));
+        assert!(matches_glob("./src/**/models.rs", "./src/users/admin/models.rs"));
+        assert!(matches_glob("src/user_?.rs", "./src/user_1.rs"));
+        assert!(matches_glob("./src/*_dto.rs", "./src/user_dto.rs"));
+        assert!(!matches_glob("./src/*_dto.rs", "./src/user.rs"));
+    }
+
+    #[test]
+    fn test_is_packaged() {
+        let manifest = Manifest::parse("[package]\nexclude = [\"fixtures/**\", \"vendor\", \"/src/dev.rs\"]");
+        assert!(is_packaged(&manifest, "src/lib.rs"));
+        assert!(!is_packaged(&manifest, "fixtures/user.rs"));
+        assert!(!is_packaged(&manifest, "src/vendor/snippet.rs"));
+        assert!(!is_packaged(&manifest, "src/dev.rs"));
+        assert!(is_packaged(&manifest, "src/api/dev.rs"));
+
+        let manifest = Manifest::parse("[package]\ninclude = [\"src/**/*.rs\"]\nexclude = [\"src/**\"]");
+        assert!(is_packaged(&manifest, "src/lib.rs"));
+        assert!(!is_packaged(&manifest, "build/generated.rs"));
+    }
+
+    #[test]
+    fn test_parse_files_respects_manifest() {
+        let root = TempDir::new("manifest");
+        fs::create_dir_all(root.join("src/fixtures")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"api\"\nexclude = [\"fixtures\"]",
+        )
+        .unwrap();
+        fs::write(root.join("src/lib.rs"), "pub mod api;").unwrap();
+        fs::write(root.join("src/fixtures/broken.rs"), "not rust").unwrap();
+
+        let files = parse_files(root.join("src")).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].0.ends_with("lib.rs"));
+    }
+
+    #[test]
+    fn test_excluded_files() {
+        let root = TempDir::new("excluded");
+        for dir in ["internal", "api/generated", "generated"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("mod.rs"), "").unwrap();
+        }
+        fs::write(root.join("lib.rs"), "").unwrap();
+        fs::write(root.join("api/mod.rs"), "").unwrap();
+
+        let root_str = root.to_string_lossy().to_string();
+        let excluded = vec![format!("{}/internal/", root_str), format!("{}/**/generated", root_str)];
+        let mut skipped = vec![];
+        let files = collect_files_skipping(&root, &["rs"], &excluded, &mut skipped).unwrap();
+        let excluded_root =
+            collect_files_skipping(&root, &["rs"], std::slice::from_ref(&root_str), &mut skipped).unwrap();
+
+        let mut files: Vec<String> = files
+            .iter()
+            .map(|file| file.strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["api/mod.rs", "lib.rs"]);
+        assert!(excluded_root.is_empty());
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn test_skipped_entries() {
+        let root = TempDir::new("skipped-entries");
+        for dir in ["src/target", "src/.backup", "target/debug/build", "api/target"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("mod.rs"), "").unwrap();
+        }
+        fs::write(root.join("Cargo.toml"), "[package]\nname = \"api\"\n").unwrap();
+        fs::write(root.join("api/target/CACHEDIR.TAG"), "").unwrap();
+        fs::write(root.join("src/.lib.rs.swp.rs"), "").unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        let files = collect_files_with(&root, &["rs"]).unwrap();
+
+        let files: Vec<String> = files
+            .iter()
+            .map(|file| file.strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        assert_eq!(files, vec!["src/lib.rs", "src/target/mod.rs"]);
+    }
+
+    #[test]
+    fn test_retain_unignored() {
+        let root = TempDir::new("gitignore");
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("src/generated")).unwrap();
+        fs::write(root.join(".gitignore"), "# build output\n/out\n*.bak.rs\ngenerated/\n").unwrap();
+        fs::write(root.join("src/.gitignore"), "!keep.bak.rs\n").unwrap();
+        fs::create_dir_all(root.join("out")).unwrap();
+        for file in [
+            "out/api.rs",
+            "src/lib.rs",
+            "src/old.bak.rs",
+            "src/keep.bak.rs",
+            "src/generated/mod.rs",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let files = retain_unignored(collect_files_with(&root, &["rs"]).unwrap());
+
+        let files: Vec<String> = files
+            .iter()
+            .map(|file| file.strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        assert_eq!(files, vec!["src/keep.bak.rs", "src/lib.rs"]);
+    }
+
+    #[test]
+    fn test_collect_files_sorted() {
+        let root = TempDir::new("sorted");
+        fs::create_dir_all(root.join("b")).unwrap();
+        for file in ["c.rs", "a.rs", "b/mod.rs", "b.rs"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let files = collect_files_with(&root, &["rs"]).unwrap();
+
+        let files: Vec<String> = files
+            .iter()
+            .map(|file| file.strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        assert_eq!(files, vec!["a.rs", "b/mod.rs", "b.rs", "c.rs"]);
+    }
+
+    #[test]
+    fn test_tracked_sources() {
+        let root = TempDir::new("tracked");
+        fs::create_dir_all(root.join("src/api/v1")).unwrap();
+        for file in ["src/lib.rs", "src/api/v1/users.rs"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let src = root.join("src").to_string_lossy().to_string();
+        let files = [root.join("src/lib.rs"), root.join("src/api/v1/users.rs")];
+        let sources = tracked_sources(&src, files.iter().map(|file| file.to_str().unwrap()));
+        let root = fs::canonicalize(&root).unwrap();
+
+        let sources: Vec<String> = sources
+            .iter()
+            .map(|source| {
+                Path::new(source)
+                    .strip_prefix(&root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        assert_eq!(
+            sources,
+            vec!["src", "src/lib.rs", "src/api/v1/users.rs", "src/api/v1", "src/api"]
+        );
+        assert!(tracked_sources("./does/not/exist", []).is_empty());
+    }
+
+    #[test]
+    fn test_expand_env_vars() {
+        std::env::set_var("UTOIPAUTO_TEST_OUT_DIR", "/target/out");
+        assert_eq!(
+            expand_env_vars("$UTOIPAUTO_TEST_OUT_DIR/proto.rs").unwrap(),
+            "/target/out/proto.rs"
+        );
+        assert_eq!(
+            expand_env_vars("${UTOIPAUTO_TEST_OUT_DIR}_v2, ./src").unwrap(),
+            "/target/out_v2, ./src"
+        );
+        assert_eq!(expand_env_vars("./src").unwrap(), "./src");
+        assert_eq!(
+            expand_env_vars("$UTOIPAUTO_TEST_UNSET").unwrap_err().to_string(),
+            "utoipauto: `$UTOIPAUTO_TEST_UNSET` of `$UTOIPAUTO_TEST_UNSET` is not set"
+        );
+        assert_eq!(
+            expand_env_vars("${UTOIPAUTO_TEST_OUT_DIR/src").unwrap_err().to_string(),
+            "utoipauto: Unclosed `${` in `${UTOIPAUTO_TEST_OUT_DIR/src`, expected `${NAME}`"
+        );
+        assert_eq!(
+            expand_env_vars("./$/src").unwrap_err().to_string(),
+            "utoipauto: Expected the name of an environment variable after `$` in `./$/src`"
+        );
+    }
+
+    #[test]
+    fn test_package_targets() {
+        let root = TempDir::new("package-targets");
+        fs::create_dir_all(root.join("src/bin/admin")).unwrap();
+        fs::create_dir_all(root.join("api")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"my-app\"\n[lib]\npath = \"api/lib.rs\"\n[[bin]]\nname = \"server\"\n\
+             path = \"server.rs\"",
+        )
+        .unwrap();
+        for file in [
+            "api/lib.rs",
+            "server.rs",
+            "src/main.rs",
+            "src/bin/cli.rs",
+            "src/bin/admin/main.rs",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let targets = package_targets(&root);
+        let bin = |name: &str, path: &str| (name.to_string(), PathBuf::from(path));
+        assert_eq!(
+            targets,
+            Some(PackageTargets {
+                lib: Some(("my_app".to_string(), PathBuf::from("api/lib.rs"))),
+                bins: vec![
+                    bin("server", "server.rs"),
+                    bin("my-app", "src/main.rs"),
+                    bin("admin", "src/bin/admin/main.rs"),
+                    bin("cli", "src/bin/cli.rs")
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_root_crate_name() {
+        let root = TempDir::new("root-crate-name");
+        fs::create_dir_all(root.join("other-api/src/routes")).unwrap();
+        fs::write(root.join("other-api/Cargo.toml"), "[package]\nname = \"other-api\"").unwrap();
+        fs::create_dir_all(root.join("renamed/src")).unwrap();
+        fs::write(
+            root.join("renamed/Cargo.toml"),
+            "[package]\nname = \"renamed-api\"\n[lib]\nname = \"api\"",
+        )
+        .unwrap();
+        let path = |dir: &str| root.join(dir).to_string_lossy().to_string();
+        let other = root_crate_name(&path("other-api/src/routes"), Some("my-app"));
+        let glob = root_crate_name(&format!("{}/**/*.rs", path("other-api/src")), Some("my-app"));
+        let renamed = root_crate_name(&path("renamed/src"), Some("my-app"));
+        let current = root_crate_name(&path("other-api/src"), Some("other-api"));
+        assert_eq!(other, Some("other_api".to_string()));
+        assert_eq!(glob, Some("other_api".to_string()));
+        assert_eq!(renamed, Some("api".to_string()));
+        assert_eq!(current, None);
+    }
+
+    #[test]
+    fn test_check_root() {
+        assert!(check_root("./src").is_ok());
+        assert!(check_root("./src/lib.rs").is_ok());
+        assert!(check_root("./src/**/*.rs").is_ok());
+        let error = check_root("./src/handelrs").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert_eq!(error.to_string(), "`./src/handelrs` does not exist");
+        assert_eq!(
+            check_root("./src/handelrs/**/*.rs").unwrap_err().to_string(),
+            "`./src/handelrs` does not exist"
+        );
+    }
+
+    #[test]
+    fn test_skip_unreadable_files() {
+        let root = TempDir::new("unreadable");
+        fs::write(root.join("lib.rs"), "pub mod api;").unwrap();
+        fs::write(root.join("latin1.rs"), b"// caf\xe9").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("missing.rs"), root.join("dangling.rs")).unwrap();
+
+        let mut skipped = vec![];
+        let files = collect_files_skipping(&root, &["rs"], &[], &mut skipped).unwrap();
+        let files = parse_readable_files(files, &mut skipped).unwrap();
+        assert!(parse_files(&root).is_err());
+
+        assert_eq!(files.len(), 1);
+        assert!(files[0].0.ends_with("lib.rs"));
+        let mut skipped: Vec<String> = skipped
+            .iter()
+            .map(|skipped| skipped.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        skipped.sort();
+        match cfg!(unix) {
+            true => assert_eq!(skipped, vec!["dangling.rs", "latin1.rs"]),
+            false => assert_eq!(skipped, vec!["latin1.rs"]),
+        }
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let root = TempDir::new("invalid");
+        fs::write(root.join("lib.rs"), "pub mod api;").unwrap();
+        fs::write(root.join("api.rs"), "pub fn").unwrap();
+
+        let mut skipped = vec![];
+        let files = collect_files_skipping(&root, &["rs"], &[], &mut skipped).unwrap();
+        let error = parse_readable_files(files, &mut skipped).map(|_| ()).unwrap_err();
+        let missing = parse_file(root.join("missing.rs")).map(|_| ()).unwrap_err();
+
+        assert!(error.to_string().starts_with("utoipauto: Failed to parse `"));
+        assert!(error.to_string().contains("api.rs`"));
+        assert!(skipped.is_empty());
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_parse_failures() {
+        let root = TempDir::new("parse-failures");
+        fs::write(root.join("lib.rs"), "pub mod api;\npub mod models;").unwrap();
+        fs::write(root.join("api.rs"), "pub fn get_users() {}\n<<<<<<< HEAD\n").unwrap();
+        fs::write(
+            root.join("models.rs"),
+            "pub struct User {\n    id: u64\n    name: String,\n}",
+        )
+        .unwrap();
+
+        let mut skipped = vec![];
+        let files = collect_files_skipping(&root, &["rs"], &[], &mut skipped).unwrap();
+        let error = parse_readable_files(files, &mut skipped).map(|_| ()).unwrap_err();
+
+        assert_eq!(error.clone().into_iter().count(), 1);
+        let message = error.to_string();
+        assert!(message.starts_with("utoipauto: Failed to parse 2 files:\n  - `"));
+        #[cfg(feature = "parse-locations")]
+        {
+            assert!(message.contains("api.rs` at line 2, column 1: "));
+            assert!(message.contains("models.rs` at line 3, column 5: expected `,`"));
+        }
+        #[cfg(not(feature = "parse-locations"))]
+        assert!(message.contains("models.rs`: expected `,`"));
+    }
 
     #[test]
     fn test_extract_module_name_from_path() {
@@ -131,6 +1236,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extract_module_name_from_path_with_extensions() {
+        assert_eq!(
+            extract_module_name_from_path("./src/generated/routes.rs.in", "crate")
+                .to_token_stream()
+                .to_string()
+                .replace(" ", ""),
+            "crate::generated::routes"
+        );
+    }
+
     #[test]
     fn test_extract_module_name_from_path_windows() {
         assert_eq!(
@@ -241,6 +1357,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extract_module_name_with_strip_mapping() {
+        let mapping = ModuleMapping {
+            strip: vec!["routes".to_string()],
+            replace: vec![],
+        };
+        assert_eq!(
+            extract_module_name_from_path_with("./src/routes/users/mod.rs", "crate", &mapping)
+                .to_token_stream()
+                .to_string()
+                .replace(" ", ""),
+            "crate::users"
+        );
+    }
+
+    #[test]
+    fn test_extract_module_name_with_replace_mapping() {
+        let mapping = ModuleMapping {
+            strip: vec![],
+            replace: vec![("handlers_v1".to_string(), "v1".to_string())],
+        };
+        assert_eq!(
+            extract_module_name_from_path_with("./src/api/handlers_v1/users.rs", "crate", &mapping)
+                .to_token_stream()
+                .to_string()
+                .replace(" ", ""),
+            "crate::api::v1::users"
+        );
+    }
+
     #[test]
     fn test_extract_module_name_from_workspace_with_external_crate_and_underscore() {
         assert_eq!(
@@ -251,4 +1397,43 @@ mod tests {
             "other_crate::retail_api::controllers"
         );
     }
+
+    #[test]
+    fn test_workspace_paths() {
+        let root = TempDir::new("workspace");
+        let package = |dir: &str, manifest: &str, lib: bool| {
+            fs::create_dir_all(root.join(dir).join("src")).unwrap();
+            fs::write(root.join(dir).join("Cargo.toml"), manifest).unwrap();
+            let file = if lib { "lib.rs" } else { "main.rs" };
+            fs::write(root.join(dir).join("src").join(file), "").unwrap();
+        };
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"server\", \"crates/*\"]\nexclude = [\"crates/old\"]\n",
+        )
+        .unwrap();
+        package("server", "[package]\nname = \"server\"\n", false);
+        package("crates/users-api", "[package]\nname = \"users-api\"\n", true);
+        package(
+            "crates/pets",
+            "[package]\nname = \"pets\"\n[lib]\nname = \"pets_lib\"\n",
+            true,
+        );
+        package("crates/cli", "[package]\nname = \"cli\"\n", false);
+        package("crates/old", "[package]\nname = \"old\"\n", true);
+        fs::create_dir_all(root.join("crates/assets")).unwrap();
+
+        let root_str = root.to_string_lossy().to_string();
+        let paths = workspace_paths(&root_str, Some("server"));
+        let not_a_workspace = workspace_paths(&format!("{}/server", root_str), None);
+        assert_eq!(
+            paths.unwrap(),
+            vec![
+                format!("{}/server/src", root_str),
+                format!("{}/crates/pets/src from pets_lib", root_str),
+                format!("{}/crates/users-api/src from users_api", root_str),
+            ]
+        );
+        assert!(not_a_workspace.unwrap_err().ends_with("has no [workspace] members"));
+    }
 }
@@ -0,0 +1,341 @@
+//! The expressions of `filter = "..."`, e.g. `module.starts_with('crate::api') && !tag('internal')`
+
+/// What an expression is evaluated against : a discovered item, and its routes and tags if it is a handler
+#[derive(Debug, Default)]
+pub struct FilterItem {
+    /// `handler`, `schema`, `response`, `modifier` or `custom_impl`
+    pub kind: &'static str,
+    pub path: String,
+    /// The methods of the routes declared by the handler, lowercase
+    pub methods: Vec<String>,
+    /// The paths of the routes declared by the handler, e.g. `/users/{id}`
+    pub routes: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+impl FilterItem {
+    fn values(&self, field: Field) -> Vec<&str> {
+        match field {
+            Field::Path => vec![&self.path],
+            Field::Module => vec![self.path.rsplit_once("::").map_or("", |(module, _)| module)],
+            Field::Name => vec![self.path.rsplit_once("::").map_or(self.path.as_str(), |(_, name)| name)],
+            Field::Kind => vec![self.kind],
+            Field::Method => self.methods.iter().map(String::as_str).collect(),
+            Field::Route => self.routes.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Path,
+    Module,
+    Name,
+    Kind,
+    Method,
+    Route,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Test {
+    StartsWith,
+    EndsWith,
+    Contains,
+    Equals,
+}
+
+#[derive(Debug, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Tag(String),
+    /// Whether any value of the field passes the test with any of the strings
+    Test(Field, Test, Vec<String>),
+}
+
+/// A parsed `filter`, keeping the discovered items it matches
+#[derive(Debug)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn parse(source: &str) -> Result<Filter, String> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+        };
+        let expr = parser.or()?;
+        match parser.next() {
+            None => Ok(Filter { expr }),
+            Some(token) => Err(format!("unexpected {}", token.describe())),
+        }
+    }
+
+    pub fn matches(&self, item: &FilterItem) -> bool {
+        evaluate(&self.expr, item)
+    }
+}
+
+fn evaluate(expr: &Expr, item: &FilterItem) -> bool {
+    match expr {
+        Expr::And(left, right) => evaluate(left, item) && evaluate(right, item),
+        Expr::Or(left, right) => evaluate(left, item) || evaluate(right, item),
+        Expr::Not(expr) => !evaluate(expr, item),
+        Expr::Tag(tag) => item.tags.contains(tag),
+        Expr::Test(field, test, strings) => item.values(*field).iter().any(|value| {
+            strings.iter().any(|string| match test {
+                Test::StartsWith => value.starts_with(string.as_str()),
+                Test::EndsWith => value.ends_with(string.as_str()),
+                Test::Contains => value.contains(string.as_str()),
+                Test::Equals => value == string,
+            })
+        }),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Punct(&'static str),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(ident) => format!("`{}`", ident),
+            Token::Str(string) => format!("'{}'", string),
+            Token::Punct(punct) => format!("`{}`", punct),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    const PUNCTS: &[&str] = &["&&", "||", "==", "!=", "!", "(", ")", "[", "]", ",", "."];
+    let mut tokens = vec![];
+    let mut rest = source.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c == '\'' || c == '"' {
+            let end = rest[1..]
+                .find(c)
+                .ok_or_else(|| format!("unterminated string {}", rest))?;
+            tokens.push(Token::Str(rest[1..=end].to_string()));
+            rest = &rest[end + 2..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(punct) = PUNCTS.iter().find(|punct| rest.starts_with(**punct)) {
+            tokens.push(Token::Punct(punct));
+            rest = &rest[punct.len()..];
+        } else {
+            return Err(format!("unexpected `{}`", c));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let is_next = matches!(self.tokens.get(self.position), Some(Token::Punct(next)) if *next == punct);
+        if is_next {
+            self.position += 1;
+        }
+        is_next
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), String> {
+        match self.eat(punct) {
+            true => Ok(()),
+            false => Err(format!("expected `{}`, found {}", punct, self.found())),
+        }
+    }
+
+    fn found(&self) -> String {
+        self.tokens
+            .get(self.position)
+            .map_or_else(|| "the end of the filter".to_string(), Token::describe)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        match self.tokens.get(self.position) {
+            Some(Token::Str(string)) => {
+                self.position += 1;
+                Ok(string.clone())
+            }
+            _ => Err(format!("expected a string, found {}", self.found())),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat("||") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.eat("&&") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        match self.eat("!") {
+            true => Ok(Expr::Not(Box::new(self.not()?))),
+            false => self.atom(),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        if self.eat("(") {
+            let expr = self.or()?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+        let field = match self.next() {
+            Some(Token::Ident(ident)) if ident == "tag" => {
+                self.expect("(")?;
+                let tag = self.string()?;
+                self.expect(")")?;
+                return Ok(Expr::Tag(tag));
+            }
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "path" => Field::Path,
+                "module" => Field::Module,
+                "name" => Field::Name,
+                "kind" => Field::Kind,
+                "method" => Field::Method,
+                "route" => Field::Route,
+                _ => {
+                    return Err(format!(
+                        "unknown field `{}`, expected one of path, module, name, kind, method, route or tag(...)",
+                        ident
+                    ))
+                }
+            },
+            Some(token) => return Err(format!("unexpected {}", token.describe())),
+            None => return Err("unexpected end of the filter".to_string()),
+        };
+        if self.eat(".") {
+            let test = match self.next() {
+                Some(Token::Ident(ident)) if ident == "starts_with" => Test::StartsWith,
+                Some(Token::Ident(ident)) if ident == "ends_with" => Test::EndsWith,
+                Some(Token::Ident(ident)) if ident == "contains" => Test::Contains,
+                _ => return Err("expected starts_with, ends_with or contains after `.`".to_string()),
+            };
+            self.expect("(")?;
+            let string = self.string()?;
+            self.expect(")")?;
+            return Ok(Expr::Test(field, test, vec![string]));
+        }
+        if self.eat("==") {
+            return Ok(Expr::Test(field, Test::Equals, vec![self.string()?]));
+        }
+        if self.eat("!=") {
+            return Ok(Expr::Not(Box::new(Expr::Test(
+                field,
+                Test::Equals,
+                vec![self.string()?],
+            ))));
+        }
+        match self.next() {
+            Some(Token::Ident(ident)) if ident == "in" => {
+                self.expect("[")?;
+                let mut strings = vec![self.string()?];
+                while self.eat(",") {
+                    strings.push(self.string()?);
+                }
+                self.expect("]")?;
+                Ok(Expr::Test(field, Test::Equals, strings))
+            }
+            _ => Err("expected `.starts_with(...)`, `== ...`, `!= ...` or `in [...]` after a field".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn handler(path: &str, methods: &[&str], tags: &[&str]) -> FilterItem {
+        FilterItem {
+            kind: "handler",
+            path: path.to_string(),
+            methods: methods.iter().map(|method| method.to_string()).collect(),
+            routes: vec!["/users".to_string()],
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let filter =
+            Filter::parse("module.starts_with('crate::api') && !tag('internal') && method in ['get', \"post\"]")
+                .unwrap();
+        assert!(filter.matches(&handler("crate::api::get_users", &["get"], &["users"])));
+        assert!(!filter.matches(&handler("crate::api::get_stats", &["get"], &["internal"])));
+        assert!(!filter.matches(&handler("crate::api::delete_user", &["delete"], &[])));
+        assert!(!filter.matches(&handler("crate::admin::get_users", &["get"], &[])));
+
+        let schema = FilterItem {
+            kind: "schema",
+            path: "crate::api::User".to_string(),
+            ..Default::default()
+        };
+        let filter = Filter::parse("kind != 'handler' || (route.starts_with('/admin'))").unwrap();
+        assert!(filter.matches(&schema));
+        assert!(!filter.matches(&handler("crate::api::get_users", &["get"], &[])));
+        assert!(Filter::parse("name.ends_with('User') && path == 'crate::api::User'")
+            .unwrap()
+            .matches(&schema));
+    }
+
+    #[test]
+    fn test_filter_precedence() {
+        let filter = Filter::parse("kind == 'schema' || kind == 'handler' && name.contains('x')").unwrap();
+        let schema = FilterItem {
+            kind: "schema",
+            path: "crate::User".to_string(),
+            ..Default::default()
+        };
+        assert!(filter.matches(&schema));
+    }
+
+    #[test]
+    fn test_invalid_filter() {
+        assert_eq!(
+            Filter::parse("module.starts_with('crate").unwrap_err(),
+            "unterminated string 'crate"
+        );
+        assert_eq!(
+            Filter::parse("owner == 'me'").unwrap_err(),
+            "unknown field `owner`, expected one of path, module, name, kind, method, route or tag(...)"
+        );
+        assert_eq!(Filter::parse("tag('a') tag('b')").unwrap_err(), "unexpected `tag`");
+        assert_eq!(
+            Filter::parse("method in ['get'").unwrap_err(),
+            "expected `]`, found the end of the filter"
+        );
+        assert!(Filter::parse("").is_err());
+    }
+}
@@ -1,9 +1,21 @@
+#![cfg_attr(feature = "tracked-path", feature(proc_macro_tracked_path))]
 extern crate proc_macro;
 extern crate proc_macro2;
 extern crate quote;
 extern crate syn;
 pub mod attribute_utils;
+pub mod cache;
+pub mod codegen;
+pub mod debug;
+pub mod diagnostics;
 pub mod discover;
 pub mod file_utils;
+pub mod filter;
+pub mod manifest;
+pub mod path_utils;
+pub mod spec;
 pub mod string_utils;
 pub mod token_utils;
+
+#[cfg(test)]
+mod test_utils;
@@ -0,0 +1,269 @@
+use std::{fs, io, path::Path};
+
+/// Minimal reader for the parts of a `Cargo.toml` manifest utoipauto cares about.
+///
+/// Only tables, array of tables, strings and arrays of strings are understood,
+/// anything else is kept as raw text.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    tables: Vec<Table>,
+}
+
+#[derive(Debug)]
+struct Table {
+    name: String,
+    entries: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Array(Vec<String>),
+    Other(String),
+}
+
+impl Manifest {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Manifest, io::Error> {
+        Ok(Manifest::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn parse(content: &str) -> Manifest {
+        let mut tables = vec![Table {
+            name: String::new(),
+            entries: vec![],
+        }];
+        let mut lines = content.lines();
+        while let Some(line) = lines.next() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                let name = line.trim_start_matches('[').trim_end_matches(']').trim();
+                tables.push(Table {
+                    name: name.to_string(),
+                    entries: vec![],
+                });
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let mut value = value.trim().to_string();
+            // Arrays may span several lines
+            if value.starts_with('[') {
+                while !is_closed(&value) {
+                    match lines.next() {
+                        Some(next) => {
+                            value.push(' ');
+                            value.push_str(strip_comment(next).trim());
+                        }
+                        None => break,
+                    }
+                }
+            }
+            let key = key.trim().trim_matches('"').to_string();
+            tables.last_mut().unwrap().entries.push((key, parse_value(&value)));
+        }
+        Manifest { tables }
+    }
+
+    /// Get a value of the first table `table`, e.g. `get("package", "name")`
+    pub fn get(&self, table: &str, key: &str) -> Option<&Value> {
+        self.tables
+            .iter()
+            .filter(|t| t.name == table)
+            .find_map(|t| t.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v))
+    }
+
+    /// The value of the key in each of the tables `table`, e.g. the `path` of every `[[bin]]`
+    pub fn get_each(&self, table: &str, key: &str) -> Vec<Option<&Value>> {
+        self.tables
+            .iter()
+            .filter(|t| t.name == table)
+            .map(|t| t.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v))
+            .collect()
+    }
+
+    /// The keys of the tables `table`, `""` being the entries before the first table
+    pub fn keys(&self, table: &str) -> Vec<&str> {
+        self.tables
+            .iter()
+            .filter(|t| t.name == table)
+            .flat_map(|t| t.entries.iter().map(|(key, _)| key.as_str()))
+            .collect()
+    }
+
+    pub fn has_table(&self, table: &str) -> bool {
+        self.tables.iter().any(|t| t.name == table)
+    }
+
+    pub fn get_str(&self, table: &str, key: &str) -> Option<&str> {
+        match self.get(table, key)? {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The `[package].name` of the manifest
+    pub fn package_name(&self) -> Option<&str> {
+        self.get_str("package", "name")
+    }
+
+    /// The name of the library crate, as used in paths : `[lib].name` or the package name
+    pub fn lib_name(&self) -> Option<String> {
+        self.get_str("lib", "name")
+            .or(self.package_name())
+            .map(|name| name.replace('-', "_"))
+    }
+}
+
+/// The characters of the text outside of its strings, with their index. A string ends with the quote it started with,
+/// a `"` one may contain `\"` escapes, e.g. `"it's \"#1\""`
+fn outside_strings(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut quote = None;
+    let mut escaped = false;
+    text.char_indices().filter(move |&(_, c)| {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None => return true,
+        }
+        false
+    })
+}
+
+fn strip_comment(line: &str) -> &str {
+    match outside_strings(line).find(|(_, c)| *c == '#') {
+        Some((i, _)) => &line[..i],
+        None => line,
+    }
+}
+
+fn is_closed(value: &str) -> bool {
+    let mut depth = 0;
+    for (_, c) in outside_strings(value) {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    depth <= 0
+}
+
+fn parse_value(value: &str) -> Value {
+    if let Some(string) = parse_string(value) {
+        return Value::String(string);
+    }
+    if value.starts_with('[') && value.ends_with(']') {
+        let items: Option<Vec<String>> = value[1..value.len() - 1]
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(parse_string)
+            .collect();
+        if let Some(items) = items {
+            return Value::Array(items);
+        }
+    }
+    Value::Other(value.to_string())
+}
+
+fn parse_string(value: &str) -> Option<String> {
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let content = (value.len() >= 2 && value.ends_with(quote)).then(|| &value[1..value.len() - 1])?;
+    // Only the basic strings have escapes, the ones of the quote and of the backslash are kept as the character
+    if quote == '\'' {
+        return Some(content.to_string());
+    }
+    let mut string = String::new();
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(escaped @ ('"' | '\\'))) => {
+                string.push(escaped);
+                chars.next();
+            }
+            _ => string.push(c),
+        }
+    }
+    Some(string)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+[package]
+name = "my-api" # the name
+version.workspace = true
+exclude = [
+    "fixtures/**", # test data
+    "vendor",
+]
+
+[lib]
+path = "src/lib.rs"
+
+[dependencies]
+utoipa = { version = "5", features = ["yaml"] }
+"#;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::parse(MANIFEST);
+        assert_eq!(manifest.package_name(), Some("my-api"));
+        assert_eq!(manifest.get_str("lib", "path"), Some("src/lib.rs"));
+        assert_eq!(
+            manifest.get("package", "exclude"),
+            Some(&Value::Array(vec!["fixtures/**".to_string(), "vendor".to_string()]))
+        );
+        assert!(matches!(manifest.get("dependencies", "utoipa"), Some(Value::Other(_))));
+        assert_eq!(manifest.get("package", "missing"), None);
+    }
+
+    #[test]
+    fn test_parse_quotes() {
+        let manifest = Manifest::parse(
+            "description = \"it's # not a comment\" # a comment\n\
+             title = 'the \"#1\" api' # a comment\n\
+             motto = \"say \\\"hi\\\" # there\"\n\
+             keywords = [\"it's\", # a comment\n\"]\", 'api']",
+        );
+        assert_eq!(manifest.get_str("", "description"), Some("it's # not a comment"));
+        assert_eq!(manifest.get_str("", "title"), Some("the \"#1\" api"));
+        assert_eq!(manifest.get_str("", "motto"), Some("say \"hi\" # there"));
+        assert_eq!(
+            manifest.get("", "keywords"),
+            Some(&Value::Array(vec![
+                "it's".to_string(),
+                "]".to_string(),
+                "api".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_get_each() {
+        let manifest = Manifest::parse("[[bin]]\nname = \"server\"\npath = \"bin/server.rs\"\n[[bin]]\nname = \"cli\"");
+        assert_eq!(
+            manifest.get_each("bin", "path"),
+            vec![Some(&Value::String("bin/server.rs".to_string())), None]
+        );
+        assert_eq!(manifest.get_each("lib", "path"), Vec::<Option<&Value>>::new());
+    }
+
+    #[test]
+    fn test_lib_name() {
+        assert_eq!(Manifest::parse(MANIFEST).lib_name(), Some("my_api".to_string()));
+        assert_eq!(
+            Manifest::parse("[package]\nname = \"my-api\"\n[lib]\nname = \"api\"").lib_name(),
+            Some("api".to_string())
+        );
+    }
+}
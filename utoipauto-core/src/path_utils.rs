@@ -0,0 +1,145 @@
+use quote::ToTokens;
+
+/// Render a path without whitespaces, e.g. `crate::api::get_user`
+pub fn path_to_string(path: &syn::Path) -> String {
+    path.to_token_stream().to_string().replace(' ', "")
+}
+
+/// Check if `path` starts with all the segments of `prefix`, ignoring generic arguments
+pub fn starts_with(path: &syn::Path, prefix: &syn::Path) -> bool {
+    path.segments.len() >= prefix.segments.len()
+        && path
+            .segments
+            .iter()
+            .zip(prefix.segments.iter())
+            .all(|(segment, prefix_segment)| segment.ident == prefix_segment.ident)
+}
+
+/// Replace the `prefix` of `path` by `replacement`, return None if the path does not start with `prefix`
+///
+/// # Example
+/// ```
+/// use utoipauto_core::path_utils::{path_to_string, replace_prefix};
+/// let path = replace_prefix(
+///     &syn::parse_quote!(crate::internal::api::get_user),
+///     &syn::parse_quote!(crate::internal),
+///     &syn::parse_quote!(crate::public),
+/// )
+/// .unwrap();
+/// assert_eq!(path_to_string(&path), "crate::public::api::get_user");
+/// ```
+pub fn replace_prefix(path: &syn::Path, prefix: &syn::Path, replacement: &syn::Path) -> Option<syn::Path> {
+    if !starts_with(path, prefix) {
+        return None;
+    }
+    let mut segments = replacement.segments.clone();
+    segments.extend(path.segments.iter().skip(prefix.segments.len()).cloned());
+    Some(syn::Path {
+        leading_colon: replacement.leading_colon,
+        segments,
+    })
+}
+
+/// Remove the `prefix` of `path`, keeping the remaining relative path.
+/// Return None if the path does not start with `prefix`, or would be empty.
+pub fn strip_prefix(path: &syn::Path, prefix: &syn::Path) -> Option<syn::Path> {
+    if !starts_with(path, prefix) || path.segments.len() == prefix.segments.len() {
+        return None;
+    }
+    Some(syn::Path {
+        leading_colon: None,
+        segments: path.segments.iter().skip(prefix.segments.len()).cloned().collect(),
+    })
+}
+
+/// Resolve the leading `self::` and `super::` of a `path` written in `module`, e.g. `self::api::get_user`
+/// written in `crate::docs` is `crate::docs::api::get_user`. Other paths are returned unchanged.
+pub fn canonicalize(path: &syn::Path, module: &syn::Path) -> syn::Path {
+    let mut segments = path.segments.iter().peekable();
+    if path.leading_colon.is_some() || !segments.peek().is_some_and(|s| s.ident == "self" || s.ident == "super") {
+        return path.clone();
+    }
+    let mut canonical: Vec<syn::PathSegment> = module.segments.iter().cloned().collect();
+    if segments.peek().is_some_and(|segment| segment.ident == "self") {
+        segments.next();
+    }
+    while segments.peek().is_some_and(|segment| segment.ident == "super") {
+        // `super` of the crate root
+        if canonical.len() <= 1 {
+            return path.clone();
+        }
+        canonical.pop();
+        segments.next();
+    }
+    canonical.extend(segments.cloned());
+    syn::Path {
+        leading_colon: module.leading_colon,
+        segments: canonical.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_starts_with() {
+        assert!(starts_with(
+            &syn::parse_quote!(crate::api::get_user),
+            &syn::parse_quote!(crate::api)
+        ));
+        assert!(!starts_with(
+            &syn::parse_quote!(crate::apis::get_user),
+            &syn::parse_quote!(crate::api)
+        ));
+        assert!(!starts_with(&syn::parse_quote!(crate), &syn::parse_quote!(crate::api)));
+    }
+
+    #[test]
+    fn test_replace_prefix_no_match() {
+        assert!(replace_prefix(
+            &syn::parse_quote!(crate::api::get_user),
+            &syn::parse_quote!(crate::internal),
+            &syn::parse_quote!(crate::public),
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_strip_prefix() {
+        assert_eq!(
+            strip_prefix(
+                &syn::parse_quote!(crate::internal::api::get_user),
+                &syn::parse_quote!(crate::internal)
+            )
+            .map(|path| path_to_string(&path)),
+            Some("api::get_user".to_string())
+        );
+        assert!(strip_prefix(&syn::parse_quote!(crate::internal), &syn::parse_quote!(crate::internal)).is_none());
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let canonical = |path: syn::Path| path_to_string(&canonicalize(&path, &syn::parse_quote!(crate::api::docs)));
+        assert_eq!(
+            canonical(syn::parse_quote!(self::get_user)),
+            "crate::api::docs::get_user"
+        );
+        assert_eq!(
+            canonical(syn::parse_quote!(super::users::get_user)),
+            "crate::api::users::get_user"
+        );
+        assert_eq!(
+            canonical(syn::parse_quote!(self::super::super::get_user)),
+            "crate::get_user"
+        );
+        assert_eq!(
+            canonical(syn::parse_quote!(crate::users::get_user)),
+            "crate::users::get_user"
+        );
+        assert_eq!(
+            canonical(syn::parse_quote!(super::super::super::get_user)),
+            "super::super::super::get_user"
+        );
+    }
+}
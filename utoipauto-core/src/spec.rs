@@ -0,0 +1,199 @@
+use std::{fs, io, path::Path};
+
+/// Minimal reader for the endpoints of an OpenApi documentation exported as json, e.g. by `cargo utoipauto export`.
+///
+/// The whole document is parsed, but only the keys of the `paths` object and of its path items are kept.
+#[derive(Debug, Default)]
+pub struct Spec {
+    /// The endpoints of the documentation, as `GET /users/{id}`
+    pub endpoints: Vec<String>,
+}
+
+const METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+enum Value {
+    Object(Vec<(String, Value)>),
+    Other,
+}
+
+impl Spec {
+    /// Read the documentation at `path`, `None` if there is no such file yet
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Option<Spec>, String> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(content) => Spec::parse(&content)
+                .map(Some)
+                .map_err(|e| format!("Invalid OpenApi documentation {}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Spec, String> {
+        let mut parser = Parser {
+            chars: content.chars().peekable(),
+        };
+        let document = parser.value()?;
+        parser.skip_whitespace();
+        if parser.chars.next().is_some() {
+            return Err("trailing characters after the document".to_string());
+        }
+
+        let mut endpoints = vec![];
+        if let Value::Object(entries) = document {
+            let paths = entries.into_iter().find(|(key, _)| key == "paths");
+            if let Some((_, Value::Object(paths))) = paths {
+                for (path, item) in paths {
+                    let Value::Object(operations) = item else {
+                        continue;
+                    };
+                    let methods = operations.iter().filter(|(key, _)| METHODS.contains(&key.as_str()));
+                    endpoints.extend(methods.map(|(method, _)| format!("{} {}", method.to_uppercase(), path)));
+                }
+            }
+        }
+        Ok(Spec { endpoints })
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected `{}`, found `{}`", expected, c)),
+            None => Err(format!("expected `{}`, found the end of the document", expected)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('{') => self.object(),
+            Some('[') => {
+                self.chars.next();
+                self.skip_whitespace();
+                if self.chars.next_if_eq(&']').is_none() {
+                    loop {
+                        self.value()?;
+                        self.skip_whitespace();
+                        match self.chars.next() {
+                            Some(',') => continue,
+                            Some(']') => break,
+                            _ => return Err("expected `,` or `]` in an array".to_string()),
+                        }
+                    }
+                }
+                Ok(Value::Other)
+            }
+            Some('"') => self.string().map(|_| Value::Other),
+            Some(_) => {
+                // Numbers, booleans and null, their value doesn't matter
+                let mut literal = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_alphanumeric() || "+-.".contains(*c)) {
+                    literal.push(c);
+                }
+                match literal.is_empty() {
+                    true => Err(format!("unexpected `{}`", self.chars.peek().unwrap())),
+                    false => Ok(Value::Other),
+                }
+            }
+            None => Err("unexpected end of the document".to_string()),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut entries = vec![];
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'}').is_some() {
+            return Ok(Value::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Object(entries)),
+                _ => return Err("expected `,` or `}` in an object".to_string()),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some('r') => string.push('\r'),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('u') => {
+                        let code: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                        let code = u32::from_str_radix(&code, 16).map_err(|_| "invalid unicode escape".to_string())?;
+                        string.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    Some(c) => string.push(c),
+                    None => return Err("unterminated string".to_string()),
+                },
+                Some(c) => string.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SPEC: &str = r#"{
+  "openapi": "3.1.0",
+  "info": { "title": "Pets \"API\"", "version": "1.0.0" },
+  "paths": {
+    "/pets": {
+      "get": { "tags": ["pets"], "responses": { "200": { "description": "ok" } } },
+      "post": { "deprecated": true, "responses": {} }
+    },
+    "/pets/{id}": {
+      "parameters": [{ "name": "id", "in": "path", "required": true }],
+      "delete": { "operationId": "delete_pet", "x-rate": 1.5e3 }
+    }
+  },
+  "components": { "schemas": { "Pet": { "type": "object", "nullable": null } } }
+}"#;
+
+    #[test]
+    fn test_parse_spec() {
+        let spec = Spec::parse(SPEC).unwrap();
+        assert_eq!(spec.endpoints, vec!["GET /pets", "POST /pets", "DELETE /pets/{id}"]);
+        assert!(Spec::parse("{}").unwrap().endpoints.is_empty());
+    }
+
+    #[test]
+    fn test_parse_invalid_spec() {
+        assert!(Spec::parse("{\"paths\": {").is_err());
+        assert!(Spec::parse("{\"paths\": {}} trailing").is_err());
+        assert!(Spec::parse("{\"paths\" {}}").is_err());
+    }
+
+    #[test]
+    fn test_missing_spec() {
+        assert!(Spec::from_path("./does/not/exist.json").unwrap().is_none());
+    }
+}
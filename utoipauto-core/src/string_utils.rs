@@ -1,6 +1,14 @@
 use proc_macro2::TokenStream;
 
-use crate::{discover::discover_from_file, token_utils::Parameters};
+use crate::{
+    debug,
+    diagnostics::{self, Warning, WarningKind},
+    discover::{discover_from_crate_root, discover_from_file, discover_from_included, sort_paths, Discovered},
+    file_utils::{expand_env_vars, root_crate_name},
+    manifest::Manifest,
+    path_utils::{canonicalize, path_to_string, starts_with},
+    token_utils::{package_location, Parameters},
+};
 
 pub fn rem_first_and_last(value: &str) -> &str {
     let mut chars = value.chars();
@@ -34,7 +42,7 @@ pub fn trim_parentheses(str: &str) -> String {
 /// use utoipauto_core::string_utils::extract_paths;
 /// let paths = extract_paths(
 ///    "(utoipa_auto_macro::tests::controllers::controller1 => ./utoipa-auto-macro/tests/controllers/controller1.rs) ; (utoipa_auto_macro::tests::controllers::controller2 => ./utoipa-auto-macro/tests/controllers/controller2.rs)"
-/// );
+/// ).unwrap();
 /// assert_eq!(
 ///   paths,
 ///  vec![
@@ -43,11 +51,13 @@ pub fn trim_parentheses(str: &str) -> String {
 /// ]   
 /// );
 /// ```
-pub fn extract_paths(attributes: &str) -> Vec<String> {
+pub fn extract_paths(attributes: &str) -> syn::Result<Vec<String>> {
     let attributes = trim_parentheses(attributes);
 
     if attributes.contains('|') {
-        panic!("Please use the new syntax ! paths=\"(MODULE_TREE_PATH => MODULE_SRC_PATH) ;\"")
+        return Err(diagnostics::error(
+            "Please use the new syntax ! paths=\"(MODULE_TREE_PATH => MODULE_SRC_PATH) ;\"",
+        ));
     }
     let paths = if attributes.contains("=>") {
         extract_paths_arrow(attributes)
@@ -55,9 +65,9 @@ pub fn extract_paths(attributes: &str) -> Vec<String> {
         extract_paths_coma(attributes)
     };
     if paths.is_empty() {
-        panic!("utoipauto: No paths specified !")
+        return Err(diagnostics::error("No paths specified !"));
     }
-    paths
+    Ok(paths)
 }
 
 // (MODULE_TREE_PATH => MODULE_SRC_PATH) ; (MODULE_TREE_PATH => MODULE_SRC_PATH) ;
@@ -91,38 +101,186 @@ fn extract_paths_coma(attributes: String) -> Vec<String> {
 /// Return the list of all the functions with the #[utoipa] attribute
 /// and the list of all the structs with the #[derive(ToSchema)] attribute
 /// and the list of all the structs with the #[derive(ToResponse)] attribute
-pub fn discover(paths: Vec<String>, params: &Parameters) -> (TokenStream, TokenStream, TokenStream) {
-    let mut uto_paths = Vec::new();
-    let mut uto_models = Vec::new();
-    let mut uto_responses = Vec::new();
+/// and the list of all the structs with the #[utoipauto_modifier] attribute
+pub fn discover(paths: Vec<String>, params: &Parameters) -> syn::Result<Discovered> {
+    let mut discovered = Discovered::default();
     for p in paths {
-        let path = extract_crate_name(p);
-        let (list_fn, list_model, list_reponse) = discover_from_file(path.paths, path.crate_name, params);
-        uto_paths.extend(list_fn);
-        uto_models.extend(list_model);
-        uto_responses.extend(list_reponse);
-    }
-    // We need to add a coma after each path
-    (
-        quote::quote!(#(#uto_paths),*),
-        quote::quote!(#(#uto_models),*),
-        quote::quote!(#(#uto_responses),*),
-    )
+        let path = extract_crate_name(expand_env_vars(&p)?);
+        discovered.extend(match &path.module {
+            Some(module) => discover_from_included(path.paths, module, params)?,
+            None if path.is_crate_root => discover_from_crate_root(path.paths, path.crate_name, params)?,
+            None => discover_from_file(path.paths, path.crate_name, params)?,
+        });
+    }
+    if !params.only.is_empty() {
+        let is_kept = |path: &syn::Path| params.only.iter().any(|module| starts_with(path, module));
+        debug::retain_logged(&mut discovered.fns, "only", is_kept);
+        debug::retain_logged(&mut discovered.models, "only", is_kept);
+        debug::retain_logged(&mut discovered.responses, "only", is_kept);
+        debug::retain_logged(&mut discovered.modifiers, "only", is_kept);
+        debug::retain_logged(&mut discovered.custom_impls, "only", is_kept);
+    }
+    if let Some(filter) = &params.filter {
+        discovered.retain_matching(filter);
+    }
+    match params.sub_docs {
+        Some(_) => discovered.retain_sub_docs(params),
+        None => discovered.sub_docs.clear(),
+    }
+    // The items added by hand are checked, deduplicated and sorted like the discovered ones
+    let doc_module = discovered.doc_module(params);
+    let extras = [
+        (&mut discovered.fns, &params.extra_paths),
+        (&mut discovered.models, &params.extra_schemas),
+        (&mut discovered.responses, &params.extra_responses),
+    ];
+    let mut has_extras = false;
+    for (items, extra_items) in extras {
+        items.extend(extra_items.iter().map(|path| canonicalize(path, &doc_module)));
+        has_extras |= !extra_items.is_empty();
+    }
+    if !params.generic_schemas.is_empty() {
+        discovered.instantiate_generic_models(&params.generic_schemas, &doc_module)?;
+        has_extras = true;
+    }
+    if has_extras {
+        discovered.dedup(&[]);
+    }
+    discovered.check_visibility(params);
+    discovered.check_routes();
+    if let Some(facade) = &params.reroot {
+        // The working directory of the compiler is the root of the workspace for its members
+        let (_, package_prefix) = package_location();
+        discovered.reroot(facade, &package_prefix, params)?;
+    }
+    if let Some(path_transform) = &params.path_transform {
+        for path in discovered
+            .fns
+            .iter_mut()
+            .chain(discovered.models.iter_mut())
+            .chain(discovered.responses.iter_mut())
+            .chain(discovered.modifiers.iter_mut())
+            .chain(discovered.custom_impls.iter_mut())
+        {
+            *path = path_transform(path.clone());
+        }
+    }
+    sort_paths(&mut discovered.fns, params.order);
+    sort_paths(&mut discovered.models, params.order);
+    sort_paths(&mut discovered.responses, params.order);
+    Ok(discovered)
+}
+
+/// Discover the items of the paths, and the components of `components_from` if requested,
+/// merged with the discovery of the other `#[utoipauto]` attributes of the doc struct
+pub fn discover_all(paths: &[String], params: &Parameters) -> syn::Result<Discovered> {
+    let mut discovered = discover_with_components(paths, params)?;
+    for merged in &params.merged {
+        discovered.extend(discover_with_components(&extract_paths(&merged.paths)?, merged)?);
+    }
+    discovered.check_response_bodies();
+    if params.checks(WarningKind::DuplicateSchema) {
+        discovered.check_schema_names();
+    }
+    if discovered.is_empty() && params.checks(WarningKind::EmptyDiscovery) {
+        discovered.warnings.push(Warning::new(
+            WarningKind::EmptyDiscovery,
+            format!("nothing was discovered in `{}`", params.paths),
+        ));
+    }
+    Ok(discovered)
+}
+
+fn discover_with_components(paths: &[String], params: &Parameters) -> syn::Result<Discovered> {
+    let mut discovered = discover(paths.to_vec(), params)?;
+
+    // Collect the schemas and responses from a narrower set of paths if requested
+    if let Some(components_from) = &params.components_from {
+        let mut components = discover(extract_paths(components_from)?, params)?;
+        discovered.models = std::mem::take(&mut components.models);
+        discovered.responses = std::mem::take(&mut components.responses);
+        components.fns.clear();
+        components.modifiers.clear();
+        discovered.extend(components);
+    }
+    Ok(discovered)
+}
+
+/// Return the list of all the structs with the #[derive(ToSchema)] attribute in the given paths,
+/// as expanded by `include_schemas!("./src/models/**")`
+pub fn discover_schemas(paths: &str, params: &Parameters) -> syn::Result<TokenStream> {
+    let uto_models = discover(extract_paths(paths)?, params)?.models;
+    Ok(quote::quote!(#(#uto_models),*))
+}
+
+/// Describe the items discovered in the given paths, as expanded by `discovered!("./src")` :
+/// a `&'static [utoipauto::DiscoveredItem]`
+pub fn discover_items(paths: &str, params: &Parameters) -> syn::Result<TokenStream> {
+    let discovered = discover(extract_paths(paths)?, params)?;
+    let categories = [
+        (quote::quote!(Handler), &discovered.fns),
+        (quote::quote!(Schema), &discovered.models),
+        (quote::quote!(Response), &discovered.responses),
+        (quote::quote!(Modifier), &discovered.modifiers),
+        (quote::quote!(CustomImpl), &discovered.custom_impls),
+    ];
+    let items = categories.iter().flat_map(|(kind, paths)| {
+        paths.iter().map(move |path| {
+            let full_path = path_to_string(path);
+            let (module, name) = full_path.rsplit_once("::").unwrap_or(("", &full_path));
+            quote::quote! {
+                ::utoipauto::DiscoveredItem {
+                    name: #name,
+                    module: #module,
+                    path: #full_path,
+                    kind: ::utoipauto::ItemKind::#kind,
+                }
+            }
+        })
+    });
+    Ok(quote::quote!(&[#(#items),*]))
 }
 
 #[derive(Debug, PartialEq)]
 struct Path {
     paths: String,
     crate_name: String,
+    /// The module the files are included into, with `... as crate::proto`
+    module: Option<String>,
+    /// Whether the path is the root of the crate, with `... in crate`
+    is_crate_root: bool,
 }
 
 fn extract_crate_name(path: String) -> Path {
+    let (path, module) = match path.split_once(" as ") {
+        Some((path, module)) => (path, Some(module.trim().to_string())),
+        None => (path.as_str(), None),
+    };
+    if let Some((paths, crate_name)) = path.split_once(" in ") {
+        return Path {
+            paths: paths.to_string(),
+            crate_name: crate_name.trim().to_string(),
+            module,
+            is_crate_root: true,
+        };
+    }
     let mut path = path.split(" from ");
     let paths = path.next().unwrap();
-    let crate_name = path.next().unwrap_or("crate").to_string();
+    // The crate of another package is its library, not to mistype its name. Out of a build, e.g. with
+    // cargo-utoipauto, the current package is the one of the working directory
+    let crate_name = path.next().map(str::to_string).unwrap_or_else(|| {
+        let current = std::env::var("CARGO_PKG_NAME").ok().or_else(|| {
+            Manifest::from_path("Cargo.toml")
+                .ok()
+                .and_then(|manifest| manifest.package_name().map(str::to_string))
+        });
+        root_crate_name(paths, current.as_deref()).unwrap_or("crate".to_string())
+    });
     Path {
         paths: paths.to_string(),
         crate_name,
+        module,
+        is_crate_root: false,
     }
 }
 
@@ -133,7 +291,7 @@ mod test {
     #[test]
     fn test_extract_path() {
         let paths = "./src";
-        let extracted = extract_paths(paths);
+        let extracted = extract_paths(paths).unwrap();
         assert_eq!(extracted, vec!["./src".to_string()]);
     }
 
@@ -145,7 +303,9 @@ mod test {
             ),
             super::Path {
                 paths: "utoipa_auto_macro::from::controllers::controller1".to_string(),
-                crate_name: "utoipa_auto_macro".to_string()
+                crate_name: "utoipa_auto_macro".to_string(),
+                module: None,
+                is_crate_root: false,
             }
         );
     }
@@ -156,7 +316,35 @@ mod test {
             super::extract_crate_name("utoipa_auto_macro::from::controllers::controller1".to_string()),
             super::Path {
                 paths: "utoipa_auto_macro::from::controllers::controller1".to_string(),
-                crate_name: "crate".to_string()
+                crate_name: "crate".to_string(),
+                module: None,
+                is_crate_root: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_extract_crate_name_included() {
+        assert_eq!(
+            super::extract_crate_name("/target/out/proto as crate::proto".to_string()),
+            super::Path {
+                paths: "/target/out/proto".to_string(),
+                crate_name: "crate".to_string(),
+                module: Some("crate::proto".to_string()),
+                is_crate_root: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_extract_crate_name_root() {
+        assert_eq!(
+            super::extract_crate_name("./crate_folder in my_api".to_string()),
+            super::Path {
+                paths: "./crate_folder".to_string(),
+                crate_name: "my_api".to_string(),
+                module: None,
+                is_crate_root: true,
             }
         );
     }
@@ -166,7 +354,8 @@ mod test {
         assert_eq!(
             super::extract_paths(
                 "(utoipa_auto_macro::tests::controllers::controller1 => ./utoipa-auto-macro/tests/controllers/controller1.rs) ; (utoipa_auto_macro::tests::controllers::controller2 => ./utoipa-auto-macro/tests/controllers/controller2.rs)"
-            ),
+            )
+            .unwrap(),
             vec![
                 "./utoipa-auto-macro/tests/controllers/controller1.rs".to_string(),
                 "./utoipa-auto-macro/tests/controllers/controller2.rs".to_string()
@@ -179,7 +368,8 @@ mod test {
         assert_eq!(
             super::extract_paths(
                 "./utoipa-auto-macro/tests/controllers/controller1.rs, ./utoipa-auto-macro/tests/controllers/controller2.rs"
-            ),
+            )
+            .unwrap(),
             vec![
                 "./utoipa-auto-macro/tests/controllers/controller1.rs".to_string(),
                 "./utoipa-auto-macro/tests/controllers/controller2.rs".to_string()
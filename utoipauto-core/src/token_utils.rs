@@ -1,30 +1,1194 @@
 use proc_macro::TokenStream;
-use proc_macro2::Literal;
+use proc_macro2::{Delimiter, Literal, TokenTree};
 use quote::quote;
-use syn::Attribute;
+use syn::{parse::Parser, punctuated::Punctuated, Attribute};
+
+use crate::{
+    diagnostics::{self, WarningKind},
+    file_utils::{package_targets, workspace_paths, ModuleMapping, PackageTargets},
+    filter::Filter,
+    manifest::{Manifest, Value},
+    path_utils::{replace_prefix, strip_prefix},
+};
+
+/// Transformation applied to every discovered path before it is emitted
+pub type PathTransform = Box<dyn Fn(syn::Path) -> syn::Path>;
 
 pub struct Parameters {
     pub paths: String,
-    pub fn_attribute_name: String,
+    /// The attributes of the handlers, matching any segment of the path of the attribute, e.g. `utoipa` or `path`
+    /// for `#[utoipa::path]`
+    pub fn_attribute_names: Vec<String>,
     pub schema_attribute_name: String,
     pub response_attribute_name: String,
+    /// The segments of the path of the utoipa crate in the scanned sources, e.g. `["openapi"]` for a dependency renamed
+    /// to `openapi`, or `["my_framework", "openapi"]` for a re-export
+    pub utoipa_crate: Vec<String>,
+    /// The derives of the crate implying `ToSchema`, e.g. `ApiModel` deriving it in turn, matched by their last segment
+    pub schema_derives: Vec<String>,
+    /// The derives of the crate implying `ToResponse`, matched by their last segment
+    pub response_derives: Vec<String>,
+    /// Whether the schemas are discovered from their derive, their manual `impl ToSchema`, or both
+    pub schema_source: SchemaSource,
+    /// Where the manual implementations of ToSchema and ToResponse go
+    pub custom_impls: CustomImpls,
+    /// Only match the manual implementations of the traits written with the path of `utoipa_crate`, e.g.
+    /// `impl utoipa::ToSchema for X`
+    pub qualified_impls: bool,
+    pub order: Order,
+    pub path_transform: Option<PathTransform>,
+    pub components_into: Option<syn::Path>,
+    pub components_from: Option<String>,
+    pub module_mapping: ModuleMapping,
+    pub prefer_reexports: bool,
+    /// Emit the paths of the re-exports at the crate root, like a prelude, when there are some
+    pub flatten: bool,
+    /// Report what looks like mistakes in the discovery setup
+    pub pedantic: bool,
+    /// Report the handlers with neither a doc comment nor a description
+    pub require_descriptions: bool,
+    /// List the discovered items in the doc comment of the annotated struct
+    pub document_generated: bool,
+    /// Scan the sources in doc builds too, see `is_doc_build`
+    pub scan_docs: bool,
+    /// What the discovery does under rust-analyzer, see `is_rust_analyzer`
+    pub rust_analyzer: IdeMode,
+    /// Report the handlers documenting no response body
+    pub require_response_bodies: bool,
+    /// Report the functions with one of the `route_attributes` but no `#[utoipa::path]`
+    pub require_documented_routes: bool,
+    /// The routing attributes of the frameworks, e.g. `get` for `#[get("/users")]`, see `DEFAULT_ROUTE_ATTRIBUTES`
+    pub route_attributes: Vec<String>,
+    /// The documentation exported by a previous build, to report the endpoints removed since
+    pub previous_spec: Option<String>,
+    /// Fail rather than warn when an endpoint of `previous_spec` was removed
+    pub deny_removed_endpoints: bool,
+    /// The file the expansion of the macro is written to, for inspection, see `codegen::write_expanded`
+    pub emit_to: Option<String>,
+    /// The classes of findings failing the build rather than warning, see `checks`. `strict = true` adds the ones of
+    /// an empty discovery, see `STRICT_FAIL_ON`
+    pub fail_on: Vec<WarningKind>,
+    /// Number of nested inline modules to descend into, unlimited when None
+    pub max_item_depth: Option<usize>,
+    /// Maximum number of files of a path, to fail fast on a mistakenly broad path
+    pub max_files: usize,
+    /// The file chosen for the modules backed by both `foo.rs` and `foo/mod.rs`, as `(module, file)`
+    pub module_files: Vec<(String, String)>,
+    /// Extensions of the files to scan, e.g. `rs` or `rs.in`
+    pub extensions: Vec<String>,
+    /// The files and directories left out of the scan, with their subtrees
+    pub exclude: Vec<String>,
+    /// Scan the `#[cfg(test)]` modules and the `tests` directories too
+    pub include_tests: bool,
+    /// Leave out the files ignored by the `.gitignore` files of the repository
+    pub respect_gitignore: bool,
+    /// Skip the files that aren't valid Rust with a warning, instead of failing the discovery
+    pub lenient: bool,
+    /// Store the items of each scanned file under `target/`, keyed by its content, so unchanged files aren't parsed again
+    pub parse_cache: bool,
+    /// Other docs merged into the annotated one
+    pub compose: Vec<ComposedDoc>,
+    /// Split the discovered items into a doc per module of the doc struct, see `split_hierarchy`
+    pub hierarchy: Option<Hierarchy>,
+    /// Compose the other docs of the scanned files into the annotated one, see `Discovered::take_sub_docs`
+    pub sub_docs: Option<Hierarchy>,
+    /// The maximum number of items of each kind a single derive gets, see `split_chunks`
+    pub chunk_size: Option<usize>,
+    /// The facade crate re-exporting the items of the other crates, see `Discovered::reroot`
+    pub reroot: Option<String>,
+    /// How the invocations of declarative macros register discovered items
+    pub macros: Vec<MacroTemplate>,
+    /// Module prefixes the discovered items must start with, every item is kept when empty
+    pub only: Vec<syn::Path>,
+    /// The expression the discovered items must match, see `Filter`
+    pub filter: Option<Filter>,
+    /// Handlers the discovery can't find, added to the discovered ones
+    pub extra_paths: Vec<syn::Path>,
+    /// Schemas the discovery can't find, added to the discovered ones
+    pub extra_schemas: Vec<syn::Path>,
+    /// Responses the discovery can't find, added to the discovered ones
+    pub extra_responses: Vec<syn::Path>,
+    /// The instantiations of the generic schemas to emit, e.g. `Paginated<User>`
+    pub generic_schemas: Vec<syn::Path>,
+    /// The annotated doc struct, to find the module it's declared in
+    pub doc_struct: Option<syn::Ident>,
+    /// Assumed values of the cfg predicates the discovery depends on, a missing predicate is assumed not to hold.
+    /// See `cfg_variants`
+    pub cfg_values: Vec<(String, bool)>,
+    /// The parameters of the other `#[utoipauto]` attributes of the doc struct, whose discovery is merged into
+    /// this one, see `take_repeated_attributes`
+    pub merged: Vec<Parameters>,
+}
+
+impl Parameters {
+    /// The parameters of a macro without attributes : the `UTOIPAUTO_*` environment variables, the `utoipauto.toml`
+    /// of the project, or the defaults
+    pub fn from_env() -> syn::Result<Parameters> {
+        extract_attributes(proc_macro2::TokenStream::new())
+    }
+
+    /// The parameters written as the attributes of the macro, e.g. `paths = "./src", prefer_reexports = true`
+    pub fn from_attributes(attributes: &str) -> syn::Result<Parameters> {
+        let stream = attributes
+            .parse()
+            .map_err(|_| diagnostics::error(format!("Invalid attributes \"{}\"", attributes)))?;
+        parse_attributes(stream)
+    }
+
+    /// Whether the findings of the class are reported. The classes of `fail_on` are always checked, the others
+    /// depend on the flag enabling them, if any
+    pub fn checks(&self, kind: WarningKind) -> bool {
+        if self.fail_on.contains(&kind) {
+            return true;
+        }
+        match kind {
+            WarningKind::UndescribedHandler => self.require_descriptions,
+            WarningKind::MissingResponseBody => self.require_response_bodies,
+            WarningKind::UndocumentedRoute => self.require_documented_routes,
+            WarningKind::UnnecessaryIgnore
+            | WarningKind::EmptyRoot
+            | WarningKind::UnreachableModule
+            | WarningKind::UnmatchedPath
+            | WarningKind::DuplicateSchema
+            | WarningKind::EmptyDiscovery => self.pedantic,
+            _ => true,
+        }
+    }
+
+    pub fn cfg_value(&self, predicate: &str) -> bool {
+        self.cfg_values.iter().any(|(cfg, value)| cfg == predicate && *value)
+    }
+}
+
+/// A template of `macros = ["endpoint! => path(0)"]` : the invocations of `endpoint!` register their first
+/// argument as a handler of the current module
+pub struct MacroTemplate {
+    /// The name of the macro, without the `!`
+    pub name: String,
+    pub kind: TemplateKind,
+    /// The index of the argument naming the item, from 0
+    pub argument: usize,
+}
+
+/// What an argument of a macro template names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TemplateKind {
+    /// A handler, as in `paths(...)`
+    Path,
+    Schema,
+    Response,
+}
+
+impl MacroTemplate {
+    fn from_attribute(value: &str) -> syn::Result<Self> {
+        let invalid = || {
+            diagnostics::error(format!(
+                "Invalid macros template \"{}\", expected \"MACRO! => path(N)\", \"MACRO! => schema(N)\" \
+                 or \"MACRO! => response(N)\"",
+                value
+            ))
+        };
+        let (name, item) = value.split_once("=>").ok_or_else(invalid)?;
+        let (kind, argument) = item.trim().trim_end_matches(')').split_once('(').ok_or_else(invalid)?;
+        let kind = match kind.trim() {
+            "path" => TemplateKind::Path,
+            "schema" => TemplateKind::Schema,
+            "response" => TemplateKind::Response,
+            _ => return Err(invalid()),
+        };
+        Ok(MacroTemplate {
+            name: name.trim().trim_end_matches('!').trim().to_string(),
+            kind,
+            argument: argument.trim().parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// A doc of `compose = ["crate_a::Doc", "/b => crate_b::Doc"]`
+#[derive(Clone)]
+pub struct ComposedDoc {
+    /// Nest the paths of the doc under this prefix, instead of merging them as is
+    pub nest: Option<String>,
+    pub doc: syn::Path,
+}
+
+impl ComposedDoc {
+    fn from_attribute(value: &str) -> syn::Result<Self> {
+        let (nest, doc) = match value.split_once("=>") {
+            Some((nest, doc)) => (Some(nest.trim().to_string()), doc.trim()),
+            None => (None, value.trim()),
+        };
+        Ok(ComposedDoc {
+            nest,
+            doc: syn::parse_str(doc).map_err(|_| diagnostics::error(format!("Invalid path \"{}\" in compose", doc)))?,
+        })
+    }
+}
+
+/// How the docs of `hierarchy = "merge"` are composed into the annotated one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hierarchy {
+    /// Merge the docs as is
+    Merge,
+    /// Nest the paths of each doc under the name of its module, e.g. `/users`
+    Nest,
+}
+
+impl Hierarchy {
+    fn from_attribute(value: &str) -> syn::Result<Self> {
+        match value {
+            "merge" => Ok(Hierarchy::Merge),
+            "nest" => Ok(Hierarchy::Nest),
+            _ => Err(diagnostics::error(format!(
+                "Unknown hierarchy \"{}\", expected one of \"merge\" or \"nest\"",
+                value
+            ))),
+        }
+    }
+}
+
+/// The web framework of `framework = "axum"`, setting the default handler attributes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framework {
+    Actix,
+    Axum,
+    Rocket,
+    Salvo,
+    Poem,
+}
+
+impl Framework {
+    fn from_attribute(value: &str) -> syn::Result<Self> {
+        match value {
+            "actix" => Ok(Framework::Actix),
+            "axum" => Ok(Framework::Axum),
+            "rocket" => Ok(Framework::Rocket),
+            "salvo" => Ok(Framework::Salvo),
+            "poem" => Ok(Framework::Poem),
+            _ => Err(diagnostics::error(format!(
+                "Unknown framework \"{}\", expected one of \"actix\", \"axum\", \"rocket\", \"salvo\" or \"poem\"",
+                value
+            ))),
+        }
+    }
+
+    /// The attributes of the handlers of the framework, see `Parameters::fn_attribute_names`
+    pub fn fn_attribute_names(self) -> Vec<String> {
+        let names: &[&str] = match self {
+            // The routing attributes of these frameworks don't document the handlers, `#[utoipa::path]` does
+            Framework::Actix | Framework::Axum | Framework::Rocket | Framework::Poem => &["utoipa"],
+            // `#[endpoint]` of salvo documents the handler by itself
+            Framework::Salvo => &["utoipa", "endpoint"],
+        };
+        names.iter().map(|name| name.to_string()).collect()
+    }
+}
+
+/// Order in which the discovered items are emitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Order {
+    /// Keep the order in which the items were discovered
+    Source,
+    /// Sort the items by their full path
+    Alphabetical,
+    /// Sort the items by module, keeping the source order inside a module
+    #[default]
+    Module,
+}
+
+impl Order {
+    fn from_attribute(value: &str) -> syn::Result<Self> {
+        match value {
+            "source" => Ok(Order::Source),
+            "alphabetical" => Ok(Order::Alphabetical),
+            "module" => Ok(Order::Module),
+            _ => Err(diagnostics::error(format!(
+                "Unknown order \"{}\", expected one of \"alphabetical\", \"source\" or \"module\"",
+                value
+            ))),
+        }
+    }
+}
+
+/// What the discovery does when the macros are expanded by rust-analyzer, on every edit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdeMode {
+    /// Discover the items as in a build
+    #[default]
+    Full,
+    /// Reuse the discovery made earlier in the process, even if the files changed since
+    Cached,
+    /// Skip the discovery, as in doc builds
+    Skip,
+}
+
+impl IdeMode {
+    fn from_attribute(value: &str) -> syn::Result<Self> {
+        match value {
+            "full" => Ok(IdeMode::Full),
+            "cached" => Ok(IdeMode::Cached),
+            "skip" => Ok(IdeMode::Skip),
+            _ => Err(diagnostics::error(format!(
+                "Unknown rust_analyzer mode \"{}\", expected one of \"full\", \"cached\" or \"skip\"",
+                value
+            ))),
+        }
+    }
+}
+
+/// How the schemas are discovered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SchemaSource {
+    /// Only from `#[derive(ToSchema)]`
+    Derives,
+    /// Only from `impl ToSchema for X`
+    Impls,
+    /// From both
+    #[default]
+    Both,
+}
+
+impl SchemaSource {
+    fn from_attribute(value: &str) -> syn::Result<Self> {
+        match value {
+            "derives" => Ok(SchemaSource::Derives),
+            "impls" => Ok(SchemaSource::Impls),
+            "both" => Ok(SchemaSource::Both),
+            _ => Err(diagnostics::error(format!(
+                "Unknown schema_source \"{}\", expected one of \"derives\", \"impls\" or \"both\"",
+                value
+            ))),
+        }
+    }
+
+    pub fn derives(self) -> bool {
+        self != SchemaSource::Impls
+    }
+
+    pub fn impls(self) -> bool {
+        self != SchemaSource::Derives
+    }
+}
+
+/// Where the manual implementations of `schema_attribute_name` and `response_attribute_name` go,
+/// e.g. `custom_impls = ["schemas => custom", "responses => none"]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomImpls {
+    pub schemas: ImplTarget,
+    pub responses: ImplTarget,
+}
+
+impl Default for CustomImpls {
+    fn default() -> Self {
+        CustomImpls {
+            schemas: ImplTarget::Schemas,
+            responses: ImplTarget::Responses,
+        }
+    }
+}
+
+/// A list of discovered items the manual implementations can go to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImplTarget {
+    Schemas,
+    Responses,
+    /// The dedicated `Discovered::custom_impls` list, only listed by `discovered!`
+    Custom,
+    /// Dropped
+    None,
+}
+
+fn parse_custom_impls(rules: &[String]) -> syn::Result<CustomImpls> {
+    let mut custom_impls = CustomImpls::default();
+    for rule in rules {
+        let invalid = || {
+            diagnostics::error(format!(
+                "Invalid custom_impls rule \"{}\", expected \"schemas => TARGET\" or \"responses => TARGET\" \
+                 with a TARGET among schemas, responses, custom and none",
+                rule
+            ))
+        };
+        let (source, target) = rule.split_once("=>").ok_or_else(invalid)?;
+        let target = match target.trim() {
+            "schemas" => ImplTarget::Schemas,
+            "responses" => ImplTarget::Responses,
+            "custom" => ImplTarget::Custom,
+            "none" => ImplTarget::None,
+            _ => return Err(invalid()),
+        };
+        match source.trim() {
+            "schemas" => custom_impls.schemas = target,
+            "responses" => custom_impls.responses = target,
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(custom_impls)
+}
+
+/// The `paths` of `workspace = true` : the written ones, followed by the crates of the workspace
+fn with_workspace_paths(paths: Option<String>) -> syn::Result<String> {
+    let current = std::env::var("CARGO_PKG_NAME").ok();
+    let workspace_paths = workspace_paths(".", current.as_deref())
+        .map_err(|e| diagnostics::error(format!("workspace = true : {}", e)))?;
+    Ok(paths.into_iter().chain(workspace_paths).collect::<Vec<_>>().join(", "))
+}
+
+/// The `paths` without the attribute, and the files they exclude, see `target_paths`. `./src` if the package can't
+/// be read
+fn default_paths() -> (String, Vec<String>) {
+    let (package_dir, prefix) = package_location();
+    let Some(targets) = package_targets(&package_dir) else {
+        return ("./src".to_string(), vec![]);
+    };
+    let bin = std::env::var("CARGO_BIN_NAME").ok();
+    let crate_name = std::env::var("CARGO_CRATE_NAME").ok();
+    let target = match (&bin, &crate_name, &targets.lib) {
+        (Some(bin), _, _) => Target::Bin(bin),
+        // A test or an example uses the library by its name
+        (None, Some(crate_name), Some((lib_name, _))) if crate_name != lib_name => Target::Other,
+        _ => Target::Lib,
+    };
+    let (paths, excludes) = target_paths(&targets, &prefix, target);
+    (paths.join(", "), excludes)
+}
+
+/// The directory of the package being compiled, and the prefix of the paths to it. The paths are relative to the
+/// working directory of the compiler, the root of the workspace for its members
+pub(crate) fn package_location() -> (std::path::PathBuf, String) {
+    let Some(package_dir) = std::env::var_os("CARGO_MANIFEST_DIR").map(std::path::PathBuf::from) else {
+        return (".".into(), ".".to_string());
+    };
+    let prefix = match std::env::current_dir() {
+        Ok(current_dir) => match package_dir.strip_prefix(&current_dir) {
+            Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+            Ok(relative) => format!("./{}", relative.to_string_lossy().replace('\\', "/")),
+            Err(_) => package_dir.to_string_lossy().to_string(),
+        },
+        Err(_) => ".".to_string(),
+    };
+    (package_dir, prefix)
+}
+
+/// The paths of `include_dirs`, e.g. `["tests", "examples"]` : the file of the test or the example being compiled,
+/// named `crate_name`, or its directory for a `main.rs`, as `crate`. The other ones are crates of their own
+fn include_dir_paths(
+    package_dir: &std::path::Path,
+    prefix: &str,
+    dirs: &[String],
+    crate_name: Option<&str>,
+) -> Vec<String> {
+    let Some(crate_name) = crate_name else {
+        return vec![];
+    };
+    let mut paths = vec![];
+    for dir in dirs {
+        let dir = dir.trim_start_matches("./").trim_end_matches('/');
+        let Ok(entries) = std::fs::read_dir(package_dir.join(dir)) else {
+            continue;
+        };
+        let mut entries: Vec<std::path::PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+        entries.sort();
+        for entry in entries {
+            let Some(name) = entry.file_stem().map(|name| name.to_string_lossy().to_string()) else {
+                continue;
+            };
+            let is_root = match entry.is_dir() {
+                true => entry.join("main.rs").is_file(),
+                false => entry.extension().is_some_and(|extension| extension == "rs"),
+            };
+            if is_root && name.replace('-', "_") == crate_name {
+                let file_name = entry.file_name().unwrap_or_default().to_string_lossy().to_string();
+                paths.push(format!("{}/{}/{} in crate", prefix, dir, file_name));
+            }
+        }
+    }
+    paths
+}
+
+/// The kind of the crate being compiled
+#[derive(Clone, Copy)]
+enum Target<'a> {
+    Lib,
+    Bin(&'a str),
+    /// A test or an example
+    Other,
+}
+
+/// The default `paths` of the crate of the package being compiled, and the files of its other crates they exclude.
+///
+/// The directory of the root file of the crate is scanned as `crate`, or the root file alone for a binary which isn't
+/// a `main.rs`. A binary out of the directory of the library also scans the library, with its name, and so does a
+/// test or an example, see `include_dir_paths` for its own file.
+fn target_paths(targets: &PackageTargets, prefix: &str, target: Target) -> (Vec<String>, Vec<String>) {
+    use std::path::{Path, PathBuf};
+    let path = |path: &Path| format!("{}/{}", prefix, path.to_string_lossy().replace('\\', "/"));
+    // Not to scan the whole package, e.g. its `target`, for a root file at its root
+    let parent = |root: &PathBuf| match root.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => root.clone(),
+    };
+    // A `src` directory is laid out as expected, the others are the root of the crate
+    let root_path = |scanned: &PathBuf, crate_name: &str| match (scanned.ends_with("src"), crate_name) {
+        (true, "crate") => path(scanned),
+        (true, crate_name) => format!("{} from {}", path(scanned), crate_name),
+        (false, crate_name) => format!("{} in {}", path(scanned), crate_name),
+    };
+    // The scanned part of a binary : the directory of a `main.rs`, otherwise the root file
+    let bin_scan = |root: &PathBuf| match root.file_name().is_some_and(|name| name == "main.rs") {
+        true => parent(root),
+        false => root.clone(),
+    };
+    let lib_dir = targets.lib.as_ref().map(|(_, root)| parent(root));
+    let bin = match target {
+        Target::Bin(bin) => targets.bins.iter().find(|(name, _)| name == bin),
+        _ => None,
+    };
+    let lib_crate = match (target, &targets.lib) {
+        (Target::Other, Some((lib_name, _))) => lib_name.as_str(),
+        _ => "crate",
+    };
+    let mut scanned = vec![];
+    match (bin, &lib_dir) {
+        (Some((_, root)), Some(lib_dir)) if bin_scan(root) == *lib_dir => scanned.push((lib_dir.clone(), "crate")),
+        (Some((_, root)), lib_dir) => {
+            scanned.push((bin_scan(root), "crate"));
+            // Out of the binary, and not the parent of its root, which would be excluded
+            if let Some(((lib_name, _), lib_dir)) = targets.lib.as_ref().zip(lib_dir.as_ref()) {
+                if !root.starts_with(lib_dir) && !lib_dir.starts_with(bin_scan(root)) {
+                    scanned.push((lib_dir.clone(), lib_name.as_str()));
+                }
+            }
+        }
+        (None, Some(lib_dir)) => scanned.push((lib_dir.clone(), lib_crate)),
+        (None, None) => match targets.bins.first() {
+            Some((_, root)) => scanned.push((bin_scan(root), "crate")),
+            None => return (vec![format!("{}/src", prefix)], vec![]),
+        },
+    }
+    let own_root = match (bin, &targets.lib) {
+        (Some((_, root)), _) => Some(root),
+        (None, None) => targets.bins.first().map(|(_, root)| root),
+        (None, Some(_)) => None,
+    };
+    // The other binaries under the scanned directories, the whole directory of a `main.rs` in a subdirectory
+    let excludes = targets
+        .bins
+        .iter()
+        .map(|(_, root)| root)
+        .filter(|root| Some(*root) != own_root)
+        .filter_map(|root| {
+            let (dir, _) = scanned.iter().find(|(dir, _)| root.starts_with(dir))?;
+            Some(match bin_scan(root) {
+                excluded if excluded != *dir && excluded.starts_with(dir) => path(&excluded),
+                _ => path(root),
+            })
+        })
+        .collect();
+    let paths = scanned
+        .iter()
+        .map(|(scanned, crate_name)| root_path(scanned, crate_name))
+        .collect();
+    (paths, excludes)
+}
+
+/// The classes failing the build with `strict = true` : nothing discovered at all, or by one of the paths
+const STRICT_FAIL_ON: &[WarningKind] = &[
+    WarningKind::EmptyDiscovery,
+    WarningKind::EmptyRoot,
+    WarningKind::UnmatchedPath,
+];
+
+fn parse_fail_on(names: &[String], strict: bool) -> syn::Result<Vec<WarningKind>> {
+    let mut fail_on: Vec<WarningKind> = names
+        .iter()
+        .map(|name| {
+            WarningKind::from_name(name).ok_or_else(|| {
+                let known: Vec<&str> = WarningKind::ALL.iter().map(|kind| kind.name()).collect();
+                diagnostics::error(format!(
+                    "Unknown fail_on \"{}\", expected one of {}",
+                    name,
+                    known.join(", ")
+                ))
+            })
+        })
+        .collect::<syn::Result<_>>()?;
+    if strict {
+        for kind in STRICT_FAIL_ON {
+            if !fail_on.contains(kind) {
+                fail_on.push(*kind);
+            }
+        }
+    }
+    Ok(fail_on)
+}
+
+/// The attributes of the macro, read by `extract_attributes`
+const ATTRIBUTES: &[&str] = &[
+    "paths",
+    "workspace",
+    "function_attribute_name",
+    "function_attributes",
+    "framework",
+    "schema_attribute_name",
+    "response_attribute_name",
+    "schema_derives",
+    "utoipa_crate",
+    "response_derives",
+    "schema_source",
+    "custom_impls",
+    "qualified_impls",
+    "order",
+    "path_transform",
+    "components_into",
+    "components_from",
+    "module_mapping",
+    "prefer_reexports",
+    "flatten",
+    "pedantic",
+    "require_descriptions",
+    "document_generated",
+    "scan_docs",
+    "rust_analyzer",
+    "require_response_bodies",
+    "require_documented_routes",
+    "route_attributes",
+    "previous_spec",
+    "deny_removed_endpoints",
+    "emit_to",
+    "fail_on",
+    "strict",
+    "only",
+    "modules",
+    "filter",
+    "extra_paths",
+    "extra_schemas",
+    "extra_responses",
+    "generic_schemas",
+    "extensions",
+    "exclude",
+    "include_tests",
+    "include_dirs",
+    "respect_gitignore",
+    "lenient",
+    "parse_cache",
+    "max_item_depth",
+    "max_files",
+    "module_files",
+    "compose",
+    "hierarchy",
+    "sub_docs",
+    "chunk_size",
+    "macros",
+    "reroot",
+];
+
+/// The shorter names of some attributes, as `(alias, attribute)`
+const ALIASES: &[(&str, &str)] = &[
+    ("function_attribute", "function_attribute_name"),
+    ("schema_derive", "schema_attribute_name"),
+    ("response_derive", "response_attribute_name"),
+];
+
+/// Check and read the attributes of the macro, see `normalize_attributes`
+pub fn parse_attributes(stream: proc_macro2::TokenStream) -> syn::Result<Parameters> {
+    extract_attributes(normalize_attributes(stream)?)
+}
+
+/// Check the names of the attributes, and rewrite their structured forms into the `name = value` ones read by
+/// `extract_attributes` : the `ALIASES`, and `paths(["./src", "./other/src" as other_crate])` for
+/// `paths = "./src, ./other/src from other_crate"`.
+///
+/// The errors point at the faulty attribute, e.g. a misspelled name.
+pub fn normalize_attributes(stream: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let mut normalized = vec![];
+    for attribute in split_attributes(stream) {
+        let mut tokens = attribute.into_iter();
+        let ident = match tokens.next() {
+            Some(TokenTree::Ident(ident)) => ident,
+            Some(token) => {
+                return Err(syn::Error::new(
+                    token.span(),
+                    "utoipauto: Expected the name of an attribute",
+                ))
+            }
+            // A trailing coma
+            None => continue,
+        };
+        let name = ident.to_string();
+        let name = match ALIASES.iter().find(|(alias, _)| *alias == name) {
+            Some((_, attribute)) => attribute.to_string(),
+            None if ATTRIBUTES.contains(&name.as_str()) => name,
+            None => return Err(unknown_attribute(&ident)),
+        };
+        let name = proc_macro2::Ident::new(&name, ident.span());
+        let value: Vec<TokenTree> = tokens.collect();
+        match value.as_slice() {
+            [TokenTree::Punct(punct), _, ..] if punct.as_char() == '=' => {
+                let value = &value[1..];
+                normalized.push(quote!(#name = #(#value)*));
+            }
+            [TokenTree::Group(group)] if name == "paths" && group.delimiter() == Delimiter::Parenthesis => {
+                let paths = Literal::string(&parse_structured_paths(group.stream())?);
+                normalized.push(quote!(#name = #paths));
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &ident,
+                    format!("utoipauto: Expected `{} = ...`", ident),
+                ))
+            }
+        }
+    }
+    Ok(quote!(#(#normalized),*))
+}
+
+/// The tokens of each attribute, separated by the comas outside of any group
+fn split_attributes(stream: proc_macro2::TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut attributes = vec![vec![]];
+    for token in stream {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => attributes.push(vec![]),
+            _ => attributes.last_mut().unwrap().push(token),
+        }
+    }
+    attributes
+}
+
+fn unknown_attribute(ident: &proc_macro2::Ident) -> syn::Error {
+    let name = ident.to_string();
+    let closest = ATTRIBUTES
+        .iter()
+        .chain(ALIASES.iter().map(|(alias, _)| alias))
+        .map(|attribute| (edit_distance(&name, attribute), attribute))
+        .min();
+    let message = match closest {
+        Some((distance, attribute)) if distance <= 3 => {
+            format!("utoipauto: Unknown attribute `{}`, did you mean `{}`?", name, attribute)
+        }
+        _ => format!("utoipauto: Unknown attribute `{}`", name),
+    };
+    syn::Error::new(ident.span(), message)
+}
+
+/// The number of characters to insert, remove or replace to go from one string to the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(a != *b);
+            current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// A path of `paths([...])`, with the crate it belongs to, e.g. `"./other/src" as other_crate`
+struct StructuredPath {
+    path: syn::LitStr,
+    crate_name: Option<syn::Ident>,
+}
+
+impl syn::parse::Parse for StructuredPath {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let crate_name = match input.parse::<Option<syn::Token![as]>>()? {
+            Some(_) => Some(input.parse()?),
+            None => None,
+        };
+        Ok(StructuredPath { path, crate_name })
+    }
+}
+
+/// The `paths` string of `paths(["./src", "./other/src" as other_crate])`, the brackets being optional
+fn parse_structured_paths(stream: proc_macro2::TokenStream) -> syn::Result<String> {
+    let mut tokens = stream.clone().into_iter();
+    let stream = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Group(group)), None) if group.delimiter() == Delimiter::Bracket => group.stream(),
+        _ => stream,
+    };
+    let paths = Punctuated::<StructuredPath, syn::Token![,]>::parse_terminated.parse2(stream)?;
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|path| match &path.crate_name {
+            Some(crate_name) => format!("{} from {}", path.path.value(), crate_name),
+            None => path.path.value(),
+        })
+        .collect();
+    Ok(paths.join(", "))
 }
 
 /// Extract the paths string attribute from the proc_macro::TokenStream
 ///
 /// If none is specified, we use the default path "./src"
-pub fn extract_attributes(stream: proc_macro2::TokenStream) -> Parameters {
-    let paths = extract_attribute("paths", stream.clone());
-    let fn_attribute_name = extract_attribute("function_attribute_name", stream.clone());
-    let schema_attribute_name = extract_attribute("schema_attribute_name", stream.clone());
-    let response_attribute_name = extract_attribute("response_attribute_name", stream);
-    // if no paths specified, we use the default path "./src"
-    Parameters {
-        paths: paths.unwrap_or("./src".to_string()),
-        fn_attribute_name: fn_attribute_name.unwrap_or("utoipa".to_string()),
+pub fn extract_attributes(stream: proc_macro2::TokenStream) -> syn::Result<Parameters> {
+    // The attributes missing from the macro default to the `UTOIPAUTO_*` environment variables, then to the
+    // `utoipauto.toml` of the project
+    let config = ProjectConfig::load()?;
+    let config = config.as_ref();
+    let string = |name: &str| {
+        extract_attribute(name, stream.clone())
+            .or_else(|| env_default(name))
+            .or_else(|| config.and_then(|config| config.string(name)))
+    };
+    let flag = |name: &str| -> syn::Result<Option<bool>> {
+        if let Some(value) = extract_bool_attribute(name, stream.clone())? {
+            return Ok(Some(value));
+        }
+        if let Some(value) = env_bool(name)? {
+            return Ok(Some(value));
+        }
+        config.map_or(Ok(None), |config| config.flag(name))
+    };
+    let int = |name: &str| -> syn::Result<Option<usize>> {
+        if let Some(value) = extract_int_attribute(name, stream.clone())? {
+            return Ok(Some(value));
+        }
+        if let Some(value) = env_int(name)? {
+            return Ok(Some(value));
+        }
+        config.map_or(Ok(None), |config| config.int(name))
+    };
+    let list = |name: &str| -> syn::Result<Option<Vec<String>>> {
+        Ok(extract_list_attribute(name, stream.clone())?
+            .or_else(|| env_list(name))
+            .or_else(|| config.and_then(|config| config.list(name))))
+    };
+    let paths = string("paths");
+    let workspace = flag("workspace")?;
+    let include_dirs = list("include_dirs")?;
+    let fn_attribute_name = string("function_attribute_name");
+    let fn_attributes = list("function_attributes")?;
+    let framework = string("framework");
+    let schema_attribute_name = string("schema_attribute_name");
+    let response_attribute_name = string("response_attribute_name");
+    let schema_derives = list("schema_derives")?;
+    let utoipa_crate: Vec<String> = string("utoipa_crate")
+        .unwrap_or("utoipa".to_string())
+        .split("::")
+        .map(|segment| segment.trim().to_string())
+        .collect();
+    if utoipa_crate
+        .iter()
+        .any(|segment| syn::parse_str::<syn::Ident>(segment).is_err())
+    {
+        return Err(diagnostics::error(format!(
+            "Invalid utoipa_crate \"{}\", expected a path",
+            utoipa_crate.join("::")
+        )));
+    }
+    let response_derives = list("response_derives")?;
+    let schema_source = string("schema_source");
+    let custom_impls = list("custom_impls")?;
+    let qualified_impls = flag("qualified_impls")?;
+    let order = string("order");
+    let path_transform = string("path_transform");
+    let components_into = string("components_into");
+    let components_from = string("components_from");
+    let module_mapping = string("module_mapping");
+    let prefer_reexports = flag("prefer_reexports")?;
+    let flatten = flag("flatten")?;
+    let pedantic = flag("pedantic")?;
+    let require_descriptions = flag("require_descriptions")?;
+    let document_generated = flag("document_generated")?;
+    let scan_docs = flag("scan_docs")?;
+    let rust_analyzer = string("rust_analyzer");
+    let require_response_bodies = flag("require_response_bodies")?;
+    let require_documented_routes = flag("require_documented_routes")?;
+    let route_attributes = list("route_attributes")?;
+    let previous_spec = string("previous_spec");
+    let deny_removed_endpoints = flag("deny_removed_endpoints")?;
+    let emit_to = string("emit_to");
+    let fail_on = list("fail_on")?;
+    let strict = flag("strict")?;
+    let only = list("only")?;
+    let modules = string("modules");
+    let filter = string("filter");
+    let extra_paths = list("extra_paths")?;
+    let extra_schemas = list("extra_schemas")?;
+    let extra_responses = list("extra_responses")?;
+    let generic_schemas = list("generic_schemas")?;
+    let extensions = list("extensions")?;
+    let exclude = string("exclude");
+    let include_tests = flag("include_tests")?;
+    let respect_gitignore = flag("respect_gitignore")?;
+    let lenient = flag("lenient")?;
+    let parse_cache = flag("parse_cache")?;
+    let max_item_depth = int("max_item_depth")?;
+    let max_files = int("max_files")?;
+    let module_files = list("module_files")?;
+    let compose = list("compose")?;
+    let hierarchy = string("hierarchy");
+    let sub_docs = string("sub_docs");
+    let chunk_size = int("chunk_size")?;
+    let macros = list("macros")?;
+    let reroot = string("reroot");
+    // Without paths, the crates of the package are read from its manifest
+    let (paths, target_excludes) = match (workspace, paths) {
+        (Some(true), paths) => (with_workspace_paths(paths)?, vec![]),
+        (_, Some(paths)) => (paths, vec![]),
+        (_, None) => default_paths(),
+    };
+    let paths = match include_dirs {
+        Some(dirs) => {
+            let (package_dir, prefix) = package_location();
+            let crate_name = std::env::var("CARGO_CRATE_NAME").ok();
+            std::iter::once(paths)
+                .chain(include_dir_paths(&package_dir, &prefix, &dirs, crate_name.as_deref()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+        None => paths,
+    };
+    let framework = framework
+        .map(|framework| Framework::from_attribute(&framework))
+        .transpose()?;
+    let module_files = module_files
+        .unwrap_or_default()
+        .iter()
+        .map(|rule| match rule.split_once("=>") {
+            Some((module, file)) => Ok((module.trim().replace(' ', ""), file.trim().to_string())),
+            None => Err(diagnostics::error(format!(
+                "Invalid module_files rule \"{}\", expected \"MODULE => FILE\"",
+                rule
+            ))),
+        })
+        .collect::<syn::Result<_>>()?;
+    Ok(Parameters {
+        paths,
+        // The names written explicitly replace the ones of the framework, `#[utoipa::path]` of which is written with
+        // the name of the utoipa crate
+        fn_attribute_names: match (fn_attribute_name, fn_attributes) {
+            (None, None) => framework
+                .map(|framework| framework.fn_attribute_names())
+                .unwrap_or_else(|| vec!["utoipa".to_string()])
+                .into_iter()
+                .map(|name| match name == "utoipa" {
+                    true => utoipa_crate.last().cloned().unwrap_or(name),
+                    false => name,
+                })
+                .collect(),
+            (name, names) => name.into_iter().chain(names.unwrap_or_default()).collect(),
+        },
         schema_attribute_name: schema_attribute_name.unwrap_or("ToSchema".to_string()),
         response_attribute_name: response_attribute_name.unwrap_or("ToResponse".to_string()),
+        schema_derives: schema_derives.unwrap_or_default(),
+        utoipa_crate,
+        response_derives: response_derives.unwrap_or_default(),
+        schema_source: schema_source
+            .map(|source| SchemaSource::from_attribute(&source))
+            .transpose()?
+            .unwrap_or_default(),
+        custom_impls: custom_impls
+            .map(|rules| parse_custom_impls(&rules))
+            .transpose()?
+            .unwrap_or_default(),
+        qualified_impls: qualified_impls.unwrap_or(false),
+        order: order
+            .map(|order| Order::from_attribute(&order))
+            .transpose()?
+            .unwrap_or_default(),
+        path_transform: path_transform.map(|rules| parse_path_transform(&rules)).transpose()?,
+        components_into: components_into
+            .map(|path| {
+                syn::parse_str(&path)
+                    .map_err(|_| diagnostics::error(format!("Invalid path \"{}\" in components_into", path)))
+            })
+            .transpose()?,
+        components_from,
+        module_mapping: module_mapping
+            .map(|rules| parse_module_mapping(&rules))
+            .transpose()?
+            .unwrap_or_default(),
+        prefer_reexports: prefer_reexports.unwrap_or(false),
+        flatten: flatten.unwrap_or(false),
+        pedantic: pedantic.unwrap_or(false),
+        require_descriptions: require_descriptions.unwrap_or(false),
+        document_generated: document_generated.unwrap_or(false),
+        scan_docs: scan_docs.unwrap_or(false),
+        rust_analyzer: rust_analyzer
+            .map(|mode| IdeMode::from_attribute(&mode))
+            .transpose()?
+            .unwrap_or_default(),
+        require_response_bodies: require_response_bodies.unwrap_or(false),
+        require_documented_routes: require_documented_routes.unwrap_or(false),
+        route_attributes: route_attributes
+            .unwrap_or_else(|| DEFAULT_ROUTE_ATTRIBUTES.iter().map(|name| name.to_string()).collect()),
+        previous_spec,
+        deny_removed_endpoints: deny_removed_endpoints.unwrap_or(false),
+        emit_to,
+        fail_on: parse_fail_on(&fail_on.unwrap_or_default(), strict.unwrap_or(false))?,
+        max_item_depth,
+        max_files: max_files.unwrap_or(DEFAULT_MAX_FILES),
+        module_files,
+        compose: compose
+            .unwrap_or_default()
+            .iter()
+            .map(|doc| ComposedDoc::from_attribute(doc))
+            .collect::<syn::Result<_>>()?,
+        hierarchy: hierarchy
+            .map(|hierarchy| Hierarchy::from_attribute(&hierarchy))
+            .transpose()?,
+        sub_docs: sub_docs
+            .map(|sub_docs| Hierarchy::from_attribute(&sub_docs))
+            .transpose()?,
+        chunk_size: match chunk_size {
+            Some(0) => return Err(diagnostics::error("chunk_size must be at least 1")),
+            chunk_size => chunk_size,
+        },
+        reroot,
+        macros: macros
+            .unwrap_or_default()
+            .iter()
+            .map(|template| MacroTemplate::from_attribute(template))
+            .collect::<syn::Result<_>>()?,
+        extensions: extensions
+            .map(|extensions| {
+                extensions
+                    .into_iter()
+                    .map(|extension| extension.trim_start_matches('.').to_string())
+                    .collect()
+            })
+            .unwrap_or_else(|| vec!["rs".to_string()]),
+        exclude: exclude
+            .map(|exclude| split_alternatives(&exclude))
+            .unwrap_or_default()
+            .into_iter()
+            .chain(target_excludes)
+            .collect(),
+        include_tests: include_tests.unwrap_or(false),
+        respect_gitignore: respect_gitignore.unwrap_or(false),
+        lenient: lenient.unwrap_or(false),
+        parse_cache: parse_cache.unwrap_or(false),
+        only: parse_path_list("only", only)?
+            .into_iter()
+            .chain(parse_path_list(
+                "modules",
+                modules.map(|modules| split_alternatives(&modules)),
+            )?)
+            .collect(),
+        filter: filter
+            .map(|filter| {
+                Filter::parse(&filter).map_err(|e| diagnostics::error(format!("Invalid filter \"{}\": {}", filter, e)))
+            })
+            .transpose()?,
+        extra_paths: parse_path_list("extra_paths", extra_paths)?,
+        extra_schemas: parse_path_list("extra_schemas", extra_schemas)?,
+        extra_responses: parse_path_list("extra_responses", extra_responses)?,
+        generic_schemas: parse_path_list("generic_schemas", generic_schemas)?,
+        doc_struct: None,
+        cfg_values: vec![],
+        merged: vec![],
+    })
+}
+
+/// Default maximum number of files of a path
+const DEFAULT_MAX_FILES: usize = 5000;
+
+/// The routing attributes of actix and rocket, e.g. `#[get("/users")]` or `#[actix_web::route(...)]`
+const DEFAULT_ROUTE_ATTRIBUTES: &[&str] = &[
+    "get", "post", "put", "delete", "patch", "head", "options", "trace", "connect", "route", "routes",
+];
+
+/// Maximum number of cfg predicates in a discovery, each of them doubles the number of variants
+const MAX_CFGS: usize = 6;
+
+/// The values of the cfg predicates in a variant of the doc, with the predicate selecting it
+pub type CfgVariant = (Vec<(String, bool)>, proc_macro2::TokenStream);
+
+/// Every combination of values of the cfg predicates, with the predicate selecting it,
+/// e.g. `all(feature = "a", not(feature = "b"))`
+///
+/// A proc macro can't evaluate the cfg of the crate it expands in,
+/// so a variant of the doc is emitted for each combination instead.
+pub fn cfg_variants(cfgs: &[String]) -> syn::Result<Vec<CfgVariant>> {
+    if cfgs.len() > MAX_CFGS {
+        return Err(diagnostics::error(format!(
+            "Discovery depends on {} cfg predicates, at most {} are supported",
+            cfgs.len(),
+            MAX_CFGS
+        )));
     }
+    let predicates: Vec<proc_macro2::TokenStream> = cfgs
+        .iter()
+        .map(|cfg| {
+            cfg.parse()
+                .map_err(|_| diagnostics::error(format!("Invalid cfg predicate \"{}\"", cfg)))
+        })
+        .collect::<syn::Result<_>>()?;
+    Ok((0..1usize << cfgs.len())
+        .map(|combination| {
+            let values: Vec<(String, bool)> = cfgs
+                .iter()
+                .enumerate()
+                .map(|(i, cfg)| (cfg.clone(), combination & (1 << i) != 0))
+                .collect();
+            let predicates = values.iter().zip(&predicates).map(|((_, value), cfg)| match value {
+                true => quote!(#cfg),
+                false => quote!(not(#cfg)),
+            });
+            let predicate = quote!(all(#(#predicates),*));
+            (values, predicate)
+        })
+        .collect())
+}
+
+/// Build the module mapping from rules separated by a coma :
+/// `"handlers_v1 => v1"` maps the `handlers_v1` directory or file to the `v1` module,
+/// `"routes =>"` ignores the `routes` directory level.
+fn parse_module_mapping(rules: &str) -> syn::Result<ModuleMapping> {
+    let mut mapping = ModuleMapping::default();
+    for rule in rules.split(',').filter(|rule| !rule.trim().is_empty()) {
+        match rule.split_once("=>") {
+            Some((segment, module)) if module.trim().is_empty() => mapping.strip.push(segment.trim().to_string()),
+            Some((segment, module)) => mapping
+                .replace
+                .push((segment.trim().to_string(), module.trim().to_string())),
+            None => {
+                return Err(diagnostics::error(format!(
+                    "Invalid module_mapping rule \"{}\", expected \"SEGMENT => MODULE\" or \"SEGMENT =>\"",
+                    rule.trim()
+                )))
+            }
+        }
+    }
+    Ok(mapping)
+}
+
+/// Build a path transformation from rules separated by a coma :
+/// `"crate::old => crate::new"` replaces the `crate::old` prefix by `crate::new`,
+/// `"crate::internal =>"` strips the `crate::internal` prefix.
+///
+/// The first matching rule is applied.
+fn parse_path_transform(rules: &str) -> syn::Result<PathTransform> {
+    let parse_path = |path: &str| {
+        syn::parse_str::<syn::Path>(path.trim())
+            .map_err(|_| diagnostics::error(format!("Invalid path \"{}\" in path_transform", path.trim())))
+    };
+    let rules: Vec<(syn::Path, Option<syn::Path>)> = rules
+        .split(',')
+        .filter(|rule| !rule.trim().is_empty())
+        .map(|rule| match rule.split_once("=>") {
+            Some((prefix, replacement)) if replacement.trim().is_empty() => Ok((parse_path(prefix)?, None)),
+            Some((prefix, replacement)) => Ok((parse_path(prefix)?, Some(parse_path(replacement)?))),
+            None => Err(diagnostics::error(format!(
+                "Invalid path_transform rule \"{}\", expected \"PREFIX => REPLACEMENT\" or \"PREFIX =>\"",
+                rule.trim()
+            ))),
+        })
+        .collect::<syn::Result<_>>()?;
+
+    Ok(Box::new(move |path| {
+        rules
+            .iter()
+            .find_map(|(prefix, replacement)| match replacement {
+                Some(replacement) => replace_prefix(&path, prefix, replacement),
+                None => strip_prefix(&path, prefix),
+            })
+            .unwrap_or(path)
+    }))
 }
 
 // extract the name = "" attributes from the proc_macro::TokenStream
@@ -46,35 +1210,381 @@ fn extract_attribute(name: &str, stream: proc_macro2::TokenStream) -> Option<Str
     None
 }
 
+/// Whether the macros are expanded to build documentation, on docs.rs or by rustdoc with `cargo doc`.
+///
+/// The layout of the sources may not be the expected one there, e.g. on docs.rs, so the discovery is skipped
+/// unless `scan_docs` is set : the doc structs compile, with only the items listed by hand.
+pub fn is_doc_build() -> bool {
+    std::env::var_os("DOCS_RS").is_some() || std::env::current_exe().is_ok_and(|exe| is_rustdoc(&exe))
+}
+
+/// Proc macros run in the process of the compiler, which is rustdoc for doc builds
+fn is_rustdoc(exe: &std::path::Path) -> bool {
+    exe.file_stem()
+        .is_some_and(|name| name.to_string_lossy().starts_with("rustdoc"))
+}
+
+/// Check if the macros are expanded by rust-analyzer.
+///
+/// It expands them in its proc macro server, which it starts with `RUST_ANALYZER_INTERNALS_DO_NOT_USE` set, and on
+/// every edit : `rust_analyzer = "cached"` or `"skip"` keep the discovery from slowing down the completions.
+pub fn is_rust_analyzer() -> bool {
+    std::env::var_os("RUST_ANALYZER_INTERNALS_DO_NOT_USE").is_some()
+        || std::env::current_exe().is_ok_and(|exe| is_proc_macro_server(&exe))
+}
+
+fn is_proc_macro_server(exe: &std::path::Path) -> bool {
+    exe.file_stem()
+        .is_some_and(|name| name.to_string_lossy().contains("proc-macro-srv"))
+}
+
+/// Check if the discovery is skipped : in doc builds unless `scan_docs` is set, see `is_doc_build`, and under
+/// rust-analyzer with `rust_analyzer = "skip"`
+pub fn skips_discovery(params: &Parameters) -> bool {
+    (is_doc_build() && !params.scan_docs) || (params.rust_analyzer == IdeMode::Skip && is_rust_analyzer())
+}
+
+/// The defaults of the attributes written in a `utoipauto.toml`, with the names and values of the macro, e.g.
+/// `paths = ["./src", "./generated"]` or `pedantic = true`
+struct ProjectConfig {
+    path: std::path::PathBuf,
+    manifest: Manifest,
+}
+
+impl ProjectConfig {
+    const FILE_NAME: &'static str = "utoipauto.toml";
+
+    /// The config of the crate being compiled, or of the closest of its parent directories, e.g. the workspace root
+    fn load() -> syn::Result<Option<ProjectConfig>> {
+        let Some(dir) = std::env::var_os("CARGO_MANIFEST_DIR")
+            .map(std::path::PathBuf::from)
+            .or_else(|| std::env::current_dir().ok())
+        else {
+            return Ok(None);
+        };
+        ProjectConfig::find(&dir)
+    }
+
+    fn find(dir: &std::path::Path) -> syn::Result<Option<ProjectConfig>> {
+        let Some(path) = dir
+            .ancestors()
+            .map(|dir| dir.join(ProjectConfig::FILE_NAME))
+            .find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+        let manifest = Manifest::from_path(&path)
+            .map_err(|e| diagnostics::error(format!("Can't read `{}`: {}", path.display(), e)))?;
+        if let Some(key) = manifest.keys("").into_iter().find(|key| !ATTRIBUTES.contains(key)) {
+            return Err(diagnostics::error(format!(
+                "Unknown attribute `{}` in `{}`",
+                key,
+                path.display()
+            )));
+        }
+        Ok(Some(ProjectConfig { path, manifest }))
+    }
+
+    /// An array is joined like the paths of the macro, e.g. `"./src, ./generated"`
+    fn string(&self, name: &str) -> Option<String> {
+        match self.manifest.get("", name)? {
+            Value::String(value) | Value::Other(value) => Some(value.clone()),
+            Value::Array(values) => Some(values.join(", ")),
+        }
+    }
+
+    fn flag(&self, name: &str) -> syn::Result<Option<bool>> {
+        self.string(name)
+            .map(|value| match value.as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(diagnostics::error(format!(
+                    "Expected {} = true or {} = false in `{}`",
+                    name,
+                    name,
+                    self.path.display()
+                ))),
+            })
+            .transpose()
+    }
+
+    fn int(&self, name: &str) -> syn::Result<Option<usize>> {
+        self.string(name)
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    diagnostics::error(format!("Expected {} = <integer> in `{}`", name, self.path.display()))
+                })
+            })
+            .transpose()
+    }
+
+    /// A string is a list separated by comas, like the environment variables
+    fn list(&self, name: &str) -> Option<Vec<String>> {
+        match self.manifest.get("", name)? {
+            Value::Array(values) => Some(values.clone()),
+            Value::String(value) | Value::Other(value) => Some(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|element| !element.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// The default of an attribute from the environment, e.g. `UTOIPAUTO_FUNCTION_ATTRIBUTE_NAME` for `function_attribute_name`
+fn env_default(name: &str) -> Option<String> {
+    std::env::var(format!("UTOIPAUTO_{}", name.to_uppercase()))
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+fn env_bool(name: &str) -> syn::Result<Option<bool>> {
+    env_default(name)
+        .map(|value| match value.trim() {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            _ => Err(diagnostics::error(format!(
+                "Expected UTOIPAUTO_{}=true or UTOIPAUTO_{}=false",
+                name.to_uppercase(),
+                name.to_uppercase()
+            ))),
+        })
+        .transpose()
+}
+
+fn env_int(name: &str) -> syn::Result<Option<usize>> {
+    env_default(name)
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| diagnostics::error(format!("Expected UTOIPAUTO_{}=<integer>", name.to_uppercase())))
+        })
+        .transpose()
+}
+
+/// The elements of a list are separated by comas, e.g. `UTOIPAUTO_EXTENSIONS=rs,rs.in`
+fn env_list(name: &str) -> Option<Vec<String>> {
+    env_default(name).map(|value| {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|element| !element.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+// extract the name = true / name = false attributes from the proc_macro::TokenStream
+fn extract_bool_attribute(name: &str, stream: proc_macro2::TokenStream) -> syn::Result<Option<bool>> {
+    let mut tokens = stream.into_iter();
+    while let Some(token) = tokens.next() {
+        let proc_macro2::TokenTree::Ident(ident) = token else {
+            continue;
+        };
+        if ident != name {
+            continue;
+        }
+        let value = match (tokens.next(), tokens.next()) {
+            (Some(proc_macro2::TokenTree::Punct(punct)), Some(proc_macro2::TokenTree::Ident(value)))
+                if punct.as_char() == '=' =>
+            {
+                value.to_string()
+            }
+            _ => String::new(),
+        };
+        return match value.as_str() {
+            "true" => Ok(Some(true)),
+            "false" => Ok(Some(false)),
+            _ => Err(diagnostics::error(format!(
+                "Expected {} = true or {} = false",
+                name, name
+            ))),
+        };
+    }
+    Ok(None)
+}
+
+// extract the name = 42 attributes from the proc_macro::TokenStream
+fn extract_int_attribute(name: &str, stream: proc_macro2::TokenStream) -> syn::Result<Option<usize>> {
+    let mut tokens = stream.into_iter();
+    while let Some(token) = tokens.next() {
+        let proc_macro2::TokenTree::Ident(ident) = token else {
+            continue;
+        };
+        if ident != name {
+            continue;
+        }
+        return match (tokens.next(), tokens.next()) {
+            (Some(proc_macro2::TokenTree::Punct(punct)), Some(proc_macro2::TokenTree::Literal(value)))
+                if punct.as_char() == '=' =>
+            {
+                value
+                    .to_string()
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| diagnostics::error(format!("Expected {} = <integer>", name)))
+            }
+            _ => Err(diagnostics::error(format!("Expected {} = <integer>", name))),
+        };
+    }
+    Ok(None)
+}
+
+// extract the name = ["a", "b"] attributes from the proc_macro::TokenStream
+fn extract_list_attribute(name: &str, stream: proc_macro2::TokenStream) -> syn::Result<Option<Vec<String>>> {
+    let mut tokens = stream.into_iter();
+    while let Some(token) = tokens.next() {
+        let proc_macro2::TokenTree::Ident(ident) = token else {
+            continue;
+        };
+        if ident != name {
+            continue;
+        }
+        let group = match (tokens.next(), tokens.next()) {
+            (Some(proc_macro2::TokenTree::Punct(punct)), Some(proc_macro2::TokenTree::Group(group)))
+                if punct.as_char() == '=' && group.delimiter() == proc_macro2::Delimiter::Bracket =>
+            {
+                group
+            }
+            _ => return Err(diagnostics::error(format!("Expected {} = [\"...\", ...]", name))),
+        };
+        // The elements are string literals, or paths written as is
+        let mut values = vec![];
+        let mut element: Vec<proc_macro2::TokenTree> = vec![];
+        // The commas between generic arguments, e.g. `HashMap<K, V>`, don't separate elements
+        let mut depth = 0usize;
+        for token in group
+            .stream()
+            .into_iter()
+            .chain([proc_macro2::Punct::new(',', proc_macro2::Spacing::Alone).into()])
+        {
+            match token {
+                proc_macro2::TokenTree::Punct(punct) if punct.as_char() == '<' => {
+                    depth += 1;
+                    element.push(punct.into());
+                }
+                proc_macro2::TokenTree::Punct(punct) if punct.as_char() == '>' && depth > 0 => {
+                    depth -= 1;
+                    element.push(punct.into());
+                }
+                proc_macro2::TokenTree::Punct(punct) if punct.as_char() == ',' && depth == 0 => {
+                    match element.as_slice() {
+                        [] => {}
+                        [proc_macro2::TokenTree::Literal(lit)] => values.push(get_content(lit.clone())),
+                        _ => values.push(element.iter().map(|token| token.to_string()).collect()),
+                    }
+                    element.clear();
+                }
+                token => element.push(token),
+            }
+        }
+        return Ok(Some(values));
+    }
+    Ok(None)
+}
+
 fn get_content(lit: Literal) -> String {
     let content = lit.to_string();
     content[1..content.len() - 1].to_string()
 }
 
+/// The elements of the `|` separated attributes, e.g. `modules = "crate::api::v1 | crate::models"`
+fn split_alternatives(alternatives: &str) -> Vec<String> {
+    alternatives
+        .split('|')
+        .map(str::trim)
+        .filter(|alternative| !alternative.is_empty())
+        .map(|alternative| alternative.to_string())
+        .collect()
+}
+
+fn parse_path_list(name: &str, paths: Option<Vec<String>>) -> syn::Result<Vec<syn::Path>> {
+    paths
+        .unwrap_or_default()
+        .iter()
+        .map(|path| {
+            syn::parse_str(path).map_err(|_| diagnostics::error(format!("Invalid path \"{}\" in {}", path, name)))
+        })
+        .collect()
+}
+
 /// Check if the macro is placed before the #[derive] and #[openapi] attributes
-/// Otherwise, panic!
-pub fn check_macro_placement(attrs: Vec<Attribute>) {
-    if !attrs.iter().any(|elm| elm.path().is_ident("derive")) {
-        panic!("Please put utoipauto before #[derive] and #[openapi]");
+/// Otherwise, return an error
+pub fn check_macro_placement(attrs: Vec<Attribute>) -> syn::Result<()> {
+    if !attrs.iter().any(|elm| elm.path().is_ident("derive")) || !attrs.iter().any(|elm| elm.path().is_ident("openapi"))
+    {
+        return Err(diagnostics::error(
+            "Please put utoipauto before #[derive] and #[openapi]",
+        ));
     }
+    Ok(())
+}
 
-    if !attrs.iter().any(|elm| elm.path().is_ident("openapi")) {
-        panic!("Please put utoipauto before #[derive] and #[openapi]");
+/// Remove the other `#[utoipauto]` attributes of the item, and return their parameters.
+///
+/// Only the first attribute is expanded, so it discovers the items of the others too.
+pub fn take_repeated_attributes(attrs: &mut Vec<Attribute>, doc_struct: &syn::Ident) -> syn::Result<Vec<Parameters>> {
+    let mut merged = vec![];
+    let mut error: Option<syn::Error> = None;
+    attrs.retain(|attr| {
+        if !attr
+            .path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "utoipauto")
+        {
+            return true;
+        }
+        let tokens = match &attr.meta {
+            syn::Meta::List(list) => list.tokens.clone(),
+            _ => proc_macro2::TokenStream::new(),
+        };
+        match parse_attributes(tokens) {
+            Ok(mut params) => {
+                params.doc_struct = Some(doc_struct.clone());
+                merged.push(params);
+            }
+            Err(e) => match &mut error {
+                Some(error) => error.combine(e),
+                None => error = Some(e),
+            },
+        }
+        false
+    });
+    match error {
+        Some(error) => Err(error),
+        None => Ok(merged),
     }
 }
 
-// Output the macro back to the compiler
-pub fn output_macro(openapi_macro: syn::ItemStruct) -> proc_macro::TokenStream {
+// Output the macro back to the compiler, along with the items generated next to it
+pub fn output_macro(
+    openapi_macro: syn::ItemStruct,
+    additional_items: proc_macro2::TokenStream,
+) -> proc_macro::TokenStream {
     let code = quote!(
           #openapi_macro
+          #additional_items
     );
 
     TokenStream::from(code)
 }
 
+/// Put each of the items behind `#[cfg(predicate)]`
+pub fn with_cfg(items: proc_macro2::TokenStream, predicate: &proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let file: syn::File = syn::parse2(items).expect("utoipauto: Failed to parse the generated items");
+    let items = file.items.iter().map(|item| quote!(#[cfg(#predicate)] #item));
+    quote!(#(#items)*)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::TempDir;
 
     #[test]
     fn test_get_content() {
@@ -89,7 +1599,7 @@ mod tests {
             paths = "p1"
         };
 
-        let attributes = extract_attributes(tokens);
+        let attributes = extract_attributes(tokens).unwrap();
         assert_eq!(attributes.paths, "p1")
     }
 
@@ -121,15 +1631,38 @@ mod tests {
         assert_eq!(attributes, None);
     }
 
+    #[test]
+    fn test_extract_bool_attribute() {
+        let quote = quote! {
+            paths = "p1", flag = true, other = false
+        };
+
+        assert_eq!(extract_bool_attribute("flag", quote.clone()).unwrap(), Some(true));
+        assert_eq!(extract_bool_attribute("other", quote.clone()).unwrap(), Some(false));
+        assert_eq!(extract_bool_attribute("not_found", quote).unwrap(), None);
+    }
+
+    #[test]
+    fn test_extract_bool_attribute_invalid() {
+        let error = extract_bool_attribute("flag", quote! { flag = "yes" }).unwrap_err();
+        assert!(error.to_string().contains("Expected flag = true or flag = false"));
+    }
+
     #[test]
     fn test_extract_attributes_empty() {
         let tokens = quote! {};
 
-        let attributes = extract_attributes(tokens);
+        let attributes = extract_attributes(tokens).unwrap();
         assert_eq!(attributes.paths, "./src");
-        assert_eq!(attributes.fn_attribute_name, "utoipa");
+        assert_eq!(attributes.fn_attribute_names, vec!["utoipa"]);
         assert_eq!(attributes.schema_attribute_name, "ToSchema");
         assert_eq!(attributes.response_attribute_name, "ToResponse");
+        assert_eq!(attributes.order, Order::Module);
+        assert!(attributes.path_transform.is_none());
+        assert!(attributes.components_into.is_none());
+        assert_eq!(attributes.components_from, None);
+        assert!(!attributes.prefer_reexports);
+        assert!(!attributes.flatten);
     }
 
     #[test]
@@ -138,10 +1671,502 @@ mod tests {
             paths = "p1", function_attribute_name = "handler", schema_attribute_name = "Schema", response_attribute_name = "Response"
         };
 
-        let attributes = extract_attributes(tokens);
+        let attributes = extract_attributes(tokens).unwrap();
         assert_eq!(attributes.paths, "p1");
-        assert_eq!(attributes.fn_attribute_name, "handler");
+        assert_eq!(attributes.fn_attribute_names, vec!["handler"]);
         assert_eq!(attributes.schema_attribute_name, "Schema");
         assert_eq!(attributes.response_attribute_name, "Response");
     }
+
+    #[test]
+    fn test_extract_attributes_order() {
+        let tokens = quote! {
+            paths = "p1", order = "alphabetical"
+        };
+
+        let attributes = extract_attributes(tokens).unwrap();
+        assert_eq!(attributes.order, Order::Alphabetical);
+    }
+
+    #[test]
+    fn test_is_rustdoc() {
+        assert!(is_rustdoc(std::path::Path::new(
+            "/home/me/.rustup/toolchains/stable/bin/rustdoc"
+        )));
+        assert!(!is_rustdoc(std::path::Path::new(
+            "/home/me/.rustup/toolchains/stable/bin/rustc"
+        )));
+    }
+
+    #[test]
+    fn test_is_proc_macro_server() {
+        assert!(is_proc_macro_server(std::path::Path::new(
+            "/home/me/.rustup/toolchains/stable/libexec/rust-analyzer-proc-macro-srv"
+        )));
+        assert!(!is_proc_macro_server(std::path::Path::new(
+            "/home/me/.rustup/toolchains/stable/bin/rustc"
+        )));
+    }
+
+    #[test]
+    fn test_extract_attributes_rust_analyzer() {
+        assert_eq!(extract_attributes(quote! {}).unwrap().rust_analyzer, IdeMode::Full);
+        let attributes = extract_attributes(quote! { rust_analyzer = "cached" }).unwrap();
+        assert_eq!(attributes.rust_analyzer, IdeMode::Cached);
+    }
+
+    #[test]
+    fn test_extract_attributes_unknown_rust_analyzer() {
+        let error = extract_attributes(quote! { rust_analyzer = "lazy" })
+            .map(|_| ())
+            .unwrap_err();
+        assert!(error.to_string().contains("Unknown rust_analyzer mode \"lazy\""));
+    }
+
+    #[test]
+    fn test_extract_attributes_schema_source() {
+        assert_eq!(extract_attributes(quote! {}).unwrap().schema_source, SchemaSource::Both);
+        let attributes = extract_attributes(quote! { schema_source = "derives" }).unwrap();
+        assert_eq!(attributes.schema_source, SchemaSource::Derives);
+        assert!(attributes.schema_source.derives() && !attributes.schema_source.impls());
+    }
+
+    #[test]
+    fn test_parse_custom_impls() {
+        assert_eq!(
+            parse_custom_impls(&["schemas => custom".to_string(), "responses => schemas".to_string()]).unwrap(),
+            CustomImpls {
+                schemas: ImplTarget::Custom,
+                responses: ImplTarget::Schemas,
+            }
+        );
+        assert_eq!(parse_custom_impls(&[]).unwrap(), CustomImpls::default());
+    }
+
+    #[test]
+    fn test_parse_invalid_custom_impls() {
+        let error = parse_custom_impls(&["schemas => elsewhere".to_string()]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Invalid custom_impls rule \"schemas => elsewhere\""));
+    }
+
+    #[test]
+    fn test_extract_attributes_unknown_schema_source() {
+        let error = extract_attributes(quote! { schema_source = "manual" })
+            .map(|_| ())
+            .unwrap_err();
+        assert!(error.to_string().contains("Unknown schema_source"));
+    }
+
+    #[test]
+    fn test_extract_attributes_unknown_order() {
+        let error = extract_attributes(quote! { order = "random" }).map(|_| ()).unwrap_err();
+        assert!(error.to_string().contains("Unknown order"));
+    }
+
+    #[test]
+    fn test_parse_path_transform() {
+        let transform = parse_path_transform("crate::internal::api => crate::api, crate::internal =>").unwrap();
+        let transformed = |path: syn::Path| crate::path_utils::path_to_string(&transform(path));
+
+        assert_eq!(
+            transformed(syn::parse_quote!(crate::internal::api::get_user)),
+            "crate::api::get_user"
+        );
+        assert_eq!(transformed(syn::parse_quote!(crate::internal::User)), "User");
+        assert_eq!(transformed(syn::parse_quote!(crate::other::User)), "crate::other::User");
+    }
+
+    #[test]
+    fn test_parse_path_transform_invalid_rule() {
+        let error = parse_path_transform("crate::internal").map(|_| ()).unwrap_err();
+        assert!(error.to_string().contains("Invalid path_transform rule"));
+    }
+
+    #[test]
+    fn test_extract_attributes_components_into() {
+        let attributes = extract_attributes(quote! { components_into = "crate::docs::SchemaDoc" }).unwrap();
+        assert_eq!(
+            attributes
+                .components_into
+                .map(|path| crate::path_utils::path_to_string(&path)),
+            Some("crate::docs::SchemaDoc".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_module_mapping() {
+        assert_eq!(
+            parse_module_mapping("routes =>, handlers_v1 => v1").unwrap(),
+            ModuleMapping {
+                strip: vec!["routes".to_string()],
+                replace: vec![("handlers_v1".to_string(), "v1".to_string())],
+            }
+        );
+    }
+
+    #[test]
+    fn test_cfg_variants() {
+        let variants = cfg_variants(&["test".to_string(), "feature = \"a\"".to_string()]).unwrap();
+        assert_eq!(variants.len(), 4);
+        assert_eq!(
+            variants[1].0,
+            vec![("test".to_string(), true), ("feature = \"a\"".to_string(), false)]
+        );
+        assert_eq!(
+            variants[1].1.to_string(),
+            quote!(all(test, not(feature = "a"))).to_string()
+        );
+    }
+
+    #[test]
+    fn test_env_defaults() {
+        // Not an attribute, so that the other tests don't see it
+        std::env::set_var("UTOIPAUTO_TEST_DEFAULT", " rs, rs.in ,");
+        assert_eq!(
+            env_list("test_default"),
+            Some(vec!["rs".to_string(), "rs.in".to_string()])
+        );
+        std::env::set_var("UTOIPAUTO_TEST_DEFAULT", "1");
+        assert_eq!(env_bool("test_default").unwrap(), Some(true));
+        assert_eq!(env_int("test_default").unwrap(), Some(1));
+        std::env::set_var("UTOIPAUTO_TEST_DEFAULT", "");
+        assert_eq!(env_default("test_default"), None);
+        std::env::remove_var("UTOIPAUTO_TEST_DEFAULT");
+        assert_eq!(env_default("test_default"), None);
+    }
+
+    #[test]
+    fn test_target_paths() {
+        let targets = PackageTargets {
+            lib: Some(("my_app".to_string(), "src/lib.rs".into())),
+            bins: vec![
+                ("my-app".to_string(), "src/main.rs".into()),
+                ("admin".to_string(), "src/bin/admin/main.rs".into()),
+                ("cli".to_string(), "src/bin/cli.rs".into()),
+            ],
+        };
+        let strings = |strings: &[&str]| strings.iter().map(|string| string.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            target_paths(&targets, ".", Target::Lib),
+            (
+                strings(&["./src"]),
+                strings(&["./src/main.rs", "./src/bin/admin", "./src/bin/cli.rs"])
+            )
+        );
+        assert_eq!(
+            target_paths(&targets, "./app", Target::Bin("my-app")),
+            (
+                strings(&["./app/src"]),
+                strings(&["./app/src/bin/admin", "./app/src/bin/cli.rs"])
+            )
+        );
+        // The library would scan the binary again
+        assert_eq!(
+            target_paths(&targets, ".", Target::Bin("cli")),
+            (strings(&["./src/bin/cli.rs in crate"]), vec![])
+        );
+        assert_eq!(
+            target_paths(&targets, ".", Target::Bin("admin")),
+            (strings(&["./src/bin/admin in crate"]), vec![])
+        );
+        let custom = PackageTargets {
+            lib: Some(("my_app".to_string(), "api/lib.rs".into())),
+            bins: vec![("server".to_string(), "server.rs".into())],
+        };
+        assert_eq!(
+            target_paths(&custom, ".", Target::Bin("server")),
+            (strings(&["./server.rs in crate", "./api in my_app"]), vec![])
+        );
+        assert_eq!(
+            target_paths(&custom, ".", Target::Lib),
+            (strings(&["./api in crate"]), vec![])
+        );
+        let at_root = PackageTargets {
+            lib: Some(("my_app".to_string(), "lib.rs".into())),
+            bins: vec![],
+        };
+        assert_eq!(
+            target_paths(&at_root, ".", Target::Lib),
+            (strings(&["./lib.rs in crate"]), vec![])
+        );
+        let bin_only = PackageTargets {
+            lib: None,
+            bins: vec![("server".to_string(), "src/main.rs".into())],
+        };
+        assert_eq!(
+            target_paths(&bin_only, ".", Target::Bin("server")),
+            (strings(&["./src"]), vec![])
+        );
+        assert_eq!(target_paths(&bin_only, ".", Target::Lib), (strings(&["./src"]), vec![]));
+    }
+
+    #[test]
+    fn test_include_dir_paths() {
+        let root = TempDir::new("include-dirs");
+        std::fs::create_dir_all(root.join("tests")).unwrap();
+        std::fs::create_dir_all(root.join("examples/docs-server")).unwrap();
+        for file in ["tests/api.rs", "tests/other.rs", "examples/docs-server/main.rs"] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        let dirs = vec!["tests".to_string(), "./examples/".to_string()];
+        let test = include_dir_paths(&root, ".", &dirs, Some("api"));
+        let example = include_dir_paths(&root, "./app", &dirs, Some("docs_server"));
+        let lib = include_dir_paths(&root, ".", &dirs, Some("my_app"));
+        assert_eq!(test, vec!["./tests/api.rs in crate"]);
+        assert_eq!(example, vec!["./app/examples/docs-server in crate"]);
+        assert!(lib.is_empty());
+    }
+
+    #[test]
+    fn test_project_config() {
+        let root = TempDir::new("project-config");
+        std::fs::create_dir_all(root.join("api/src")).unwrap();
+        std::fs::write(
+            root.join("utoipauto.toml"),
+            "# The defaults of the workspace\npaths = [\"./src\", \"./generated\"]\nframework = \"axum\"\n\
+             pedantic = true\nmax_files = 100\nextensions = \"rs, rs.in\"\n",
+        )
+        .unwrap();
+        let config = ProjectConfig::find(&root.join("api/src")).unwrap();
+        std::fs::write(root.join("utoipauto.toml"), "pedantc = true").unwrap();
+        let unknown = ProjectConfig::find(&root).map(|_| ()).unwrap_err();
+
+        let config = config.unwrap();
+        assert_eq!(config.string("paths"), Some("./src, ./generated".to_string()));
+        assert_eq!(config.string("framework"), Some("axum".to_string()));
+        assert_eq!(config.flag("pedantic").unwrap(), Some(true));
+        assert_eq!(config.int("max_files").unwrap(), Some(100));
+        assert_eq!(
+            config.list("extensions"),
+            Some(vec!["rs".to_string(), "rs.in".to_string()])
+        );
+        assert_eq!(config.string("exclude"), None);
+        assert!(unknown
+            .to_string()
+            .starts_with("utoipauto: Unknown attribute `pedantc`"));
+        assert!(ProjectConfig::find(&std::env::temp_dir().join("utoipauto-no-config"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_extract_list_attribute() {
+        let tokens = quote! { paths = "./src", only = ["crate::api", "crate::webhooks",] };
+        assert_eq!(
+            extract_list_attribute("only", tokens).unwrap(),
+            Some(vec!["crate::api".to_string(), "crate::webhooks".to_string()])
+        );
+        assert_eq!(
+            extract_list_attribute("only", quote! { paths = "./src" }).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_extract_list_attribute_paths() {
+        let tokens = quote! { compose = [crate_a::Doc, "/b => crate_b::Doc"] };
+        assert_eq!(
+            extract_list_attribute("compose", tokens).unwrap(),
+            Some(vec!["crate_a::Doc".to_string(), "/b => crate_b::Doc".to_string()])
+        );
+
+        let tokens = quote! { generic_schemas = [Paginated<User>, HashMap<String, Vec<User> >] };
+        assert_eq!(
+            extract_list_attribute("generic_schemas", tokens).unwrap(),
+            Some(vec![
+                "Paginated<User>".to_string(),
+                "HashMap<String,Vec<User>>".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_extract_list_attribute_invalid() {
+        let error = extract_list_attribute("only", quote! { only = "crate::api" }).unwrap_err();
+        assert!(error.to_string().contains("Expected only = ["));
+    }
+
+    #[test]
+    fn test_extract_int_attribute() {
+        assert_eq!(
+            extract_int_attribute("max_item_depth", quote! { max_item_depth = 3 }).unwrap(),
+            Some(3)
+        );
+        assert_eq!(
+            extract_int_attribute("max_item_depth", quote! { paths = "./src" }).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_extract_int_attribute_invalid() {
+        let error = extract_int_attribute("max_item_depth", quote! { max_item_depth = "3" }).unwrap_err();
+        assert!(error.to_string().contains("Expected max_item_depth = <integer>"));
+    }
+
+    #[test]
+    fn test_fail_on() {
+        let params = extract_attributes(quote! { fail_on = ["private_item", "empty_discovery"] }).unwrap();
+        assert_eq!(
+            params.fail_on,
+            vec![WarningKind::PrivateItem, WarningKind::EmptyDiscovery]
+        );
+        assert!(params.checks(WarningKind::EmptyDiscovery));
+        assert!(!params.checks(WarningKind::EmptyRoot));
+        assert!(params.checks(WarningKind::ConflictingRoute));
+    }
+
+    #[test]
+    fn test_strict() {
+        let params = extract_attributes(quote! { strict = true, fail_on = ["private_item", "empty_root"] }).unwrap();
+        assert_eq!(
+            params.fail_on,
+            vec![
+                WarningKind::PrivateItem,
+                WarningKind::EmptyRoot,
+                WarningKind::EmptyDiscovery,
+                WarningKind::UnmatchedPath
+            ]
+        );
+        assert!(params.checks(WarningKind::EmptyDiscovery));
+        assert!(!params.checks(WarningKind::UnreachableModule));
+        assert!(extract_attributes(quote! { strict = false })
+            .unwrap()
+            .fail_on
+            .is_empty());
+    }
+
+    #[test]
+    fn test_utoipa_crate() {
+        let params =
+            extract_attributes(quote! { utoipa_crate = "my_framework::openapi", framework = "salvo" }).unwrap();
+        assert_eq!(params.utoipa_crate, vec!["my_framework", "openapi"]);
+        assert_eq!(params.fn_attribute_names, vec!["openapi", "endpoint"]);
+        let params = extract_attributes(quote! { utoipa_crate = "openapi", function_attributes = ["utoipa"] }).unwrap();
+        assert_eq!(params.fn_attribute_names, vec!["utoipa"]);
+        assert_eq!(extract_attributes(quote! {}).unwrap().utoipa_crate, vec!["utoipa"]);
+    }
+
+    #[test]
+    fn test_utoipa_crate_invalid() {
+        let error = extract_attributes(quote! { utoipa_crate = "my-framework::openapi" })
+            .map(|_| ())
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("utoipauto: Invalid utoipa_crate \"my-framework::openapi\", expected a path"));
+    }
+
+    #[test]
+    fn test_function_attributes() {
+        let params = extract_attributes(quote! { function_attributes = ["path", "endpoint"] }).unwrap();
+        assert_eq!(params.fn_attribute_names, vec!["path", "endpoint"]);
+        let params =
+            extract_attributes(quote! { function_attribute_name = "handler", function_attributes = ["endpoint"] })
+                .unwrap();
+        assert_eq!(params.fn_attribute_names, vec!["handler", "endpoint"]);
+    }
+
+    #[test]
+    fn test_framework() {
+        let params = extract_attributes(quote! { framework = "salvo" }).unwrap();
+        assert_eq!(params.fn_attribute_names, vec!["utoipa", "endpoint"]);
+        let params = extract_attributes(quote! { framework = "axum" }).unwrap();
+        assert_eq!(params.fn_attribute_names, vec!["utoipa"]);
+        let params = extract_attributes(quote! { framework = "salvo", function_attribute_name = "handler" }).unwrap();
+        assert_eq!(params.fn_attribute_names, vec!["handler"]);
+    }
+
+    #[test]
+    fn test_unknown_framework() {
+        let error = extract_attributes(quote! { framework = "warp" })
+            .map(|_| ())
+            .unwrap_err();
+        assert!(error.to_string().contains("utoipauto: Unknown framework \"warp\""));
+    }
+
+    #[test]
+    fn test_structured_attributes() {
+        let params = parse_attributes(quote! {
+            paths(["./src", "./other/src" as other_crate]),
+            function_attribute = "handler",
+            schema_derive = "Schema",
+        })
+        .unwrap();
+        assert_eq!(params.paths, "./src, ./other/src from other_crate");
+        assert_eq!(params.fn_attribute_names, vec!["handler"]);
+        assert_eq!(params.schema_attribute_name, "Schema");
+        assert_eq!(params.response_attribute_name, "ToResponse");
+
+        let params = parse_attributes(quote! { paths("./src" as api), prefer_reexports = true }).unwrap();
+        assert_eq!(params.paths, "./src from api");
+        assert!(params.prefer_reexports);
+    }
+
+    #[test]
+    fn test_invalid_structured_attributes() {
+        let error = |tokens| parse_attributes(tokens).err().unwrap().to_string();
+        assert_eq!(
+            error(quote! { paths = "./src", prefer_rexports = true }),
+            "utoipauto: Unknown attribute `prefer_rexports`, did you mean `prefer_reexports`?"
+        );
+        assert_eq!(
+            error(quote! { colour = "blue" }),
+            "utoipauto: Unknown attribute `colour`"
+        );
+        assert_eq!(error(quote! { paths }), "utoipauto: Expected `paths = ...`");
+        assert_eq!(error(quote! { pedantic(true) }), "utoipauto: Expected `pedantic = ...`");
+        assert_eq!(error(quote! { paths(["./src" from api]) }), "expected `,`");
+    }
+
+    #[test]
+    fn test_modules() {
+        let params =
+            extract_attributes(quote! { only = ["crate::webhooks"], modules = "crate::api::v1 | crate::models" })
+                .unwrap();
+        let only: Vec<String> = params.only.iter().map(|path| quote!(#path).to_string()).collect();
+        assert_eq!(only, vec!["crate :: webhooks", "crate :: api :: v1", "crate :: models"]);
+    }
+
+    #[test]
+    fn test_invalid_modules() {
+        let error = extract_attributes(quote! { modules = "crate::api-v1" })
+            .map(|_| ())
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("utoipauto: Invalid path \"crate::api-v1\" in modules"));
+    }
+
+    #[test]
+    fn test_fail_on_unknown() {
+        let error = extract_attributes(quote! { fail_on = ["undocumented"] })
+            .map(|_| ())
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("utoipauto: Unknown fail_on \"undocumented\", expected one of undiscovered_schema"));
+    }
+
+    #[test]
+    fn test_take_repeated_attributes() {
+        let item: syn::ItemStruct = syn::parse_quote! {
+            #[utoipauto(paths = "./crate_a/src")]
+            #[utoipauto::utoipauto(paths = "./crate_b/src", prefer_reexports = true)]
+            #[derive(OpenApi)]
+            #[openapi()]
+            pub struct ApiDoc;
+        };
+        let mut attrs = item.attrs;
+        let merged = take_repeated_attributes(&mut attrs, &item.ident).unwrap();
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].paths, "./crate_a/src");
+        assert!(!merged[0].prefer_reexports);
+        assert_eq!(merged[1].paths, "./crate_b/src");
+        assert!(merged[1].prefer_reexports);
+        assert_eq!(merged[1].doc_struct, Some(item.ident));
+        assert_eq!(attrs.len(), 2);
+    }
 }
@@ -1,11 +1,13 @@
-use attribute_utils::update_openapi_macro_attributes;
+use attribute_utils::{expand_openapi, expand_openapi_variants};
 use proc_macro::TokenStream;
 
-use quote::quote;
-use string_utils::{discover, extract_paths};
+use quote::{quote, ToTokens};
+use string_utils::{discover_all, discover_items, discover_schemas, extract_paths};
 use syn::parse_macro_input;
-use token_utils::{check_macro_placement, extract_attributes, output_macro};
-use utoipauto_core::{attribute_utils, string_utils, token_utils};
+use token_utils::{check_macro_placement, parse_attributes, skips_discovery, take_repeated_attributes, Parameters};
+use utoipauto_core::{
+    attribute_utils, codegen::write_expanded, diagnostics, discover::Discovered, string_utils, token_utils,
+};
 
 /// Macro to automatically discover all the functions with the #[utoipa] attribute
 /// And the struct deriving ToSchema and ToResponse
@@ -14,28 +16,166 @@ pub fn utoipauto(
     attributes: proc_macro::TokenStream, // #[utoipauto(paths = "(MODULE_TREE_PATH => MODULE_SRC_PATH) ;")]
     item: proc_macro::TokenStream,       // #[openapi(paths = "")]
 ) -> proc_macro::TokenStream {
+    let original = proc_macro2::TokenStream::from(item.clone());
     // (MODULE_TREE_PATH => MODULE_SRC_PATH) ; (MODULE_TREE_PATH => MODULE_SRC_PATH) ; ...
-    let params = extract_attributes(attributes.into());
     // [(MODULE_TREE_PATH, MODULE_SRC_PATH)]
-    let paths: Vec<String> = extract_paths(&params.paths);
+    let (mut params, paths) = match parse_attributes(attributes.into())
+        .and_then(|params| extract_paths(&params.paths).map(|paths| (params, paths)))
+    {
+        Ok(parsed) => parsed,
+        Err(e) => return TokenStream::from(with_error(original, e)),
+    };
 
-    // #[openapi(...)]
-    let mut openapi_macro = parse_macro_input!(item as syn::ItemStruct);
+    // #[openapi(...)], or a function building the documentation
+    let mut openapi_macro =
+        match parse_macro_input!(item as syn::Item) {
+            syn::Item::Struct(openapi_macro) => openapi_macro,
+            syn::Item::Fn(function) => return TokenStream::from(expand_fn(function, params, &paths)),
+            item => return TokenStream::from(with_error(
+                item,
+                diagnostics::error(
+                    "#[utoipauto] must be placed on a struct deriving OpenApi, or on a function returning an OpenApi",
+                ),
+            )),
+        };
+    params.doc_struct = Some(openapi_macro.ident.clone());
+    params.merged = match take_repeated_attributes(&mut openapi_macro.attrs, &openapi_macro.ident) {
+        Ok(merged) => merged,
+        Err(e) => return TokenStream::from(with_error(&openapi_macro, e)),
+    };
+
+    // Check if the macro is placed before the #[derive] and #[openapi] attributes
+    if let Err(e) = check_macro_placement(openapi_macro.attrs.clone()) {
+        return TokenStream::from(with_error(&openapi_macro, e));
+    }
 
     // Discover all the functions with the #[utoipa] attribute
-    let (uto_paths, uto_models, uto_responses) = discover(paths, &params);
+    let discovered = match discover_unless_skipped(&paths, &mut params) {
+        Ok(discovered) => discovered,
+        Err(e) => return TokenStream::from(with_error(&openapi_macro, e)),
+    };
+    let ident = openapi_macro.ident.to_string();
+    let output = match discovered.cfgs.is_empty() {
+        true => match expand_openapi(openapi_macro.clone(), &params, discovered) {
+            Ok((openapi_macro, additional_items)) => quote!(#openapi_macro #additional_items),
+            Err(e) => return TokenStream::from(with_error(&openapi_macro, e)),
+        },
+        false => match expand_openapi_variants(openapi_macro.clone(), &mut params, &paths, discovered) {
+            Ok(variants) => variants,
+            Err(e) => return TokenStream::from(with_error(&openapi_macro, e)),
+        },
+    };
+    // Output the macro back to the compiler
+    TokenStream::from(emit(&ident, output, &params))
+}
 
-    // extract the openapi macro attributes : #[openapi(openapi_macro_attibutes)]
-    let openapi_macro_attibutes = &mut openapi_macro.attrs;
+/// Write the output to `emit_to`, if set
+fn emit(item_name: &str, output: proc_macro2::TokenStream, params: &Parameters) -> proc_macro2::TokenStream {
+    let Some(emit_to) = &params.emit_to else {
+        return output;
+    };
+    match write_expanded(item_name, output.clone(), emit_to) {
+        Ok(()) => output,
+        Err(e) => with_error(
+            output,
+            diagnostics::error(format!("Failed to write `{}`: {}", emit_to, e)),
+        ),
+    }
+}
 
-    // Check if the macro is placed before the #[derive] and #[openapi] attributes
-    check_macro_placement(openapi_macro_attibutes.clone());
+/// The item without the discovered items, along with the error, so that its uses still resolve
+fn with_error(item: impl ToTokens, error: syn::Error) -> proc_macro2::TokenStream {
+    let error = error.to_compile_error();
+    quote!(#item #error)
+}
 
-    // Update the openapi macro attributes with the newly discovered paths
-    update_openapi_macro_attributes(openapi_macro_attibutes, &uto_paths, &uto_models, &uto_responses);
+/// Skip the discovery in doc builds, where the sources may not be laid out as expected, and under rust-analyzer if
+/// asked to
+fn discover_unless_skipped(paths: &[String], params: &mut Parameters) -> syn::Result<Discovered> {
+    if !skips_discovery(params) {
+        return discover_all(paths, params);
+    }
+    // Nothing was removed, it just wasn't discovered
+    params.previous_spec = None;
+    Ok(Discovered::default())
+}
 
-    // Output the macro back to the compiler
-    output_macro(openapi_macro)
+/// Merge the discovered items into the documentation returned by the function
+fn expand_fn(mut function: syn::ItemFn, mut params: Parameters, paths: &[String]) -> proc_macro2::TokenStream {
+    params.doc_struct = Some(function.sig.ident.clone());
+    params.merged = match take_repeated_attributes(&mut function.attrs, &function.sig.ident) {
+        Ok(merged) => merged,
+        Err(e) => return with_error(&function, e),
+    };
+
+    // The discovered items are collected by a derived OpenApi in the body of the function
+    let openapi_macro: syn::ItemStruct = syn::parse_quote! {
+        #[derive(utoipa::OpenApi)]
+        #[openapi()]
+        struct UtoipautoDiscovered;
+    };
+    let discovered = match discover_unless_skipped(paths, &mut params) {
+        Ok(discovered) => discovered,
+        Err(e) => return with_error(&function, e),
+    };
+    let discovered_doc = match discovered.cfgs.is_empty() {
+        true => match expand_openapi(openapi_macro, &params, discovered) {
+            Ok((openapi_macro, additional_items)) => quote!(#openapi_macro #additional_items),
+            Err(e) => return with_error(&function, e),
+        },
+        false => match expand_openapi_variants(openapi_macro, &mut params, paths, discovered) {
+            Ok(variants) => variants,
+            Err(e) => return with_error(&function, e),
+        },
+    };
+
+    let body = &function.block;
+    function.block = syn::parse_quote!({
+        let mut openapi: utoipa::openapi::OpenApi = #body;
+        #discovered_doc
+        openapi.merge(<UtoipautoDiscovered as utoipa::OpenApi>::openapi());
+        openapi
+    });
+    emit(&function.sig.ident.to_string(), quote!(#function), &params)
+}
+
+/// Expand to the structs deriving ToSchema in the given paths, e.g. `include_schemas!("./src/models/**")`
+///
+/// Meant to be used inside `components(schemas(...))` of an `#[openapi]` attribute processed by `#[utoipauto]`
+#[proc_macro]
+pub fn include_schemas(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let paths = parse_macro_input!(input as syn::LitStr);
+    let params = match Parameters::from_env() {
+        Ok(params) => params,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
+    if skips_discovery(&params) {
+        return TokenStream::new();
+    }
+
+    match discover_schemas(&paths.value(), &params) {
+        Ok(schemas) => TokenStream::from(schemas),
+        Err(e) => TokenStream::from(e.to_compile_error()),
+    }
+}
+
+/// Expand to a `&'static [utoipauto::DiscoveredItem]` describing the items discovered in the given paths,
+/// e.g. `discovered!("./src")`
+#[proc_macro]
+pub fn discovered(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let paths = parse_macro_input!(input as syn::LitStr);
+    let params = match Parameters::from_env() {
+        Ok(params) => params,
+        Err(e) => return TokenStream::from(e.to_compile_error()),
+    };
+    if skips_discovery(&params) {
+        return TokenStream::from(quote!(&[]));
+    }
+
+    match discover_items(&paths.value(), &params) {
+        Ok(items) => TokenStream::from(items),
+        Err(e) => TokenStream::from(e.to_compile_error()),
+    }
 }
 
 /// Ignore the function from the auto discovery
@@ -49,6 +189,41 @@ pub fn utoipa_ignore(_attr: proc_macro::TokenStream, item: proc_macro::TokenStre
     TokenStream::from(code)
 }
 
+/// Register the item even if the discovery can't tell it apart, e.g. `#[utoipa_include(schema)]` on a type whose
+/// `ToSchema` is implemented by another macro, or `#[utoipa_include(path)]` on a handler
+#[proc_macro_attribute]
+pub fn utoipa_include(_attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(item as syn::Item);
+    let code = quote!(
+          #input
+    );
+
+    TokenStream::from(code)
+}
+
+/// Register the struct into the `modifiers(...)` of the discovered OpenApi
+#[proc_macro_attribute]
+pub fn utoipauto_modifier(_attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(item as syn::Item);
+    let code = quote!(
+          #input
+    );
+
+    TokenStream::from(code)
+}
+
+/// Emit the given path for the function instead of the computed one,
+/// e.g. `#[utoipauto::alias("crate::api::v2::get_user")]`
+#[proc_macro_attribute]
+pub fn alias(_attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(item as syn::Item);
+    let code = quote!(
+          #input
+    );
+
+    TokenStream::from(code)
+}
+
 /// Useless macro to test custom function attributes
 #[proc_macro_attribute]
 pub fn test_handler(_attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
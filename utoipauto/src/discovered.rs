@@ -0,0 +1,26 @@
+/// An item discovered by `discovered!("./src")`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscoveredItem {
+    /// The name of the item, e.g. `get_user`
+    pub name: &'static str,
+    /// The module of the item, e.g. `crate::routes::users`
+    pub module: &'static str,
+    /// The full path of the item, e.g. `crate::routes::users::get_user`
+    pub path: &'static str,
+    pub kind: ItemKind,
+}
+
+/// How the item is used in the documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    /// A function with the #[utoipa::path] attribute
+    Handler,
+    /// A struct deriving or implementing ToSchema
+    Schema,
+    /// A struct deriving or implementing ToResponse
+    Response,
+    /// A struct with the #[utoipauto_modifier] attribute
+    Modifier,
+    /// A manual implementation of ToSchema or ToResponse routed to its own list with `custom_impls`
+    CustomImpl,
+}
@@ -1,5 +1,8 @@
 #![allow(dead_code)] // This code is used in the tests
 
+mod discovered;
+
+pub use discovered::{DiscoveredItem, ItemKind};
 pub use utoipauto_macro::*;
 
 #[cfg(test)]
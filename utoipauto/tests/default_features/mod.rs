@@ -1,3 +1,4 @@
 pub mod controllers;
 pub mod models;
+pub mod modifiers;
 pub mod test;
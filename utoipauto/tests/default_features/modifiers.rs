@@ -0,0 +1,24 @@
+#![allow(dead_code)] // This code is used in the tests
+
+use utoipa::{openapi::OpenApi, Modify};
+use utoipauto_macro::utoipauto_modifier;
+
+// Registered through the marker attribute
+#[utoipauto_modifier]
+pub struct TitleAddon;
+
+impl Modify for TitleAddon {
+    fn modify(&self, openapi: &mut OpenApi) {
+        openapi.info.title = "Modified API".to_string();
+    }
+}
+
+// Registered through the fully qualified marker attribute
+#[utoipauto::utoipauto_modifier]
+pub struct VersionAddon;
+
+impl Modify for VersionAddon {
+    fn modify(&self, openapi: &mut OpenApi) {
+        openapi.info.version = "2.0.0".to_string();
+    }
+}
@@ -128,3 +128,105 @@ pub struct CustomHandlerApiDocs {}
 fn test_custom_handler() {
     assert_eq!(CustomHandlerApiDocs::openapi().paths.paths.len(), 1)
 }
+
+/// Discover modifiers marked with #[utoipauto_modifier]
+#[utoipauto(paths = "./utoipauto/tests/default_features/modifiers.rs")]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct ModifiersApiDocs {}
+
+#[test]
+fn test_marked_modifiers() {
+    let openapi = ModifiersApiDocs::openapi();
+    assert_eq!(openapi.info.title, "Modified API");
+    assert_eq!(openapi.info.version, "2.0.0");
+}
+
+/// Discover in the given order
+#[utoipauto(
+    paths = "./utoipauto/tests/default_features/controllers/controller2.rs, ./utoipauto/tests/default_features/controllers/controller1.rs",
+    order = "alphabetical"
+)]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct AlphabeticalApiDocs {}
+
+#[test]
+fn test_alphabetical_order() {
+    let openapi = AlphabeticalApiDocs::openapi();
+    let paths: Vec<&String> = openapi.paths.paths.keys().collect();
+    assert_eq!(paths, vec!["/route1", "/route3"])
+}
+
+/// Discover with a transformation of the emitted paths
+#[utoipauto(
+    paths = "./utoipauto/tests/default_features/controllers/controller1.rs, ./utoipauto/tests/default_features/controllers/controller2.rs",
+    path_transform = "crate::default_features => super"
+)]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct PathTransformApiDocs {}
+
+#[test]
+fn test_path_transform() {
+    assert_eq!(PathTransformApiDocs::openapi().paths.paths.len(), 2)
+}
+
+/// Discover paths into one struct and components into another
+#[utoipauto(
+    paths = "./utoipauto/tests/default_features/controllers/controller1.rs, ./utoipauto/tests/default_features/models.rs",
+    components_into = "crate::default_features::test::SchemaDoc"
+)]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct PathsOnlyApiDocs {}
+
+pub struct SchemaDoc;
+
+#[test]
+fn test_components_into() {
+    let paths_doc = PathsOnlyApiDocs::openapi();
+    assert_eq!(paths_doc.paths.paths.len(), 1);
+    assert!(paths_doc
+        .components
+        .map_or(true, |components| components.schemas.is_empty()));
+
+    let components = SchemaDoc::openapi().components.expect("no components");
+    assert_eq!(components.schemas.len(), 3);
+    assert_eq!(components.responses.len(), 3);
+}
+
+/// Discover components from a narrower set of paths than the handlers
+#[utoipauto(
+    paths = "./utoipauto/tests/default_features",
+    components_from = "./utoipauto/tests/default_features/controllers"
+)]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct ComponentsFromApiDocs {}
+
+#[test]
+fn test_components_from() {
+    let openapi = ComponentsFromApiDocs::openapi();
+    assert_eq!(openapi.paths.paths.len(), 2);
+    assert!(openapi
+        .components
+        .map_or(true, |components| components.schemas.is_empty()
+            && components.responses.is_empty()));
+}
+
+/// Discover schemas inside a hand-written components list
+#[utoipauto(paths = "./utoipauto/tests/default_features/controllers")]
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Percentage API", version = "1.0.0"),
+    components(schemas(utoipauto::include_schemas!("./utoipauto/tests/default_features/*.rs")))
+)]
+pub struct IncludeSchemasApiDocs {}
+
+#[test]
+fn test_include_schemas() {
+    let openapi = IncludeSchemasApiDocs::openapi();
+    assert_eq!(openapi.paths.paths.len(), 2);
+    assert_eq!(openapi.components.expect("no components").schemas.len(), 3);
+}
@@ -0,0 +1,11 @@
+#![allow(dead_code)] // This code is used in the tests
+
+mod relocated {
+    // Only reachable through the re-export below
+    #[utoipauto::alias("crate::options::aliases::get_user")]
+    #[utoipa::path(get, path = "/user")]
+    pub fn get_user() {}
+}
+
+// utoipa also needs the generated `__path_get_user`
+pub use relocated::*;
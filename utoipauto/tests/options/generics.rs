@@ -0,0 +1,19 @@
+#![allow(dead_code)] // This code is used in the tests
+
+use utoipa::ToSchema;
+
+#[derive(ToSchema)]
+pub struct User {
+    name: String,
+}
+
+#[derive(ToSchema)]
+pub struct Claims<'a> {
+    subject: &'a str,
+}
+
+#[derive(ToSchema)]
+pub struct Paginated<T: ToSchema> {
+    items: Vec<T>,
+    total: usize,
+}
@@ -0,0 +1,31 @@
+#![allow(dead_code)] // This code is used in the tests
+
+use utoipa::OpenApi;
+use utoipauto::utoipauto;
+
+#[utoipa::path(get, path = "/health")]
+pub fn health() {}
+
+pub mod users {
+    #[utoipa::path(get, path = "/users")]
+    pub fn list_users() {}
+
+    #[derive(utoipa::ToSchema)]
+    pub struct User;
+}
+
+pub mod pets {
+    #[utoipa::path(get, path = "/pets")]
+    pub fn list_pets() {}
+
+    pub mod admin {
+        #[utoipa::path(delete, path = "/pets/{id}")]
+        pub fn delete_pet() {}
+    }
+}
+
+/// A doc per module, merged into the root one
+#[utoipauto(paths = "./utoipauto/tests/options/hierarchy.rs", hierarchy = "merge")]
+#[derive(OpenApi)]
+#[openapi(info(title = "Hierarchy API", version = "1.0.0"))]
+pub struct HierarchyApiDocs;
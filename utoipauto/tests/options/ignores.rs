@@ -0,0 +1,20 @@
+#![allow(dead_code)] // This code is used in the tests
+
+use utoipa::ToSchema;
+use utoipauto::utoipa_ignore;
+
+#[utoipa_ignore(reason = "not part of the test builds", cfg(test))]
+#[utoipa::path(get, path = "/hidden")]
+pub fn hidden() {}
+
+#[utoipa_ignore(cfg(not(test)))]
+#[utoipa::path(get, path = "/shown")]
+pub fn shown() {}
+
+#[utoipa_ignore(reason = "internal")]
+#[derive(ToSchema)]
+pub struct InternalModel;
+
+#[utoipa_ignore(reason = "only outside of the test builds", cfg(not(test)))]
+#[derive(ToSchema)]
+pub struct TestModel;
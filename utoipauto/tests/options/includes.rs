@@ -0,0 +1,19 @@
+use utoipauto::utoipa_include;
+
+/// A schema implemented by a macro, which the discovery can't see
+macro_rules! opaque_schema {
+    ($name:ident) => {
+        impl utoipa::PartialSchema for $name {
+            fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+                utoipa::openapi::ObjectBuilder::new().into()
+            }
+        }
+
+        impl utoipa::ToSchema for $name {}
+    };
+}
+
+#[utoipa_include(schema)]
+pub struct Opaque;
+
+opaque_schema!(Opaque);
@@ -0,0 +1,16 @@
+pub mod aliases;
+pub mod generated {
+    #![allow(dead_code)] // This code is used in the tests
+    include!("generated.rs.in");
+}
+pub mod generics;
+pub mod hierarchy;
+pub mod ignores;
+pub mod includes;
+pub mod modifiers;
+pub mod only;
+pub mod reexports;
+pub mod responses;
+pub mod split;
+pub mod sub_docs;
+pub mod test;
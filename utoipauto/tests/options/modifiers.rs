@@ -0,0 +1,35 @@
+#![allow(dead_code)] // This code is used in the tests
+
+use utoipa::{
+    openapi::{tag::Tag, OpenApi},
+    Modify,
+};
+
+// Discovered from its implementation of Modify
+pub struct DiscoveredAddon;
+
+impl Modify for DiscoveredAddon {
+    fn modify(&self, openapi: &mut OpenApi) {
+        openapi.tags.get_or_insert_with(Vec::new).push(Tag::new("discovered"));
+    }
+}
+
+// Listed by hand, it is not added a second time
+pub struct ListedAddon;
+
+impl Modify for ListedAddon {
+    fn modify(&self, openapi: &mut OpenApi) {
+        openapi.tags.get_or_insert_with(Vec::new).push(Tag::new("listed"));
+    }
+}
+
+// Not a unit struct, it can't be listed by its name
+pub struct ConfiguredAddon {
+    pub title: String,
+}
+
+impl Modify for ConfiguredAddon {
+    fn modify(&self, openapi: &mut OpenApi) {
+        openapi.info.title = self.title.clone();
+    }
+}
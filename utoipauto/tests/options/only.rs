@@ -0,0 +1,17 @@
+#![allow(dead_code)] // This code is used in the tests
+
+pub mod api {
+    #[utoipa::path(get, path = "/api/users")]
+    pub fn get_users() {}
+
+    #[derive(utoipa::ToSchema)]
+    pub struct User;
+}
+
+pub mod internal {
+    #[utoipa::path(get, path = "/internal/stats")]
+    pub fn get_stats() {}
+
+    #[derive(utoipa::ToSchema)]
+    pub struct Stats;
+}
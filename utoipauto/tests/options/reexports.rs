@@ -0,0 +1,17 @@
+#![allow(dead_code)] // This code is used in the tests
+
+// Private modules, only reachable through the re-exports below
+mod handlers {
+    #[utoipa::path(get, path = "/user")]
+    pub fn get_user() {}
+}
+
+mod models {
+    #[derive(utoipa::ToSchema)]
+    pub struct User;
+}
+
+// utoipa also needs the generated `__path_get_user`, the function itself is only used by the router
+#[allow(unused_imports)]
+pub use handlers::{__path_get_user as __path_fetch_user, get_user as fetch_user};
+pub use models::User as PublicUser;
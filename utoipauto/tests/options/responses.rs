@@ -0,0 +1,17 @@
+#![allow(dead_code)] // This code is used in the tests
+
+use utoipa::{IntoResponses, ToSchema};
+
+#[derive(ToSchema)]
+pub struct Pet;
+
+#[derive(ToSchema)]
+pub struct PetError;
+
+#[derive(IntoResponses)]
+pub enum PetResponses {
+    #[response(status = 200)]
+    Found(Pet),
+    #[response(status = 404)]
+    NotFound(PetError),
+}
@@ -0,0 +1,23 @@
+#![allow(dead_code)] // This code is used in the tests
+
+pub mod api {
+    pub mod public {
+        use crate::options::split::models::Pet;
+
+        #[utoipa::path(get, path = "/pets", responses((status = 200, body = [Pet])))]
+        pub fn list_pets() {}
+    }
+
+    pub mod admin {
+        #[utoipa::path(delete, path = "/admin/pets/{id}", params(("id" = u64, Path)))]
+        pub fn delete_pet() {}
+
+        #[derive(utoipa::ToSchema)]
+        pub struct AuditLog;
+    }
+}
+
+pub mod models {
+    #[derive(utoipa::ToSchema)]
+    pub struct Pet;
+}
@@ -0,0 +1,28 @@
+#![allow(dead_code)] // This code is used in the tests
+
+#[utoipa::path(get, path = "/health")]
+pub fn health() {}
+
+pub mod users {
+    use utoipa::OpenApi;
+
+    #[utoipa::path(get, path = "/list")]
+    pub fn list_users() {}
+
+    #[derive(utoipa::ToSchema)]
+    pub struct User;
+
+    // Its items are left to it, rather than discovered by the root doc
+    #[derive(OpenApi)]
+    #[openapi(paths(list_users), components(schemas(User)))]
+    pub struct UsersDoc;
+}
+
+pub mod pets {
+    #[utoipa::path(get, path = "/list")]
+    pub fn list_pets() {}
+
+    #[derive(utoipa::OpenApi)]
+    #[openapi(paths(list_pets))]
+    pub struct PetsDoc;
+}
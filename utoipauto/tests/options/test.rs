@@ -0,0 +1,331 @@
+use utoipa::OpenApi;
+
+use utoipauto::utoipauto;
+
+use crate::options::modifiers::ListedAddon;
+
+/// Discover a function under the path of its alias
+#[utoipauto(paths = "./utoipauto/tests/options/aliases.rs")]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct AliasApiDocs {}
+
+#[test]
+fn test_alias() {
+    assert_eq!(AliasApiDocs::openapi().paths.paths.len(), 1)
+}
+
+/// Discover the items under the path of their public re-export
+#[utoipauto(paths = "./utoipauto/tests/options/reexports.rs", prefer_reexports = true)]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct ReexportsApiDocs {}
+
+#[test]
+fn test_prefer_reexports() {
+    let openapi = ReexportsApiDocs::openapi();
+    assert_eq!(openapi.paths.paths.len(), 1);
+    assert_eq!(openapi.components.expect("no components").schemas.len(), 1);
+}
+
+/// Skip the items whose conditional ignore holds
+#[utoipauto(paths = "./utoipauto/tests/options/ignores.rs")]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct IgnoresApiDocs {}
+
+#[test]
+fn test_conditional_ignores() {
+    let openapi = IgnoresApiDocs::openapi();
+    assert_eq!(openapi.paths.paths.keys().collect::<Vec<_>>(), vec!["/shown"]);
+    let schemas = openapi.components.expect("no components").schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["TestModel"]);
+}
+
+/// Register the items marked with #[utoipa_include]
+#[utoipauto(paths = "./utoipauto/tests/options/includes.rs")]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct IncludesApiDocs {}
+
+#[test]
+fn test_includes() {
+    let schemas = IncludesApiDocs::openapi().components.expect("no components").schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["Opaque"]);
+}
+
+/// Only keep the items of the given modules
+#[utoipauto(paths = "./utoipauto/tests/options/only.rs", only = ["crate::options::only::api"])]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct OnlyApiDocs {}
+
+#[test]
+fn test_only() {
+    let openapi = OnlyApiDocs::openapi();
+    assert_eq!(openapi.paths.paths.keys().collect::<Vec<_>>(), vec!["/api/users"]);
+    let schemas = openapi.components.expect("no components").schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["User"]);
+}
+
+/// The structured syntax of the attributes
+#[utoipauto(
+    paths(["./utoipauto/tests/options/only.rs"]),
+    function_attribute = "utoipa",
+    schema_derive = "ToSchema",
+    modules = "crate::options::only::api"
+)]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct StructuredApiDocs {}
+
+#[test]
+fn test_structured_attributes() {
+    let openapi = StructuredApiDocs::openapi();
+    assert_eq!(openapi.paths.paths.keys().collect::<Vec<_>>(), vec!["/api/users"]);
+    let schemas = openapi.components.expect("no components").schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["User"]);
+}
+
+/// Discover the unit structs implementing Modify, except the ones listed by hand
+#[utoipauto(paths = "./utoipauto/tests/options/modifiers.rs")]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"), modifiers(&ListedAddon))]
+pub struct ModifyImplsApiDocs {}
+
+#[test]
+fn test_modify_impls() {
+    let openapi = ModifyImplsApiDocs::openapi();
+    assert_eq!(openapi.info.title, "Percentage API");
+    let mut tags: Vec<String> = openapi
+        .tags
+        .unwrap_or_default()
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect();
+    tags.sort();
+    assert_eq!(tags, vec!["discovered", "listed"]);
+}
+
+/// Nest the other docs under the name of their module
+#[utoipauto(paths = "./utoipauto/tests/options/sub_docs.rs", sub_docs = "nest")]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct SubDocsApiDocs {}
+
+#[test]
+fn test_sub_docs() {
+    let openapi = SubDocsApiDocs::openapi();
+    assert_eq!(
+        openapi.paths.paths.keys().collect::<Vec<_>>(),
+        vec!["/health", "/users/list", "/pets/list"]
+    );
+    let schemas = openapi.components.expect("no components").schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["User"]);
+}
+
+/// Add a handler and a schema the discovery doesn't keep
+#[utoipauto(
+    paths = "./utoipauto/tests/options/only.rs",
+    only = ["crate::options::only::api"],
+    extra_paths = [crate::options::only::internal::get_stats, crate::options::only::api::get_users],
+    extra_schemas = [crate::options::only::internal::Stats]
+)]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct ExtraPathsApiDocs {}
+
+#[test]
+fn test_extra_paths() {
+    let openapi = ExtraPathsApiDocs::openapi();
+    assert_eq!(
+        openapi.paths.paths.keys().collect::<Vec<_>>(),
+        vec!["/api/users", "/internal/stats"]
+    );
+    let schemas = openapi.components.expect("no components").schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["Stats", "User"]);
+}
+
+#[test]
+fn test_hierarchy() {
+    use crate::options::hierarchy::{HierarchyApiDocs, HierarchyApiDocsPets, HierarchyApiDocsUsers};
+
+    let keys = |openapi: utoipa::openapi::OpenApi| openapi.paths.paths.into_keys().collect::<Vec<_>>();
+    assert_eq!(
+        keys(HierarchyApiDocs::openapi()),
+        vec!["/health", "/pets", "/pets/{id}", "/users"]
+    );
+    assert_eq!(keys(HierarchyApiDocsPets::openapi()), vec!["/pets", "/pets/{id}"]);
+    let users = HierarchyApiDocsUsers::openapi();
+    assert_eq!(
+        users
+            .components
+            .as_ref()
+            .expect("no components")
+            .schemas
+            .keys()
+            .collect::<Vec<_>>(),
+        vec!["User"]
+    );
+    assert_eq!(keys(users), vec!["/users"]);
+}
+
+/// Emit the listed instantiations of the generic schemas
+#[utoipauto(paths = "./utoipauto/tests/options/generics.rs", generic_schemas = [Paginated<User>])]
+#[derive(OpenApi)]
+#[openapi(info(title = "Generic API", version = "1.0.0"))]
+pub struct GenericSchemasApiDocs {}
+
+#[test]
+fn test_generic_schemas() {
+    let schemas = GenericSchemasApiDocs::openapi()
+        .components
+        .expect("no components")
+        .schemas;
+    assert_eq!(
+        schemas.keys().collect::<Vec<_>>(),
+        vec!["Claims", "Paginated_User", "User"]
+    );
+}
+
+/// Merge the discovery of several attributes with their own settings
+#[utoipauto(paths = "./utoipauto/tests/options/only.rs", only = ["crate::options::only::api"])]
+#[utoipauto(paths = "./utoipauto/tests/options/only.rs, ./utoipauto/tests/options/hierarchy.rs", only = ["crate::options::hierarchy::pets"])]
+#[derive(OpenApi)]
+#[openapi(info(title = "Merged API", version = "1.0.0"))]
+pub struct MergedApiDocs {}
+
+#[test]
+fn test_merged_attributes() {
+    let openapi = MergedApiDocs::openapi();
+    assert_eq!(
+        openapi.paths.paths.keys().collect::<Vec<_>>(),
+        vec!["/api/users", "/pets", "/pets/{id}"]
+    );
+    let schemas = openapi.components.expect("no components").schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["User"]);
+}
+
+/// Split the discovered items into several derives
+#[utoipauto(paths = "./utoipauto/tests/options/only.rs", chunk_size = 1)]
+#[derive(OpenApi)]
+#[openapi(info(title = "Chunked API", version = "1.0.0"))]
+pub struct ChunkedApiDocs {}
+
+#[test]
+fn test_chunk_size() {
+    let openapi = ChunkedApiDocs::openapi();
+    assert_eq!(openapi.info.title, "Chunked API");
+    assert_eq!(
+        openapi.paths.paths.keys().collect::<Vec<_>>(),
+        vec!["/api/users", "/internal/stats"]
+    );
+    let schemas = openapi.components.expect("no components").schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["Stats", "User"]);
+}
+
+/// Scan the files generated with another extension
+#[utoipauto(paths = "./utoipauto/tests/options", extensions = ["rs.in"])]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct ExtensionsApiDocs {}
+
+#[test]
+fn test_extensions() {
+    let openapi = ExtensionsApiDocs::openapi();
+    assert_eq!(openapi.paths.paths.keys().collect::<Vec<_>>(), vec!["/generated"]);
+}
+
+/// Discover the schemas used as response bodies along the IntoResponses
+#[utoipauto(paths = "./utoipauto/tests/options/responses.rs")]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct ResponsesApiDocs {}
+
+#[test]
+fn test_into_responses_bodies() {
+    let schemas = ResponsesApiDocs::openapi().components.expect("no components").schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["Pet", "PetError"]);
+}
+
+/// Add the discovered items to a documentation built by hand
+#[utoipauto(paths = "./utoipauto/tests/options/only.rs")]
+pub fn build_doc() -> utoipa::openapi::OpenApi {
+    utoipa::openapi::OpenApiBuilder::new()
+        .info(utoipa::openapi::InfoBuilder::new().title("Built API").version("1.0.0"))
+        .build()
+}
+
+#[test]
+fn test_function_doc() {
+    let openapi = build_doc();
+    assert_eq!(openapi.info.title, "Built API");
+    assert_eq!(openapi.paths.paths.len(), 2);
+    assert_eq!(openapi.components.expect("no components").schemas.len(), 2);
+}
+
+static DISCOVERED: &[utoipauto::DiscoveredItem] = utoipauto::discovered!("./utoipauto/tests/options/only.rs");
+
+#[test]
+fn test_discovered() {
+    assert_eq!(DISCOVERED.len(), 4);
+    assert_eq!(
+        DISCOVERED[0],
+        utoipauto::DiscoveredItem {
+            name: "get_users",
+            module: "crate::options::only::api",
+            path: "crate::options::only::api::get_users",
+            kind: utoipauto::ItemKind::Handler,
+        }
+    );
+    let schemas: Vec<&str> = DISCOVERED
+        .iter()
+        .filter(|item| item.kind == utoipauto::ItemKind::Schema)
+        .map(|item| item.name)
+        .collect();
+    assert_eq!(schemas, vec!["User", "Stats"]);
+}
+
+/// Merge and nest the other docs
+#[utoipauto(
+    paths = "./utoipauto/tests/options/aliases.rs",
+    compose = [AliasApiDocs, "/only => OnlyApiDocs"]
+)]
+#[derive(OpenApi)]
+#[openapi(info(title = "Percentage API", version = "1.0.0"))]
+pub struct ComposedApiDocs {}
+
+#[test]
+fn test_compose() {
+    let openapi = ComposedApiDocs::openapi();
+    assert_eq!(
+        openapi.paths.paths.keys().collect::<Vec<_>>(),
+        vec!["/user", "/only/api/users"]
+    );
+    assert_eq!(openapi.components.expect("no components").schemas.len(), 1);
+}
+
+/// Feed several docs from the same scan
+#[utoipauto(paths = "./utoipauto/tests/options/split.rs", only = ["crate::options::split::api::public"])]
+#[derive(OpenApi)]
+#[openapi(info(title = "Public API", version = "1.0.0"))]
+pub struct PublicApiDocs {}
+
+#[utoipauto(paths = "./utoipauto/tests/options/split.rs", only = ["crate::options::split::api::admin"])]
+#[derive(OpenApi)]
+#[openapi(info(title = "Admin API", version = "1.0.0"))]
+pub struct AdminApiDocs {}
+
+#[test]
+fn test_split_docs() {
+    let public = PublicApiDocs::openapi();
+    assert_eq!(public.paths.paths.keys().collect::<Vec<_>>(), vec!["/pets"]);
+    // The schemas used by the handlers are collected by utoipa, even outside of `only`
+    let schemas = public.components.expect("no components").schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["Pet"]);
+
+    let admin = AdminApiDocs::openapi();
+    assert_eq!(admin.paths.paths.keys().collect::<Vec<_>>(), vec!["/admin/pets/{id}"]);
+    let schemas = admin.components.expect("no components").schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["AuditLog"]);
+}
@@ -1 +1,2 @@
 mod default_features;
+mod options;